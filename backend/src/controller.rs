@@ -12,10 +12,12 @@ use cubelib::cube::turn::{ApplyAlgorithm, TransformableMut};
 use cubelib::defs::StepKind;
 use cubelib::solver::df_search::CancelToken;
 use cubelib::solver::solution::Solution;
+use cubelib::solver::stream;
 use cubelib::steps::coord::Coord;
 use cubelib::steps::dr::coords::DRUDEOFBCoord;
 use cubelib::steps::htr::coords::HTRDRUDCoord;
 use cubelib::steps::htr::subsets::DR_SUBSETS;
+use cubelib::steps::dsl;
 use cubelib::steps::solver;
 use cubelib::steps::step::StepConfig;
 use cubelib::steps::tables::PruningTables333;
@@ -37,7 +39,14 @@ pub async fn solve_stream(req: HttpRequest, steps: web::Json<SolverRequest>, app
     }
 
     let SolverRequest{ steps, scramble } = steps.0;
-    let scramble = Algorithm::from_str(scramble.as_str()).unwrap();
+    let Ok(scramble) = Algorithm::from_str(scramble.as_str()) else {
+        return HttpResponse::BadRequest().body(format!("Invalid scramble {scramble}"))
+    };
+    //Steps are built again with the quality used later on, this only reports invalid configs before streaming starts
+    if let Err(err) = solver::build_steps(steps.clone(), app_data.pruning_tables.as_ref()) {
+        debug!("Invalid step config: {err}");
+        return HttpResponse::BadRequest().body(err.to_string())
+    }
     let conn = app_data.pool.get();
 
     match conn {
@@ -53,7 +62,7 @@ pub async fn solve_stream(req: HttpRequest, steps: web::Json<SolverRequest>, app
         }
     }
 
-    info!("Streaming solve request for {scramble}");
+    info!("Streaming solve request for {scramble} with steps {}", dsl::format_steps(&steps));
 
     let mut cube = Cube333::default();
     cube.apply_alg(&scramble);
@@ -105,22 +114,31 @@ pub async fn solve_stream(req: HttpRequest, steps: web::Json<SolverRequest>, app
     HttpResponse::Ok().body(body)
 }
 
-pub fn solve_steps_quality_doubling<'a>(puzzle: Cube333, steps: Vec<StepConfig>, tables: Arc<PruningTables333>, cancel_token: Arc<CancelToken>) -> impl Iterator<Item = Solution> {
+pub fn solve_steps_quality_doubling<'a>(puzzle: Cube333, mut steps: Vec<StepConfig>, tables: Arc<PruningTables333>, cancel_token: Arc<CancelToken>) -> impl Iterator<Item = Solution> {
     let mut prev_len: Option<usize> = None;
-    let t1 = tables.clone();
-    (5..20usize).into_iter()
+    //The steps are built once with quality 1, so their step limits are the per step factors and can be scaled for every quality
+    for x in &mut steps {
+        x.quality = 1;
+        x.step_limit = None;
+    }
+    let steps = solver::build_steps(steps, tables.clone())
+        .map_err(|err| error!("{err}"))
+        .ok();
+    let solutions = (5..20usize).into_iter()
         .map(|q| 2u32.pow(q as u32) as usize)
         .flat_map(move |quality| {
-            let mut steps = steps.clone();
-            for x in &mut steps {
-                x.quality = quality;
-                x.step_limit = None;
-            }
-            let tables = t1.as_ref().clone();
-            let steps = solver::build_steps(steps, &tables).unwrap();
+            let steps: Vec<_> = steps.as_ref()?.iter()
+                .map(|(step, opts)| {
+                    let mut opts = *opts;
+                    opts.step_limit = opts.step_limit.map(|factor| factor * quality);
+                    (step.clone(), opts)
+                })
+                .collect();
             let best = cubelib::solver::solve_steps(puzzle, &steps, cancel_token.as_ref()).next();
             best
-        })
+        });
+    //Different qualities often find the same solution with commuting moves in a different order
+    stream::distinct_canonical_algorithms(solutions, true)
         .filter(move |sol| {
             match prev_len {
                 Some(p) => {
@@ -152,7 +170,7 @@ pub fn solve_steps_quality_doubling<'a>(puzzle: Cube333, steps: Vec<StepConfig>,
                             cube.transform(Transformation333::X);
                             cube.transform(Transformation333::Z);
                         }
-                        let subset_id = tables.as_ref().clone().htr_subset().unwrap().get(HTRDRUDCoord::from(&cube));
+                        let subset_id = tables.htr_subset().unwrap().get(HTRDRUDCoord::from(&cube));
                         let subset = &DR_SUBSETS[subset_id as usize];
                         step.comment = subset.to_string();
                     },
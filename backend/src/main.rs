@@ -4,6 +4,7 @@ use std::time::Duration;
 
 use actix_cors::Cors;
 use actix_web::{App, HttpServer, web};
+use cubelib::steps::registry;
 use cubelib::steps::tables::PruningTables333;
 use log::LevelFilter;
 use moka::sync::Cache;
@@ -33,14 +34,8 @@ async fn main() -> std::io::Result<()> {
     db::init_db(&conn).unwrap();
 
     let mut pruning_tables = PruningTables333::new();
-    pruning_tables.gen_eo();
-    pruning_tables.gen_dr();
-    pruning_tables.gen_htr();
-    pruning_tables.gen_fr();
-    pruning_tables.gen_fr_leave_slice();
-    pruning_tables.gen_fr_finish();
-    pruning_tables.gen_htr_finish();
-    pruning_tables.gen_htr_leave_slice_finish();
+    pruning_tables.gen_all();
+    registry::gen_all_tables(&mut pruning_tables);
 
     let pruning_tables = Arc::new(pruning_tables);
     let debounce_cache = Arc::new(Cache::builder()
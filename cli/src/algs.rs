@@ -0,0 +1,92 @@
+use std::str::FromStr;
+
+use cubelib::algs::Algorithm;
+use cubelib::cube::*;
+use cubelib::cube::turn::{ApplyAlgorithm, Invertible};
+use cubelib::defs::NissSwitchType;
+use cubelib::solver::df_search::CancelToken;
+use cubelib::steps::eo::coords::BadEdgeCount;
+use cubelib::steps::eo::eo_config;
+use cubelib::steps::step::{first_step, DefaultStepOptions};
+use cubelib::steps::tables::PruningTables333;
+use log::error;
+
+use crate::cli::{AlgArgs, MirrorArgs, MirrorAxis};
+use crate::tables;
+
+pub fn analyze(args: &AlgArgs) {
+    let Some(alg) = parse_alg(args.alg.as_str()) else {
+        return;
+    };
+    let mut cube = Cube333::default();
+    cube.apply_alg(&alg);
+
+    let mut tables = tables::pruning_tables();
+    tables.gen_eo();
+    print_analysis(&cube, &tables);
+}
+
+//Prints the bad edges and an optimal EO for every axis. The EO table has to be loaded already
+pub fn print_analysis(cube: &Cube333, tables: &PruningTables333) {
+    let eo_table = tables.eo().unwrap();
+    let cancel_token = CancelToken::default();
+
+    let axes = [
+        ("UD", CubeAxis::UD, cube.edges.count_bad_edges_ud()),
+        ("FB", CubeAxis::FB, cube.edges.count_bad_edges_fb()),
+        ("LR", CubeAxis::LR, cube.edges.count_bad_edges_lr()),
+    ];
+    for (name, axis, bad_edges) in axes {
+        let step = eo_config::eo(eo_table.clone(), vec![axis]);
+        //EO never needs more than 7 moves, so the first solution is an optimal one
+        let search_opts = DefaultStepOptions::new(0, 7, None, None, NissSwitchType::Never, Some(1));
+        let eo = first_step(&step, search_opts, *cube, &cancel_token).next();
+        match eo {
+            Some(solution) => {
+                let eo: Algorithm = solution.into();
+                println!("EO{name}: {bad_edges} bad edges, optimal EO ({}): {eo}", eo.len());
+            },
+            None => println!("EO{name}: {bad_edges} bad edges, no EO found"),
+        }
+    }
+}
+
+pub fn invert(args: &AlgArgs) {
+    let Some(alg) = parse_alg(args.alg.as_str()) else {
+        return;
+    };
+    let mut alg = alg.to_uninverted().reverse();
+    alg.normal_moves = alg.normal_moves.into_iter()
+        .map(|m| m.invert())
+        .collect();
+    println!("{alg}");
+}
+
+pub fn mirror(args: &MirrorArgs) {
+    let Some(mut alg) = parse_alg(args.alg.as_str()) else {
+        return;
+    };
+    alg.mirror(match args.axis {
+        MirrorAxis::UD => CubeAxis::UD,
+        MirrorAxis::FB => CubeAxis::FB,
+        MirrorAxis::LR => CubeAxis::LR,
+    });
+    println!("{alg}");
+}
+
+pub fn simplify(args: &AlgArgs) {
+    let Some(alg) = parse_alg(args.alg.as_str()) else {
+        return;
+    };
+    println!("{}", alg.to_uninverted().canonical());
+}
+
+pub(crate) fn parse_alg(alg: &str) -> Option<Algorithm> {
+    match Algorithm::from_str(alg) {
+        Ok(alg) => Some(alg),
+        Err(_) => {
+            error!("Invalid algorithm {alg}");
+            None
+        }
+    }
+}
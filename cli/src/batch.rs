@@ -0,0 +1,177 @@
+use std::collections::BTreeMap;
+use std::fs::File;
+use std::io::{BufRead, BufReader};
+use std::str::FromStr;
+use std::sync::{mpsc, Mutex};
+use std::thread;
+use std::time::Instant;
+
+use cubelib::algs::Algorithm;
+use cubelib::cube::*;
+use cubelib::cube::turn::ApplyAlgorithm;
+use cubelib::solver::df_search::CancelToken;
+use cubelib::solver::solution::Solution;
+use cubelib::steps::step::{DefaultStepOptions, Step, StepConfig};
+use cubelib::steps::tables::PruningTables333;
+use log::{error, info, warn};
+use serde::Serialize;
+
+use crate::cli::{BatchFormat, SolveArgs};
+
+#[derive(Serialize)]
+struct BatchResult {
+    scramble: String,
+    solution: Option<String>,
+    length: Option<usize>,
+    steps: Vec<BatchStep>,
+    time_ms: u128,
+}
+
+#[derive(Serialize)]
+struct BatchStep {
+    kind: String,
+    length: usize,
+}
+
+//Solves every scramble of the file (or stdin if the path is -) with cli.threads workers. All workers share the same steps.
+//Results are written in the order of the input.
+pub fn solve_batch(cli: &SolveArgs, path: &str, step_configs: &[StepConfig], tables: &PruningTables333, cancel_token: &CancelToken) -> Result<(), String> {
+    let reader: Box<dyn BufRead + Send> = if path == "-" {
+        Box::new(BufReader::new(std::io::stdin()))
+    } else {
+        Box::new(BufReader::new(File::open(path).map_err(|e| format!("Unable to open {path}. {e}"))?))
+    };
+    let scrambles = Mutex::new(reader.lines()
+        .map_while(Result::ok)
+        .map(|line| line.trim().to_string())
+        .filter(|line| !line.is_empty())
+        .enumerate());
+    let steps = crate::solve::build_steps(cli, step_configs.to_vec(), tables).map_err(|e| e.to_string())?;
+    let threads = cli.thread_count();
+    info!("Solving scrambles from {path} with {threads} threads");
+
+    //Scrambles that were cancelled before they were solved send None, so the results after them aren't held back forever
+    let (tx, rx) = mpsc::channel::<(usize, Option<Vec<BatchResult>>)>();
+    thread::scope(|s| {
+        for _ in 0..threads {
+            let tx = tx.clone();
+            let scrambles = &scrambles;
+            let steps = &steps;
+            s.spawn(move || {
+                loop {
+                    let next = scrambles.lock().unwrap().next();
+                    let Some((id, scramble)) = next else {
+                        return;
+                    };
+                    if cancel_token.is_cancelled() {
+                        let _ = tx.send((id, None));
+                        return;
+                    }
+                    let results = solve_scramble(cli, scramble, steps, cancel_token);
+                    //A search that was cancelled may have stopped before finding all solutions
+                    let results = if cancel_token.is_cancelled() { None } else { Some(results) };
+                    if tx.send((id, results)).is_err() {
+                        return;
+                    }
+                }
+            });
+        }
+        drop(tx);
+
+        if let BatchFormat::Csv = cli.batch_format {
+            println!("scramble,solution,length,steps,time_ms");
+        }
+        //Workers finish out of order, so we hold back results until all previous scrambles are written
+        let mut pending = BTreeMap::new();
+        let mut next_id = 0;
+        let mut unsolved = 0;
+        for (id, results) in rx {
+            pending.insert(id, results);
+            while let Some(results) = pending.remove(&next_id) {
+                print_results(cli, results, &mut unsolved);
+                next_id += 1;
+            }
+        }
+        //Only left over if a worker stopped without reporting a scramble
+        for (_, results) in pending {
+            print_results(cli, results, &mut unsolved);
+        }
+        if unsolved > 0 {
+            warn!("Cancelled, {unsolved} scrambles not solved");
+        } else if cancel_token.is_cancelled() {
+            warn!("Cancelled, the remaining scrambles were skipped");
+        }
+    });
+    Ok(())
+}
+
+fn print_results(cli: &SolveArgs, results: Option<Vec<BatchResult>>, unsolved: &mut usize) {
+    match results {
+        Some(results) => for result in results {
+            println!("{}", format_result(&cli.batch_format, &result));
+        },
+        None => *unsolved += 1,
+    }
+}
+
+fn solve_scramble<'a>(cli: &'a SolveArgs, scramble: String, steps: &'a Vec<(Step<'a>, DefaultStepOptions)>, cancel_token: &'a CancelToken) -> Vec<BatchResult> {
+    let time = Instant::now();
+    let alg = match Algorithm::from_str(scramble.as_str()) {
+        Ok(alg) => alg,
+        Err(_) => {
+            error!("Invalid scramble {scramble}");
+            return vec![BatchResult { scramble, solution: None, length: None, steps: vec![], time_ms: 0 }];
+        }
+    };
+    let mut cube = Cube333::default();
+    cube.apply_alg(&alg);
+
+    //Scorers are not Send, so every scramble gets its own. The options were already validated before starting the workers
+    let scorer = cli.parse_scorer().unwrap_or(None);
+    let solutions: Vec<Solution> = crate::solve::find_solutions(cli, cube, steps, scorer, cancel_token).collect();
+    let time_ms = time.elapsed().as_millis();
+    if solutions.is_empty() {
+        return vec![BatchResult { scramble, solution: None, length: None, steps: vec![], time_ms }];
+    }
+    solutions.into_iter()
+        .map(|solution| {
+            let steps = solution.clone().compact().steps.into_iter()
+                .map(|step| BatchStep { kind: step.kind.to_string(), length: step.alg.len() })
+                .collect();
+            let alg: Algorithm = solution.into();
+            BatchResult {
+                scramble: scramble.clone(),
+                solution: Some(alg.to_string()),
+                length: Some(alg.len()),
+                steps,
+                time_ms,
+            }
+        })
+        .collect()
+}
+
+fn format_result(format: &BatchFormat, result: &BatchResult) -> String {
+    match format {
+        BatchFormat::Ndjson => serde_json::to_string(result).unwrap(),
+        BatchFormat::Csv => {
+            let steps = result.steps.iter()
+                .map(|step| format!("{}:{}", step.kind, step.length))
+                .collect::<Vec<String>>()
+                .join(" ");
+            format!("{},{},{},{},{}",
+                    csv_field(result.scramble.as_str()),
+                    csv_field(result.solution.as_deref().unwrap_or("")),
+                    result.length.map(|l| l.to_string()).unwrap_or_default(),
+                    csv_field(steps.as_str()),
+                    result.time_ms)
+        }
+    }
+}
+
+fn csv_field(value: &str) -> String {
+    if value.contains(',') || value.contains('"') {
+        format!("\"{}\"", value.replace('"', "\"\""))
+    } else {
+        value.to_string()
+    }
+}
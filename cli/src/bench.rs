@@ -0,0 +1,85 @@
+use std::time::Instant;
+
+use cubelib::algs::Algorithm;
+use cubelib::cube::*;
+use cubelib::cube::turn::ApplyAlgorithm;
+use cubelib::solver::df_search::CancelToken;
+use cubelib::steps::solver;
+use log::error;
+use rand::prelude::*;
+
+use crate::cli::BenchArgs;
+use crate::{solve, tables};
+
+pub fn bench(args: &BenchArgs) {
+    let step_configs = match args.solve.parse_step_configs() {
+        Ok(val) => val,
+        Err(e) => {
+            error!("Unable to parse steps config. {e}");
+            return;
+        }
+    };
+    //Table generation is not part of the benchmark
+    let mut tables = tables::pruning_tables();
+    solver::gen_tables(&step_configs, &mut tables);
+    let steps = match solve::build_steps(&args.solve, step_configs, &tables) {
+        Ok(val) => val,
+        Err(e) => {
+            error!("{e}");
+            return;
+        }
+    };
+
+    let mut rng = match args.seed {
+        Some(seed) => StdRng::seed_from_u64(seed),
+        None => StdRng::from_rng(thread_rng()),
+    };
+    let cancel_token = CancelToken::default();
+    let mut total_ms = 0.0;
+    let mut total_length = 0;
+    let mut solved = 0;
+    for i in 0..args.count {
+        let scramble = random_scramble(&mut rng, args.scramble_length);
+        let mut cube = Cube333::default();
+        cube.apply_alg(&scramble);
+
+        let time = Instant::now();
+        let best = solve::find_solutions(&args.solve, cube, &steps, args.solve.parse_scorer().unwrap_or(None), &cancel_token)
+            .map(|solution| solution.len())
+            .min();
+        let ms = time.elapsed().as_secs_f64() * 1000.0;
+        total_ms += ms;
+        match best {
+            Some(length) => {
+                solved += 1;
+                total_length += length;
+                println!("{:>4} {ms:>10.1}ms {length:>3} moves  {scramble}", i + 1);
+            },
+            None => println!("{:>4} {ms:>10.1}ms  no solution  {scramble}", i + 1),
+        }
+    }
+
+    println!();
+    println!("Solved {solved}/{} scrambles in {total_ms:.1}ms", args.count);
+    if args.count > 0 {
+        println!("Average time: {:.1}ms", total_ms / args.count as f64);
+    }
+    if solved > 0 {
+        println!("Average length: {:.2}", total_length as f64 / solved as f64);
+    }
+}
+
+//Random moves that never turn a face that was already turned since the last move on a different axis, so nothing cancels
+fn random_scramble(rng: &mut StdRng, length: usize) -> Algorithm {
+    let mut moves: Vec<Turn333> = vec![];
+    while moves.len() < length {
+        let m = Turn333::ALL[rng.gen_range(0..Turn333::ALL.len())];
+        let cancels = moves.iter().rev()
+            .take_while(|p| p.face == m.face || p.face == m.face.opposite())
+            .any(|p| p.face == m.face);
+        if !cancels {
+            moves.push(m);
+        }
+    }
+    Algorithm { normal_moves: moves, inverse_moves: vec![] }
+}
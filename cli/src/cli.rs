@@ -1,9 +1,10 @@
 use std::collections::HashMap;
 use std::str::FromStr;
-use clap::{Parser, ValueEnum};
-use regex::Regex;
+use clap::{Args, Parser, Subcommand, ValueEnum};
 use cubelib::defs::*;
-use cubelib::steps::step::{StepConfig};
+use cubelib::solver::scoring::{InverseEndPenalty, LengthScorer, LongStepPenalty, NissSwitchPenalty, SolutionScorer, StepWeightScorer};
+use cubelib::steps::{dsl, presets};
+use cubelib::steps::step::StepConfig;
 use serde::Deserialize;
 use log::LevelFilter;
 
@@ -11,10 +12,87 @@ use log::LevelFilter;
 #[command(name = "Cubelib")]
 #[command(author = "Jonas Balsfulland <cubelib@joba.me>")]
 #[command(version = "1.2")]
+//Running cubelib without a subcommand is a shortcut for cubelib solve
+#[command(args_conflicts_with_subcommands = true, subcommand_negates_reqs = true)]
 pub struct Cli {
-    #[arg(short, long = "log", help = "Log level")]
+    #[arg(short, long = "log", value_enum, default_value_t = LogLevel::Warn, global = true, help = "Log level")]
     pub log: LogLevel,
-    #[arg(short, long = "format", help="Solution output format")]
+    #[command(subcommand)]
+    pub command: Option<Command>,
+    #[command(flatten)]
+    pub solve: SolveArgs,
+}
+
+#[derive(Subcommand)]
+pub enum Command {
+    #[command(about = "Solve a scramble. This is the default if no subcommand is given")]
+    Solve(SolveArgs),
+    #[command(about = "Show the number of bad edges and the optimal EO for every axis")]
+    Analyze(AlgArgs),
+    #[command(about = "Print the inverse of an algorithm")]
+    Invert(AlgArgs),
+    #[command(about = "Mirror an algorithm")]
+    Mirror(MirrorArgs),
+    #[command(about = "Un-invert an algorithm and remove cancelling moves")]
+    Simplify(AlgArgs),
+    #[command(about = "Manage the pruning tables in ~/.cubelib/tables or $CUBELIB_TABLES_DIR")]
+    Tables {
+        #[command(subcommand)]
+        command: TablesCommand,
+    },
+    #[command(about = "Measure the solver performance on random scrambles")]
+    Bench(BenchArgs),
+    #[command(about = "Interactively explore a scramble step by step. The solve options are used for every step")]
+    Repl(SolveArgs),
+}
+
+#[derive(Args)]
+pub struct AlgArgs {
+    pub alg: String,
+}
+
+#[derive(Args)]
+pub struct MirrorArgs {
+    #[arg(short = 'a', long = "axis", value_enum, default_value_t = MirrorAxis::LR, help = "The axis of the mirror plane. The default lr mirrors left and right")]
+    pub axis: MirrorAxis,
+    pub alg: String,
+}
+
+#[derive(Subcommand)]
+pub enum TablesCommand {
+    #[command(about = "Generate all missing tables")]
+    Generate {
+        #[arg(long = "compress", help = "Compress the generated tables with LZ4. Compressed tables are smaller, but every process has to keep its own copy in memory")]
+        compress: bool,
+    },
+    #[command(about = "List the tables on disk")]
+    List,
+    #[command(about = "Check that all tables on disk can be loaded")]
+    Verify,
+    #[command(about = "Delete tables that are outdated, corrupt or left over from an interrupted save")]
+    Prune,
+    #[command(about = "Show the size, depth distribution and God's number of the tables")]
+    Info {
+        #[arg(help = "Tables to show. Shows every table by default")]
+        keys: Vec<String>,
+    },
+}
+
+#[derive(Args)]
+pub struct BenchArgs {
+    #[arg(short = 'c', long = "count", default_value_t = 20, help = "Number of scrambles")]
+    pub count: usize,
+    #[arg(long = "seed", help = "Seed for the random scrambles. Random by default")]
+    pub seed: Option<u64>,
+    #[arg(long = "scramble-length", default_value_t = 25, help = "Number of random moves per scramble")]
+    pub scramble_length: usize,
+    #[command(flatten)]
+    pub solve: SolveArgs,
+}
+
+#[derive(Args, Clone)]
+pub struct SolveArgs {
+    #[arg(short, long = "format", value_enum, default_value_t = SolutionFormat::Detailed, help="Solution output format")]
     pub format: SolutionFormat,
     #[arg(short = 'a', long = "all", default_value_t = false, help = "Print solutions that would otherwise get filtered out. E.g. an EO ending in F'")]
     pub all_solutions: bool,
@@ -26,11 +104,40 @@ pub struct Cli {
     pub niss: bool,
     #[arg(short = 'n', help = "The number of solutions returned. By default 1 unless this option or --max is set")]
     pub solution_count: Option<usize>,
+    #[arg(long = "max-niss-switches", help = "Maximum number of NISS switches in a solution. Starting on the inverse counts as a switch")]
+    pub max_niss_switches: Option<usize>,
     #[arg(short = 'q', long = "quality", default_value_t = 100, help = "Influences the maximum number of solutions calculated per step. Set to 0 for infinite quality")]
     pub quality: usize,
-    #[arg(long = "steps", short = 's', default_value = "EO > RZP > DR[triggers=R,RUR,RU'R,RU2R] > HTR > FIN", help = "List of steps to perform")]
+    //Every solution of the time limited search is shorter than the previous one, so ranking and deduplicating doesn't apply
+    #[arg(short = 't', long = "time", conflicts_with_all = ["solution_count", "niss_penalty", "inverse_penalty", "long_step", "weights", "candidates", "dedup"], help = "Search for the given number of seconds, printing every solution that is shorter than the previous one. Ignores quality and can't be combined with ranking or --dedup")]
+    pub time: Option<u64>,
+    #[arg(long = "niss-penalty", help = "Rank solutions by score instead of length, adding this penalty for every NISS switch")]
+    pub niss_penalty: Option<f64>,
+    #[arg(long = "inverse-penalty", help = "Rank solutions by score instead of length, adding this penalty if the solution ends on the inverse")]
+    pub inverse_penalty: Option<f64>,
+    #[arg(long = "long-step", help = "Rank solutions by score instead of length, penalizing every move a single step uses beyond this length")]
+    pub long_step: Option<usize>,
+    #[arg(long = "long-step-penalty", default_value_t = 1.0, help = "The penalty per move for --long-step")]
+    pub long_step_penalty: f64,
+    #[arg(long = "weights", help = "Rank solutions by a weighted sum of their step lengths instead of length. E.g. 'EO=1,DR=1.5'. Unlisted steps have a weight of 1")]
+    pub weights: Option<String>,
+    #[arg(long = "candidates", default_value_t = 1000, help = "The number of solutions considered when ranking by score")]
+    pub candidates: usize,
+    #[arg(long = "dedup", value_enum, default_value_t = DedupMode::Canonical, help = "How duplicate solutions are detected")]
+    pub dedup: DedupMode,
+    #[arg(long = "steps", short = 's', default_value = presets::DEFAULT_STEPS, help = "List of steps to perform")]
     pub steps: String,
-    pub scramble: String,
+    #[arg(long = "preset", conflicts_with = "steps", help = "Use a named list of steps instead of --steps. One of default, eo-dr-only, htr-finish, leave-slice or a preset from ~/.cubelib/config.toml")]
+    pub preset: Option<String>,
+    #[arg(long = "batch", conflicts_with = "time", help = "Solve all scrambles of the given file, one per line. Use - to read from stdin")]
+    pub batch: Option<String>,
+    #[arg(long = "threads", short = 'j', help = "Number of worker threads in batch mode and with --parallel. Defaults to the number of CPUs")]
+    pub threads: Option<usize>,
+    #[arg(long = "parallel", short = 'P', default_value_t = false, conflicts_with = "batch", help = "Search the variants of every step on multiple threads. The solutions are the same as without this option. Not used with --time, and not available in batch mode, which already solves one scramble per thread")]
+    pub parallel: bool,
+    #[arg(long = "batch-format", value_enum, default_value_t = BatchFormat::Csv, help = "Output format of batch mode")]
+    pub batch_format: BatchFormat,
+    pub scramble: Option<String>,
 }
 
 #[derive(ValueEnum, Clone, Default, Debug, Deserialize)]
@@ -62,12 +169,36 @@ pub enum SolutionFormat {
     #[default]
     Detailed,
     Compact,
-    Plain
+    Plain,
+    Json,
+    Ndjson
+}
+
+#[derive(ValueEnum, Clone, Copy, Debug)]
+pub enum MirrorAxis {
+    UD,
+    FB,
+    LR,
 }
 
+#[derive(ValueEnum, Clone, Default, Debug, Deserialize)]
+#[serde(rename_all = "lowercase")]
+pub enum BatchFormat {
+    #[default]
+    Csv,
+    Ndjson
+}
 
+#[derive(ValueEnum, Clone, Default, Debug, Deserialize)]
+#[serde(rename_all = "lowercase")]
+pub enum DedupMode {
+    Exact, // Only identical algorithms
+    #[default]
+    Canonical, // Algorithms that are equal after sorting commuting moves and cancelling
+    Final, // Like canonical, but compares the final algorithm, ignoring which side a move was done on
+}
 
-impl Cli {
+impl SolveArgs {
 
     fn get_default_niss_type(&self) -> Option<NissSwitchType> {
         if self.niss {
@@ -77,80 +208,46 @@ impl Cli {
         }
     }
 
-    pub fn parse_step_configs(&self) -> Result<Vec<StepConfig>, String> {
-        let step_name_regex = Regex::new("[A-Za-z0-9_-]").unwrap();
-        let default_niss_type = self.get_default_niss_type();
-        self.steps.split(">")
-            .map(|step| step.trim())
-            .map(|step| {
-                let param_start = step.find("[");
-                if param_start.is_none() {
-                    if !step_name_regex.is_match(step) {
-                        return Err(format!("Invalid step name {}", step));
-                    }
-                    return Ok(StepConfig {
-                        kind: StepKind::from_str(step)?,
-                        substeps: None,
-                        min: None,
-                        max: None,
-                        absolute_min: None,
-                        absolute_max: None,
-                        niss: default_niss_type,
-                        step_limit: None,
-                        quality: self.quality,
-                        params: HashMap::new()
-                    });
-                } else {
-                    if !step.ends_with("]") {
-                        return Err(format!("Expected step parameters to end with ] {}", step));
-                    }
-                    let param_start = param_start.unwrap();
-                    let name = &step[0..param_start];
-                    let mut step_prototype = StepConfig {
-                        kind: StepKind::from_str(name)?,
-                        substeps: None,
-                        min: None,
-                        max: None,
-                        absolute_min: None,
-                        absolute_max: None,
-                        niss: default_niss_type,
-                        step_limit: None,
-                        quality: self.quality,
-                        params: HashMap::new()
-                    };
-                    let params: Vec<&str> = (&step[(param_start + 1)..(step.len() - 1)]).split(";").collect();
-                    for param in params {
-                        if param.contains("=") {
-                            let parts: Vec<&str> = param.split("=").collect();
-                            if parts.len() != 2 {
-                                return Err(format!("Invalid param format {}", param));
-                            }
-                            match parts[0] {
-                                "limit" => step_prototype.step_limit = Some(usize::from_str(parts[1]).map_err(|x| format!("Unable to parse value '{}' for count. '{x}'", parts[1]))?),
-                                key @ "min" | key @ "min-rel" => step_prototype.min = Some(u8::from_str(parts[1]).map_err(|x| format!("Unable to parse value '{}' for {key}. '{x}'", parts[1]))?),
-                                key @ "max" | key @ "max-rel" => step_prototype.max = Some(u8::from_str(parts[1]).map_err(|x| format!("Unable to parse value '{}' for {key}. '{x}'", parts[1]))?),
-                                "min-abs" => step_prototype.absolute_min = Some(u8::from_str(parts[1]).map_err(|x| format!("Unable to parse value '{}' for min-abs. '{x}'", parts[1]))?),
-                                "max-abs" => step_prototype.absolute_max = Some(u8::from_str(parts[1]).map_err(|x| format!("Unable to parse value '{}' for max-abs. '{x}'", parts[1]))?),
-                                "niss" => step_prototype.niss = Some(match parts[1] {
-                                    "always" | "true" => NissSwitchType::Always,
-                                    "before" => NissSwitchType::Before,
-                                    "none" | "never" | "false" => NissSwitchType::Never,
-                                    x => Err(format!("Invalid NISS type {x}. Expected one of 'always', 'before', 'none'"))?
-                                }),
-                                key => {
-                                    step_prototype.params.insert(key.to_string(), parts[1].to_string());
-                                }
-                            }
-                        } else {
-                            step_prototype.substeps = Some(step_prototype.substeps.map_or(vec![param.to_string()], |mut v|{
-                                v.push(param.to_string());
-                                v
-                            }));
-                        }
+    pub fn thread_count(&self) -> usize {
+        self.threads.unwrap_or_else(|| std::thread::available_parallelism().map(|n| n.get()).unwrap_or(1)).max(1)
+    }
+
+    //None if no ranking option is set, in which case solutions are ranked by length
+    pub fn parse_scorer(&self) -> Result<Option<Vec<Box<dyn SolutionScorer>>>, String> {
+        let mut scorers: Vec<Box<dyn SolutionScorer>> = vec![];
+        match &self.weights {
+            Some(weights) => {
+                let mut step_weights = HashMap::new();
+                for weight in weights.split(",").map(|w| w.trim()).filter(|w| !w.is_empty()) {
+                    let parts: Vec<&str> = weight.split("=").collect();
+                    if parts.len() != 2 {
+                        return Err(format!("Invalid weight format {weight}. Expected <step>=<weight>"));
                     }
-                    Ok(step_prototype)
+                    let kind = StepKind::from_str(parts[0].trim())?;
+                    let value = f64::from_str(parts[1].trim()).map_err(|x| format!("Unable to parse weight '{}' for {kind}. '{x}'", parts[1]))?;
+                    step_weights.insert(kind, value);
                 }
-            })
-            .collect()
+                scorers.push(Box::new(StepWeightScorer::new(step_weights)));
+            },
+            None => scorers.push(Box::new(LengthScorer)),
+        }
+        if let Some(penalty) = self.niss_penalty {
+            scorers.push(Box::new(NissSwitchPenalty(penalty)));
+        }
+        if let Some(penalty) = self.inverse_penalty {
+            scorers.push(Box::new(InverseEndPenalty(penalty)));
+        }
+        if let Some(threshold) = self.long_step {
+            scorers.push(Box::new(LongStepPenalty { threshold, penalty: self.long_step_penalty }));
+        }
+        if scorers.len() == 1 && self.weights.is_none() {
+            Ok(None)
+        } else {
+            Ok(Some(scorers))
+        }
+    }
+
+    pub fn parse_step_configs(&self) -> Result<Vec<StepConfig>, cubelib::Error> {
+        dsl::parse_steps_with_defaults(self.steps.as_str(), self.get_default_niss_type(), self.quality)
     }
 }
\ No newline at end of file
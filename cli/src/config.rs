@@ -0,0 +1,97 @@
+use std::collections::HashMap;
+use std::fs;
+
+use clap::ArgMatches;
+use clap::parser::ValueSource;
+use cubelib::steps::presets;
+use home::home_dir;
+use serde::Deserialize;
+
+use crate::cli::{SolutionFormat, SolveArgs};
+
+//Contents of ~/.cubelib/config.toml. Everything is optional, e.g.
+//
+//[defaults]
+//quality = 1000
+//niss = true
+//preset = "fast"
+//
+//[presets]
+//fast = "EO > DR > HTR > FIN"
+#[derive(Deserialize, Default)]
+#[serde(default, deny_unknown_fields)]
+pub struct Config {
+    pub defaults: Defaults,
+    pub presets: HashMap<String, String>,
+}
+
+//Used for every option that is not set on the command line
+#[derive(Deserialize, Default)]
+#[serde(default, deny_unknown_fields)]
+pub struct Defaults {
+    pub quality: Option<usize>,
+    pub niss: Option<bool>,
+    pub min: Option<usize>,
+    pub max: Option<usize>,
+    pub format: Option<SolutionFormat>,
+    pub preset: Option<String>,
+}
+
+impl Config {
+    pub fn load() -> Result<Config, String> {
+        let Some(mut path) = home_dir() else {
+            return Ok(Config::default());
+        };
+        path.push(".cubelib");
+        path.push("config.toml");
+        match fs::read_to_string(&path) {
+            Ok(content) => toml::from_str(content.as_str()).map_err(|e| format!("Invalid config file {}. {e}", path.display())),
+            Err(_) => Ok(Config::default()),
+        }
+    }
+
+    //Presets from the config file take precedence over the built-in ones
+    pub fn preset(&self, name: &str) -> Option<String> {
+        self.presets.get(name)
+            .cloned()
+            .or_else(|| presets::preset(name).map(|steps| steps.to_string()))
+    }
+
+    //Fills in the defaults for all options that were not given on the command line. The matches have to be the ones args was parsed from
+    pub fn apply(&self, args: &mut SolveArgs, matches: &ArgMatches) -> Result<(), String> {
+        let from_cli = |id: &str| matches.value_source(id) == Some(ValueSource::CommandLine);
+        if !from_cli("quality") {
+            if let Some(quality) = self.defaults.quality {
+                args.quality = quality;
+            }
+        }
+        if !from_cli("niss") {
+            if let Some(niss) = self.defaults.niss {
+                args.niss = niss;
+            }
+        }
+        if !from_cli("min") {
+            if let Some(min) = self.defaults.min {
+                args.min = min;
+            }
+        }
+        if args.max.is_none() {
+            args.max = self.defaults.max;
+        }
+        if !from_cli("format") {
+            if let Some(format) = self.defaults.format.clone() {
+                args.format = format;
+            }
+        }
+
+        let preset = match &args.preset {
+            Some(preset) => Some(preset),
+            None if !from_cli("steps") => self.defaults.preset.as_ref(),
+            None => None,
+        };
+        if let Some(name) = preset {
+            args.steps = self.preset(name).ok_or(format!("Unknown preset {name}"))?;
+        }
+        Ok(())
+    }
+}
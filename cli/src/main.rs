@@ -1,98 +1,58 @@
-use std::str::FromStr;
-use std::time::Instant;
-
-use clap::Parser;
-use cubelib::algs::Algorithm;
-use cubelib::cube::*;
-use cubelib::defs::StepKind;
-
-use cubelib::solver::stream;
-use cubelib::solver::df_search::CancelToken;
-use cubelib::solver::solution::Solution;
-use cubelib::steps::{eo, solver};
-use cubelib::steps::tables::PruningTables333;
-use log::{error, info};
+use clap::{CommandFactory, FromArgMatches};
+use log::error;
 use simple_logger::SimpleLogger;
-use cubelib::cube::turn::ApplyAlgorithm;
 
-use crate::cli::{Cli, SolutionFormat};
+use crate::cli::{Cli, Command, SolveArgs};
+use crate::config::Config;
 
+mod algs;
+mod batch;
+mod bench;
 mod cli;
+mod config;
+mod output;
+mod repl;
+mod solve;
+mod tables;
 
 fn main() {
-    let cli: Cli = Cli::parse();
+    //We keep the matches around to tell options given on the command line apart from defaults
+    let matches = Cli::command().get_matches();
+    let mut cli = Cli::from_arg_matches(&matches).unwrap_or_else(|e| e.exit());
     SimpleLogger::new()
         .with_level(cli.log.to_level_filter())
         .init()
         .unwrap();
 
-    let scramble = Algorithm::from_str(cli.scramble.as_str()).expect("Invalid scramble {}");
-    let mut cube = Cube333::default();
-    cube.apply_alg(&scramble);
-
-    let steps = cli.parse_step_configs();
-    let mut tables = PruningTables333::new();
-
-    let steps = if let Err(e) = steps {
-        error!("Unable to parse steps config. {e}");
-        return;
-    } else if let Ok(val) = steps {
-        solver::gen_tables(&val, &mut tables);
-        solver::build_steps(val, &tables)
-    } else {
-        unreachable!()
+    let config = match Config::load() {
+        Ok(config) => config,
+        Err(e) => {
+            error!("{e}");
+            return;
+        }
     };
-
-    let steps = if let Err(e) = steps {
-        error!("{e}");
-        return;
-    } else if let Ok(val) = steps {
-        val
-    } else {
-        unreachable!()
+    let apply_config = |args: &mut SolveArgs, name: Option<&str>| {
+        let matches = name.and_then(|name| matches.subcommand_matches(name)).unwrap_or(&matches);
+        config.apply(args, matches).inspect_err(|e| error!("{e}")).is_ok()
     };
 
-
-    let cancel_token = CancelToken::default();
-    let solutions = cubelib::solver::solve_steps(cube, &steps, &cancel_token);
-
-    info!("Generating solutions\n");
-    let time = Instant::now();
-
-    let mut solutions: Box<dyn Iterator<Item = Solution>> = Box::new(solutions
-        .skip_while(|alg| alg.len() < cli.min)
-        .take_while(|alg| cli.max.map_or(true, |max| alg.len() <= max)));
-
-    // For e.g. FR the direction of the last move always matters, so we can't filter if we're doing FR
-    let can_filter_last_move = steps.last().map(|(s, _)| s.kind() != StepKind::FR && s.kind() != StepKind::FIN).unwrap_or(true);
-    if !cli.all_solutions && can_filter_last_move {
-        solutions = Box::new(solutions
-            .filter(|alg| eo::eo_config::filter_eo_last_moves_pure(&alg.clone().into())));
-    }
-
-    //We already generate a mostly duplicate free iterator, but sometimes the same solution is valid for different stages and that can cause duplicates.
-    let solutions = stream::distinct_algorithms(solutions);
-
-    let mut solutions: Box<dyn Iterator<Item = Solution>> = Box::new(solutions);
-
-    if cli.max.is_none() || cli.solution_count.is_some() {
-        solutions = Box::new(solutions
-            .take(cli.solution_count.unwrap_or(1)))
+    match cli.command {
+        None => if apply_config(&mut cli.solve, None) {
+            solve::solve(&cli.solve)
+        },
+        Some(Command::Solve(mut args)) => if apply_config(&mut args, Some("solve")) {
+            solve::solve(&args)
+        },
+        Some(Command::Analyze(args)) => algs::analyze(&args),
+        Some(Command::Invert(args)) => algs::invert(&args),
+        Some(Command::Mirror(args)) => algs::mirror(&args),
+        Some(Command::Simplify(args)) => algs::simplify(&args),
+        Some(Command::Tables { command }) => tables::run(&command),
+        Some(Command::Bench(mut args)) => if apply_config(&mut args.solve, Some("bench")) {
+            bench::bench(&args)
+        },
+        Some(Command::Repl(mut args)) => if apply_config(&mut args, Some("repl")) {
+            repl::run(&args)
+        },
     }
-
-    //The iterator is always sorted, so this just prints the shortest solutions
-    for solution in solutions {
-        match cli.format {
-            SolutionFormat::Plain =>
-                println!("{}", Into::<Algorithm>::into(solution)),
-            SolutionFormat::Compact => {
-                let alg = Into::<Algorithm>::into(solution);
-                println!("{alg} ({})", alg.len());
-            },
-            SolutionFormat::Detailed =>
-                println!("{}", solution)
-        }
-    }
-
-    info!("Took {}ms", time.elapsed().as_millis());
 }
@@ -0,0 +1,68 @@
+use cubelib::algs::Algorithm;
+use cubelib::solver::solution::Solution;
+use serde::Serialize;
+
+use crate::cli::SolutionFormat;
+
+//Schema of the json and ndjson formats. Fields may be added, but existing ones keep their name and meaning.
+//The steps are serialized as they are by the library, the other fields are derived from them.
+#[derive(Serialize)]
+pub struct SolutionOutput {
+    //The final algorithm with all inverse moves un-inverted
+    pub solution: String,
+    pub length: usize,
+    pub niss_switches: usize,
+    #[serde(flatten)]
+    pub details: Solution,
+}
+
+impl From<Solution> for SolutionOutput {
+    fn from(solution: Solution) -> Self {
+        let alg: Algorithm = solution.clone().into();
+        SolutionOutput {
+            length: alg.len(),
+            solution: alg.to_uninverted().to_string(),
+            niss_switches: solution.niss_switches(),
+            details: solution,
+        }
+    }
+}
+
+//Json output is a single array, so solutions are collected until finish is called. All other formats print immediately.
+pub struct SolutionPrinter {
+    format: SolutionFormat,
+    collected: Vec<SolutionOutput>,
+}
+
+impl SolutionPrinter {
+    pub fn new(format: SolutionFormat) -> Self {
+        SolutionPrinter { format, collected: vec![] }
+    }
+
+    pub fn is_structured(&self) -> bool {
+        matches!(self.format, SolutionFormat::Json | SolutionFormat::Ndjson)
+    }
+
+    pub fn print(&mut self, solution: Solution) {
+        match self.format {
+            SolutionFormat::Plain =>
+                println!("{}", Into::<Algorithm>::into(solution)),
+            SolutionFormat::Compact => {
+                let alg = Into::<Algorithm>::into(solution);
+                println!("{alg} ({})", alg.len());
+            },
+            SolutionFormat::Detailed =>
+                println!("{}", solution),
+            SolutionFormat::Json =>
+                self.collected.push(solution.into()),
+            SolutionFormat::Ndjson =>
+                println!("{}", serde_json::to_string(&SolutionOutput::from(solution)).unwrap()),
+        }
+    }
+
+    pub fn finish(self) {
+        if let SolutionFormat::Json = self.format {
+            println!("{}", serde_json::to_string_pretty(&self.collected).unwrap());
+        }
+    }
+}
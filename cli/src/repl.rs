@@ -0,0 +1,221 @@
+use std::io::{stdin, stdout, BufRead, Write};
+
+use cubelib::algs::Algorithm;
+use cubelib::cube::*;
+use cubelib::cube::turn::{ApplyAlgorithm, InvertibleMut};
+use cubelib::defs::StepKind;
+use cubelib::solver::df_search::CancelToken;
+use cubelib::solver::solution::{Solution, SolutionStep};
+use cubelib::solver::{solve_steps_from, stream};
+use cubelib::steps::{eo, solver};
+use cubelib::steps::tables::PruningTables333;
+
+use crate::algs;
+use crate::cli::SolveArgs;
+use crate::{solve, tables};
+
+const HELP: &str = "Commands:
+  scramble <alg>   Start over with a new scramble
+  apply <alg>      Apply moves on the current side. Moves in parentheses are applied on the other side
+                   Moves on both sides are applied on the normal side first and end on the inverse
+  switch           Switch between normal and inverse
+  undo             Undo the last apply, switch or solve
+  solve <steps>    Search the next steps, e.g. 'solve DR[triggers=R]' and pick one of the options
+  analyze          Show the bad edges and optimal EOs of the current side
+  show             Show the cube and the moves so far
+  help             Show this help
+  quit             Exit the REPL";
+
+struct Repl {
+    args: SolveArgs,
+    //Tables are only loaded once and reused by every command
+    tables: PruningTables333,
+    scramble: Algorithm,
+    solution: Solution,
+    history: Vec<Solution>,
+}
+
+pub fn run(args: &SolveArgs) {
+    let mut repl = Repl {
+        args: args.clone(),
+        tables: tables::pruning_tables(),
+        scramble: Algorithm::new(),
+        solution: Solution::new(),
+        history: vec![],
+    };
+    if let Some(scramble) = args.scramble.as_ref() {
+        repl.set_scramble(scramble);
+    }
+    println!("Type 'help' for a list of commands");
+
+    let mut lines = stdin().lock().lines().map_while(Result::ok);
+    loop {
+        print!("{}> ", if repl.solution.ends_on_normal() { "normal" } else { "inverse" });
+        stdout().flush().unwrap();
+        let Some(line) = lines.next() else {
+            break;
+        };
+        let line = line.trim();
+        let (command, arg) = line.split_once(' ').map_or((line, ""), |(c, a)| (c, a.trim()));
+        match command {
+            "" => {},
+            "scramble" => repl.set_scramble(arg),
+            "apply" => repl.apply(arg),
+            "switch" => repl.switch(),
+            "undo" => repl.undo(),
+            "solve" => repl.solve(arg, &mut lines),
+            "analyze" => repl.analyze(),
+            "show" => repl.show(),
+            "help" => println!("{HELP}"),
+            "quit" | "exit" => break,
+            _ => println!("Unknown command {command}. Type 'help' for a list of commands"),
+        }
+    }
+}
+
+impl Repl {
+    //The cube as seen from the side the solution currently ends on
+    fn current_cube(&self) -> Cube333 {
+        let mut cube = Cube333::default();
+        cube.apply_alg(&self.scramble);
+        let alg: Algorithm = self.solution.clone().into();
+        cube.apply_alg(&alg);
+        if !self.solution.ends_on_normal() {
+            cube.invert();
+        }
+        cube
+    }
+
+    fn set_scramble(&mut self, scramble: &str) {
+        let Some(scramble) = algs::parse_alg(scramble) else {
+            return;
+        };
+        self.scramble = scramble;
+        self.solution = Solution::new();
+        self.history.clear();
+        self.show();
+    }
+
+    fn apply(&mut self, alg: &str) {
+        let Some(mut alg) = algs::parse_alg(alg) else {
+            return;
+        };
+        if alg.len() == 0 {
+            return;
+        }
+        if !self.solution.ends_on_normal() {
+            std::mem::swap(&mut alg.normal_moves, &mut alg.inverse_moves);
+        }
+        self.history.push(self.solution.clone());
+        self.solution.add_step(SolutionStep {
+            kind: StepKind::Other("moves".to_string()),
+            variant: String::default(),
+            alg,
+            comment: String::default(),
+            niss_switches: 0,
+        });
+        self.show_solution();
+    }
+
+    fn switch(&mut self) {
+        self.history.push(self.solution.clone());
+        self.solution.ends_on_normal = !self.solution.ends_on_normal;
+    }
+
+    fn undo(&mut self) {
+        match self.history.pop() {
+            Some(solution) => {
+                self.solution = solution;
+                self.show_solution();
+            },
+            None => println!("Nothing to undo"),
+        }
+    }
+
+    fn solve(&mut self, steps: &str, input: &mut impl Iterator<Item = String>) {
+        let mut args = self.args.clone();
+        args.steps = steps.to_string();
+        let step_configs = match args.parse_step_configs() {
+            Ok(val) => val,
+            Err(e) => {
+                println!("Unable to parse steps config. {e}");
+                return;
+            }
+        };
+        //Manually applied moves don't change which steps can follow
+        let previous = self.solution.steps.iter().rev()
+            .map(|step| step.kind.clone())
+            .find(|kind| !matches!(kind, StepKind::Other(_)));
+        solver::gen_tables_after(previous.clone(), &step_configs, &mut self.tables);
+        let steps = match solve::build_steps_after(&args, previous, step_configs, &self.tables) {
+            Ok(val) => val,
+            Err(e) => {
+                println!("{e}");
+                return;
+            }
+        };
+
+        let mut cube = Cube333::default();
+        cube.apply_alg(&self.scramble);
+        let known_steps = self.solution.steps.len();
+        let filter_last_move = solve::should_filter_last_move(&args, &steps);
+        let cancel_token = CancelToken::default();
+        let options: Vec<Solution> = stream::distinct_canonical_algorithms(solve_steps_from(cube, self.solution.clone(), &steps, &cancel_token), false)
+            .filter(|sol| !filter_last_move || eo::eo_config::filter_eo_last_moves_pure(&new_moves(sol, known_steps)))
+            .take(args.solution_count.unwrap_or(5))
+            .collect();
+        if options.is_empty() {
+            println!("No solution found");
+            return;
+        }
+
+        for (i, option) in options.iter().enumerate() {
+            let names = option.steps[known_steps..].iter()
+                .map(|step| if step.variant.is_empty() { step.kind.to_string() } else { format!("{}-{}", step.kind, step.variant) })
+                .collect::<Vec<String>>()
+                .join(", ");
+            let alg = new_moves(option, known_steps);
+            println!("{:>2}. {alg}  // {names} ({}/{})", i + 1, alg.len(), option.len());
+        }
+        print!("Pick an option [1-{}], leave empty to skip: ", options.len());
+        stdout().flush().unwrap();
+        let Some(choice) = input.next() else {
+            return;
+        };
+        let choice = choice.trim();
+        if choice.is_empty() {
+            return;
+        }
+        match choice.parse::<usize>().ok().and_then(|i| options.get(i.wrapping_sub(1))) {
+            Some(option) => {
+                self.history.push(self.solution.clone());
+                self.solution = option.clone();
+                self.show_solution();
+            },
+            None => println!("Invalid option {choice}"),
+        }
+    }
+
+    fn analyze(&mut self) {
+        self.tables.gen_eo();
+        algs::print_analysis(&self.current_cube(), &self.tables);
+    }
+
+    fn show(&self) {
+        println!("{}", self.current_cube());
+        self.show_solution();
+    }
+
+    fn show_solution(&self) {
+        if self.solution.steps.is_empty() {
+            println!("Scramble: {}", self.scramble);
+        } else {
+            println!("{}", self.solution);
+        }
+    }
+}
+
+fn new_moves(solution: &Solution, known_steps: usize) -> Algorithm {
+    solution.steps[known_steps..].iter()
+        .fold(Algorithm::new(), |acc, step| acc + step.alg.clone())
+}
@@ -0,0 +1,174 @@
+use std::str::FromStr;
+use std::sync::Arc;
+use std::thread;
+use std::time::{Duration, Instant};
+
+use cubelib::algs::Algorithm;
+use cubelib::cube::*;
+use cubelib::defs::StepKind;
+use cubelib::solver::bounded::BoundedSolutionIterator;
+use cubelib::solver::{scoring, stream};
+use cubelib::solver::df_search::CancelToken;
+use cubelib::solver::scoring::SolutionScorer;
+use cubelib::solver::solution::Solution;
+use cubelib::steps::{eo, solver};
+use cubelib::steps::step::{DefaultStepOptions, Step, StepConfig};
+use cubelib::steps::tables::PruningTables333;
+use cubelib::cube::turn::ApplyAlgorithm;
+use log::{error, info};
+
+use crate::batch;
+use crate::cli::{DedupMode, SolveArgs};
+use crate::output::SolutionPrinter;
+use crate::tables;
+
+pub fn solve(cli: &SolveArgs) {
+    let scorer = match cli.parse_scorer() {
+        Ok(scorer) => scorer,
+        Err(e) => {
+            error!("Unable to parse ranking options. {e}");
+            return;
+        }
+    };
+
+    let step_configs = match cli.parse_step_configs() {
+        Ok(val) => val,
+        Err(e) => {
+            error!("Unable to parse steps config. {e}");
+            return;
+        }
+    };
+    let mut tables = tables::pruning_tables();
+    solver::gen_tables(&step_configs, &mut tables);
+
+    let cancel_token = Arc::new(CancelToken::default());
+    let ct = cancel_token.clone();
+    if let Err(e) = ctrlc::set_handler(move || ct.cancel()) {
+        error!("Unable to register Ctrl-C handler. {e}");
+    }
+
+    if let Some(path) = cli.batch.as_ref() {
+        if let Err(e) = batch::solve_batch(cli, path, &step_configs, &tables, cancel_token.as_ref()) {
+            error!("{e}");
+        }
+        return;
+    }
+
+    let scramble = match cli.scramble.as_ref().map(|s| Algorithm::from_str(s.as_str())) {
+        Some(Ok(scramble)) => scramble,
+        Some(Err(_)) => {
+            error!("Invalid scramble {}", cli.scramble.as_ref().unwrap());
+            return;
+        },
+        None => {
+            error!("A scramble is required");
+            return;
+        }
+    };
+    let mut cube = Cube333::default();
+    cube.apply_alg(&scramble);
+
+    let steps = match build_steps(cli, step_configs, &tables) {
+        Ok(val) => val,
+        Err(e) => {
+            error!("{e}");
+            return;
+        }
+    };
+
+    info!("Generating solutions\n");
+    let time = Instant::now();
+
+    if let Some(seconds) = cli.time {
+        let ct = cancel_token.clone();
+        thread::spawn(move || {
+            thread::sleep(Duration::from_secs(seconds));
+            ct.cancel();
+        });
+        let min = cli.min;
+        let filter_last_move = should_filter_last_move(cli, &steps);
+        let solutions = BoundedSolutionIterator::new(cube, &steps, cli.max, cancel_token.as_ref())
+            .with_filter(move |sol| sol.len() >= min && (!filter_last_move || eo::eo_config::filter_eo_last_moves_pure(&sol.clone().into())));
+
+        let mut printer = SolutionPrinter::new(cli.format.clone());
+        let mut best = None;
+        for solution in solutions {
+            info!("Found solution of length {} after {}ms", solution.len(), time.elapsed().as_millis());
+            printer.print(solution.clone());
+            best = Some(solution);
+        }
+        match best {
+            //Structured output already ends with the best solution, repeating it would only confuse scripts
+            Some(solution) if !printer.is_structured() => {
+                println!();
+                printer.print(solution);
+            },
+            Some(_) => {},
+            None => error!("No solution found"),
+        }
+        printer.finish();
+    } else {
+        let mut printer = SolutionPrinter::new(cli.format.clone());
+        for solution in find_solutions(cli, cube, &steps, scorer, cancel_token.as_ref()) {
+            printer.print(solution);
+        }
+        printer.finish();
+    }
+
+    info!("Took {}ms", time.elapsed().as_millis());
+}
+
+pub(crate) fn build_steps<'a>(cli: &SolveArgs, step_configs: Vec<StepConfig>, tables: &'a PruningTables333) -> Result<Vec<(Step<'a>, DefaultStepOptions)>, cubelib::Error> {
+    build_steps_after(cli, None, step_configs, tables)
+}
+
+pub(crate) fn build_steps_after<'a>(cli: &SolveArgs, previous: Option<StepKind>, step_configs: Vec<StepConfig>, tables: &'a PruningTables333) -> Result<Vec<(Step<'a>, DefaultStepOptions)>, cubelib::Error> {
+    let mut steps = solver::build_steps_after(previous, step_configs, tables)?;
+    if cli.max_niss_switches.is_some() {
+        for (_, search_opts) in steps.iter_mut() {
+            search_opts.max_total_niss_switches = cli.max_niss_switches;
+        }
+    }
+    Ok(steps)
+}
+
+// For e.g. FR the direction of the last move always matters, so we can't filter if we're doing FR
+pub(crate) fn should_filter_last_move(cli: &SolveArgs, steps: &[(Step, DefaultStepOptions)]) -> bool {
+    let can_filter_last_move = steps.last().map(|(s, _)| s.kind() != StepKind::FR && s.kind() != StepKind::FIN).unwrap_or(true);
+    !cli.all_solutions && can_filter_last_move
+}
+
+//Applies the length limits, filters, deduplication and ranking to the solver output
+pub(crate) fn find_solutions<'a>(cli: &'a SolveArgs, cube: Cube333, steps: &'a Vec<(Step<'a>, DefaultStepOptions)>, scorer: Option<Vec<Box<dyn SolutionScorer>>>, cancel_token: &'a CancelToken) -> Box<dyn Iterator<Item = Solution> + 'a> {
+    let solutions: Box<dyn Iterator<Item = Solution>> = if cli.parallel {
+        Box::new(cubelib::solver::solve_steps_parallel(cube, steps, cli.thread_count(), cancel_token))
+    } else {
+        Box::new(cubelib::solver::solve_steps(cube, steps, cancel_token))
+    };
+
+    let mut solutions: Box<dyn Iterator<Item = Solution>> = Box::new(solutions
+        .skip_while(|alg| alg.len() < cli.min)
+        .take_while(|alg| cli.max.is_none_or(|max| alg.len() <= max)));
+
+    if should_filter_last_move(cli, steps) {
+        solutions = Box::new(solutions
+            .filter(|alg| eo::eo_config::filter_eo_last_moves_pure(&alg.clone().into())));
+    }
+
+    //We already generate a mostly duplicate free iterator, but sometimes the same solution is valid for different stages and that can cause duplicates.
+    let solutions: Box<dyn Iterator<Item = Solution>> = match cli.dedup {
+        DedupMode::Exact => Box::new(stream::distinct_algorithms(solutions)),
+        DedupMode::Canonical => Box::new(stream::distinct_canonical_algorithms(solutions, false)),
+        DedupMode::Final => Box::new(stream::distinct_canonical_algorithms(solutions, true)),
+    };
+
+    if let Some(scorer) = scorer {
+        let count = cli.solution_count.unwrap_or(if cli.max.is_none() { 1 } else { cli.candidates });
+        Box::new(scoring::rank_solutions(solutions.take(cli.candidates), &scorer, count).into_iter())
+    } else if cli.max.is_none() || cli.solution_count.is_some() {
+        //The iterator is always sorted, so this just returns the shortest solutions
+        Box::new(solutions.take(cli.solution_count.unwrap_or(1)))
+    } else {
+        solutions
+    }
+}
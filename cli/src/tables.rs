@@ -0,0 +1,230 @@
+use std::fs;
+#[cfg(target_os = "linux")]
+use std::path::Path;
+use std::time::Duration;
+
+use cubelib::solver::table_header;
+use cubelib::solver::lookup_table::TableType;
+use cubelib::solver::table_header::Compression;
+use cubelib::solver::table_stats::TableStats;
+use cubelib::solver::table_store::TableStore;
+use cubelib::steps::tables::PruningTables333;
+use log::info;
+
+use crate::cli::TablesCommand;
+
+//Keys accepted by PruningTables333::load. The htr key also loads the htr-subset table
+const TABLE_KEYS: [&str; 8] = ["eo", "dr", "htr", "fr", "frls", "frfin", "htrfin", "htrlsfin"];
+//Keys accepted by PruningTables333::stats
+const STATS_KEYS: [&str; 9] = ["eo", "dr", "htr", "htr-subset", "fr", "frls", "frfin", "htrfin", "htrlsfin"];
+//Writing a table only takes a few seconds, so temporary files that weren't touched for longer than this are left over
+const TEMP_FILE_MAX_AGE: Duration = Duration::from_secs(60 * 60);
+
+pub fn run(command: &TablesCommand) {
+    match command {
+        TablesCommand::Generate { compress } => generate(*compress),
+        TablesCommand::List => list(),
+        TablesCommand::Verify => verify(),
+        TablesCommand::Prune => prune(),
+        TablesCommand::Info { keys } => info(keys),
+    }
+}
+
+//Tables for solving. With the embedded-tables feature, the tables built into the binary are used instead of the store
+pub fn pruning_tables() -> PruningTables333 {
+    #[cfg(feature = "embedded-tables")]
+    return cubelib_embedded::tables();
+    #[cfg(not(feature = "embedded-tables"))]
+    PruningTables333::new()
+}
+
+//Loads every table. Missing tables and ones that can't be loaded are generated concurrently and saved
+fn generate(compress: bool) {
+    let mut tables = PruningTables333::new();
+    if !tables.store().is_writable() {
+        println!("The table store is read-only, generated tables won't be saved");
+    }
+    if compress {
+        tables.set_compression(Compression::Lz4);
+    }
+    tables.gen_all();
+    info!("All tables are available");
+}
+
+fn list() {
+    let store = TableStore::from_env();
+    let Some(dir) = store.puzzle_dir("333") else {
+        println!("Pruning tables aren't saved. Set CUBELIB_TABLES_DIR to store them");
+        return;
+    };
+    let entries = match store.list("333") {
+        Ok(entries) if !entries.is_empty() => entries,
+        Ok(_) => {
+            println!("No tables found in {}", dir.display());
+            return;
+        },
+        Err(e) => {
+            println!("Unable to read {}. {e}", dir.display());
+            return;
+        }
+    };
+    println!("{}", dir.display());
+    for (key, size) in entries {
+        println!("{:<16} {:>10} bytes", format!("{key}.tbl"), size);
+    }
+}
+
+fn verify() {
+    let mut failed = false;
+    for key in TABLE_KEYS {
+        let mut tables = PruningTables333::new();
+        match tables.load(key) {
+            Ok(_) => println!("{key:<10} OK"),
+            Err(e) => {
+                failed = true;
+                println!("{key:<10} {e}");
+            }
+        }
+    }
+    if failed {
+        println!("Run 'tables generate' to regenerate the missing tables");
+    }
+}
+
+//Built-in tables have to match the current table id. Other tables, like the ones of custom steps, only have to be intact
+fn prune() {
+    let store = TableStore::from_env();
+    if !store.is_writable() {
+        println!("The table store is read-only");
+        return;
+    }
+    let entries = match store.list("333") {
+        Ok(entries) => entries,
+        Err(e) => {
+            println!("Unable to list the tables. {e}");
+            return;
+        }
+    };
+    let mut removed = 0;
+    for (key, _) in entries {
+        let check = store.read("333", &key)
+            .and_then(|data| match PruningTables333::table_id(&key) {
+                Some(id) => table_header::deserialize(&data, &id).map(|_| ()),
+                None => table_header::verify(&data).map(|_| ()),
+            });
+        if let Err(e) = check {
+            match store.remove("333", &key) {
+                Ok(_) => {
+                    removed += 1;
+                    println!("Removed {key}. {e}");
+                },
+                Err(remove_err) => println!("Unable to remove {key}. {remove_err}"),
+            }
+        }
+    }
+    //Tables are written to a temporary file first. Only interrupted saves leave them behind, other files may still be written to
+    if let Some(dir) = store.puzzle_dir("333") {
+        let temp_files = fs::read_dir(&dir).into_iter()
+            .flatten()
+            .filter_map(Result::ok)
+            .filter(is_stale_temp_file);
+        for file in temp_files {
+            if fs::remove_file(file.path()).is_ok() {
+                removed += 1;
+                println!("Removed {}", file.file_name().to_string_lossy());
+            }
+        }
+    }
+    println!("Removed {removed} files");
+}
+
+//Temporary files are called <table>.<pid>.tmp. They are stale once the process that wrote them is gone
+//or, where we can't check that, once they haven't been written to for a while
+fn is_stale_temp_file(entry: &fs::DirEntry) -> bool {
+    let name = entry.file_name().to_string_lossy().to_string();
+    let Some(pid) = name.strip_suffix(".tmp").and_then(|name| name.rsplit_once('.')).map(|(_, pid)| pid.to_string()) else {
+        return false;
+    };
+    if is_process_gone(&pid) {
+        return true;
+    }
+    entry.metadata()
+        .and_then(|metadata| metadata.modified())
+        .ok()
+        .and_then(|modified| modified.elapsed().ok())
+        .is_some_and(|age| age > TEMP_FILE_MAX_AGE)
+}
+
+#[cfg(target_os = "linux")]
+fn is_process_gone(pid: &str) -> bool {
+    pid.parse::<u32>().is_ok() && !Path::new("/proc").join(pid).exists()
+}
+
+#[cfg(not(target_os = "linux"))]
+fn is_process_gone(_: &str) -> bool {
+    false
+}
+
+//Only shows tables that are already available. Missing tables aren't generated
+fn info(keys: &[String]) {
+    let keys: Vec<&str> = if keys.is_empty() {
+        STATS_KEYS.to_vec()
+    } else {
+        keys.iter().map(String::as_str).collect()
+    };
+    let mut tables = pruning_tables();
+    let mut missing = false;
+    for key in keys {
+        if !STATS_KEYS.contains(&key) {
+            println!("{key}: unknown table\n");
+            continue;
+        }
+        if tables.stats(key).is_none() {
+            //The htr-subset table is loaded together with the htr table
+            let load_key = if key == "htr-subset" { "htr" } else { key };
+            if let Err(e) = tables.load(load_key) {
+                missing = true;
+                println!("{key}: {e}\n");
+                continue;
+            }
+        }
+        if let Some(stats) = tables.stats(key) {
+            print_stats(key, &stats);
+        }
+    }
+    if missing {
+        println!("Run 'tables generate' to generate the missing tables");
+    }
+}
+
+fn print_stats(key: &str, stats: &TableStats) {
+    let table_type = match stats.table_type {
+        TableType::Uncompressed => "8 bit",
+        TableType::Compressed => "4 bit",
+        TableType::Niss => "8 bit with NISS",
+        TableType::ModThree => "2 bit mod 3",
+    };
+    println!("{key}: {table_type}, {} coordinates, {} bytes", stats.size, stats.memory);
+    println!("  reached {}/{} ({:.2}%)", stats.reached(), stats.size, stats.reached() as f64 * 100.0 / stats.size.max(1) as f64);
+    //The htr-subset table stores the subset of every coordinate instead of a distance
+    if key == "htr-subset" {
+        print_histogram("subset", &stats.depths);
+        println!();
+        return;
+    }
+    print_histogram(if stats.table_type == TableType::ModThree { "depth mod 3" } else { "depth" }, &stats.depths);
+    if let Some(niss) = &stats.niss {
+        print_histogram("niss bound", niss);
+    }
+    if let (Some(max), Some(average)) = (stats.max_depth(), stats.average_depth()) {
+        println!("  max depth {max}, average {average:.3}");
+    }
+    println!();
+}
+
+fn print_histogram(label: &str, counts: &[usize]) {
+    println!("  {label:>11} {:>12}", "coordinates");
+    for (value, count) in counts.iter().enumerate().filter(|(_, count)| **count > 0) {
+        println!("  {value:>11} {count:>12}");
+    }
+}
@@ -83,6 +83,46 @@ impl Algorithm {
     }
 }
 
+impl Algorithm {
+    //Merges consecutive turns of the same face and sorts turns of opposite faces, so algorithms that only differ in the order
+    //of commuting moves or in moves that cancel compare as equal. Normal and inverse moves are handled separately.
+    pub fn canonical(&self) -> Self {
+        Algorithm {
+            normal_moves: Algorithm::canonical_moves(&self.normal_moves),
+            inverse_moves: Algorithm::canonical_moves(&self.inverse_moves),
+        }
+    }
+
+    fn canonical_moves(moves: &[Turn333]) -> Vec<Turn333> {
+        let mut result: Vec<Turn333> = vec![];
+        for m in moves.iter().cloned() {
+            //Start of the moves at the end that are on the same axis as m and therefore commute with it
+            let axis_start = result.iter()
+                .rposition(|p| p.face != m.face && p.face != m.face.opposite())
+                .map_or(0, |i| i + 1);
+            match result[axis_start..].iter().position(|p| p.face == m.face) {
+                Some(i) => match (Algorithm::quarter_turns(result[axis_start + i].dir) + Algorithm::quarter_turns(m.dir)) % 4 {
+                    0 => { result.remove(axis_start + i); },
+                    1 => result[axis_start + i].dir = Direction::Clockwise,
+                    2 => result[axis_start + i].dir = Direction::Half,
+                    _ => result[axis_start + i].dir = Direction::CounterClockwise,
+                },
+                None => result.push(m),
+            }
+            result[axis_start..].sort_by_key(|p| p.face as usize);
+        }
+        result
+    }
+
+    fn quarter_turns(dir: Direction) -> u8 {
+        match dir {
+            Direction::Clockwise => 1,
+            Direction::Half => 2,
+            Direction::CounterClockwise => 3,
+        }
+    }
+}
+
 impl Algorithm {
     pub fn mirror(&mut self, axis: CubeAxis) {
         self.normal_moves = self
@@ -180,3 +220,44 @@ impl TransformableMut for Algorithm {
             .collect_vec();
     }
 }
+
+#[cfg(test)]
+mod tests {
+    use std::str::FromStr;
+
+    use crate::algs::Algorithm;
+
+    fn canonical(alg: &str) -> Algorithm {
+        Algorithm::from_str(alg).unwrap().canonical()
+    }
+
+    #[test]
+    fn canonical_sorts_opposite_faces() {
+        assert_eq!(canonical("D U R"), canonical("U D R"));
+        assert_eq!(canonical("F R L2 B"), canonical("F L2 R B"));
+        assert_ne!(canonical("U R"), canonical("R U"));
+    }
+
+    #[test]
+    fn canonical_merges_and_cancels() {
+        assert_eq!(canonical("R R"), Algorithm::from_str("R2").unwrap());
+        assert_eq!(canonical("R U D U' R"), Algorithm::from_str("R D R").unwrap());
+        assert_eq!(canonical("R U D U' D' R"), Algorithm::from_str("R2").unwrap());
+        assert_eq!(canonical("F R2 L R2 F'"), Algorithm::from_str("F L F'").unwrap());
+        assert_eq!(canonical("U U' R D2 D2"), Algorithm::from_str("R").unwrap());
+    }
+
+    #[test]
+    fn canonical_keeps_sides_apart() {
+        assert_eq!(canonical("R (R)"), Algorithm::from_str("R (R)").unwrap());
+        assert_eq!(canonical("U (D U')"), canonical("U (U' D)"));
+        assert_eq!(canonical("U (D U')").normal_moves.len(), 1);
+    }
+
+    #[test]
+    fn canonical_merge_sides() {
+        assert_eq!(Algorithm::from_str("R (R)").unwrap().to_uninverted().canonical(), Algorithm::from_str("R R'").unwrap().canonical());
+        assert_eq!(Algorithm::from_str("U R (U)").unwrap().to_uninverted().canonical(), Algorithm::from_str("U R U'").unwrap());
+        assert_eq!(Algorithm::from_str("R U (D)").unwrap().to_uninverted().canonical(), Algorithm::from_str("R D' U").unwrap().canonical());
+    }
+}
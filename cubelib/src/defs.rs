@@ -35,7 +35,7 @@ impl FromStr for StepKind {
             "frls" => Ok(Self::FRLS),
             "finish" | "fin" => Ok(Self::FIN),
             "finls" => Ok(Self::FINLS),
-            x=> Ok(Self::Other(x.to_string()))
+            _ => Ok(Self::Other(s.to_string()))
         }
     }
 }
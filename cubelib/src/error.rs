@@ -0,0 +1,52 @@
+use std::fmt::{Display, Formatter};
+
+use crate::defs::StepKind;
+
+#[derive(Debug)]
+pub enum Error {
+    //A step configuration that can't be parsed or has invalid substeps or parameters
+    InvalidConfig(String),
+    //The step can't follow the previous step. previous is None for the first step
+    UnsupportedStepOrder { previous: Option<StepKind>, kind: StepKind },
+    //A pruning table that has to be generated or loaded before building the step
+    MissingTable(String),
+    //A table file with a broken header or an unexpected size
+    CorruptTable(String),
+    //A table file written by a different version of cubelib
+    OutdatedTable { version: u8, expected: u8 },
+    //A table file that was generated for a different coordinate, move set or generator version
+    MismatchedTable(String),
+    Io(std::io::Error),
+}
+
+pub type Result<T> = std::result::Result<T, Error>;
+
+impl Display for Error {
+    fn fmt(&self, f: &mut Formatter<'_>) -> std::fmt::Result {
+        match self {
+            Error::InvalidConfig(message) => write!(f, "{message}"),
+            Error::UnsupportedStepOrder { previous: None, kind } => write!(f, "{kind:?} is not supported as a first step"),
+            Error::UnsupportedStepOrder { previous: Some(previous), kind } => write!(f, "Unsupported step order {previous:?} > {kind:?}"),
+            Error::MissingTable(key) => write!(f, "The {key} table is required but wasn't loaded"),
+            Error::CorruptTable(message) => write!(f, "Corrupt table: {message}"),
+            Error::OutdatedTable { version, expected } => write!(f, "Outdated table version {version}, expected {expected}"),
+            Error::MismatchedTable(message) => write!(f, "Mismatched table: {message}"),
+            Error::Io(e) => write!(f, "{e}"),
+        }
+    }
+}
+
+impl std::error::Error for Error {
+    fn source(&self) -> Option<&(dyn std::error::Error + 'static)> {
+        match self {
+            Error::Io(e) => Some(e),
+            _ => None,
+        }
+    }
+}
+
+impl From<std::io::Error> for Error {
+    fn from(e: std::io::Error) -> Self {
+        Error::Io(e)
+    }
+}
@@ -5,6 +5,7 @@ extern crate core;
 extern crate num_derive;
 
 pub mod algs;
+pub mod error;
 mod simd_util;
 pub mod defs;
 #[cfg(feature = "solver")]
@@ -14,3 +15,5 @@ pub mod solver;
 #[cfg(target_arch = "wasm32")]
 mod wasm_util;
 pub mod cube;
+
+pub use error::Error;
@@ -0,0 +1,160 @@
+use std::cell::Cell;
+use std::rc::Rc;
+
+use log::{debug, trace};
+
+use crate::algs::Algorithm;
+use crate::cube::Cube333;
+use crate::cube::turn::ApplyAlgorithm;
+use crate::solver::df_search::CancelToken;
+use crate::solver::solution::Solution;
+use crate::steps::step::{DefaultStepOptions, next_step, Step};
+
+type SolutionFilter<'a> = Box<dyn Fn(&Solution) -> bool + 'a>;
+
+//Depth first branch and bound over the whole pipeline. Every step yields its solutions ordered by length,
+//so the first complete solution is found greedily and every following one has to beat the current bound.
+pub struct BoundedSolutionIterator<'a> {
+    puzzle: Cube333,
+    steps: &'a Vec<(Step<'a>, DefaultStepOptions)>,
+    cancel_token: &'a CancelToken,
+    //Exclusive upper bound for the length of the next solution
+    bound: Rc<Cell<usize>>,
+    stack: Vec<Box<dyn Iterator<Item = Solution> + 'a>>,
+    filter: Option<SolutionFilter<'a>>,
+}
+
+impl<'a> BoundedSolutionIterator<'a> {
+    pub fn new(puzzle: Cube333, steps: &'a Vec<(Step<'a>, DefaultStepOptions)>, max_length: Option<usize>, cancel_token: &'a CancelToken) -> Self {
+        let mut iter = BoundedSolutionIterator {
+            puzzle,
+            steps,
+            cancel_token,
+            bound: Rc::new(Cell::new(max_length.map(|m| m + 1).unwrap_or(usize::MAX))),
+            stack: vec![],
+            filter: None,
+        };
+        if !steps.is_empty() {
+            let first = iter.expand(Solution::new(), 0);
+            iter.stack.push(first);
+        }
+        iter
+    }
+
+    //Complete solutions rejected by the filter are skipped without tightening the bound
+    pub fn with_filter<F: Fn(&Solution) -> bool + 'a>(mut self, filter: F) -> Self {
+        self.filter = Some(Box::new(filter));
+        self
+    }
+
+    pub fn bound(&self) -> Option<usize> {
+        match self.bound.get() {
+            usize::MAX => None,
+            b => Some(b - 1),
+        }
+    }
+
+    fn expand(&self, solution: Solution, step_id: usize) -> Box<dyn Iterator<Item = Solution> + 'a> {
+        let (step, search_opts) = &self.steps[step_id];
+        let bound = self.bound.clone();
+        let prefix_len = solution.len();
+        let cancel_token = self.cancel_token;
+        let puzzle = self.puzzle;
+        Box::new((search_opts.min_moves..=search_opts.max_moves)
+            .take_while(move |depth| prefix_len + (*depth as usize) < bound.get())
+            .flat_map(move |depth| {
+                let mut opts = *search_opts;
+                opts.min_moves = depth;
+                opts.max_moves = depth;
+                next_step(vec![solution.clone()].into_iter(), step, opts, puzzle, cancel_token)
+            }))
+    }
+
+    fn lower_bound(&self, solution: &Solution, step_id: usize) -> Option<u8> {
+        let (step, search_opts) = &self.steps[step_id];
+        let mut cube = self.puzzle;
+        let alg: Algorithm = solution.clone().into();
+        cube.apply_alg(&alg);
+        step.lower_bound(&cube, search_opts, solution.ends_on_normal())
+    }
+}
+
+impl Iterator for BoundedSolutionIterator<'_> {
+    type Item = Solution;
+
+    fn next(&mut self) -> Option<Self::Item> {
+        while let Some(top) = self.stack.last_mut() {
+            if self.cancel_token.is_cancelled() {
+                return None;
+            }
+            let solution = match top.next() {
+                None => {
+                    self.stack.pop();
+                    continue;
+                }
+                Some(solution) => solution,
+            };
+            let step_id = self.stack.len() - 1;
+            //Solutions of a step are ordered by length, nothing after this one can improve on the bound either
+            if solution.len() >= self.bound.get() {
+                self.stack.pop();
+                continue;
+            }
+            if step_id + 1 == self.steps.len() {
+                if !self.filter.as_ref().is_none_or(|f| f(&solution)) {
+                    continue;
+                }
+                debug!("Found solution of length {}, pruning everything that isn't shorter", solution.len());
+                self.bound.set(solution.len());
+                return Some(solution);
+            }
+            match self.lower_bound(&solution, step_id + 1) {
+                Some(h) if solution.len() + (h as usize) < self.bound.get() => {
+                    let next = self.expand(solution, step_id + 1);
+                    self.stack.push(next);
+                },
+                h => trace!("Pruning {solution:?} with lower bound {h:?} for step {}", self.steps[step_id + 1].0.kind()),
+            }
+        }
+        None
+    }
+}
+
+#[cfg(all(test, feature = "333htr"))]
+mod tests {
+    use std::str::FromStr;
+
+    use crate::algs::Algorithm;
+    use crate::cube::Cube333;
+    use crate::cube::turn::ApplyAlgorithm;
+    use crate::solver::df_search::CancelToken;
+    use crate::steps::{dsl, solver};
+    use crate::steps::tables::PruningTables333;
+
+    use super::BoundedSolutionIterator;
+
+    const SCRAMBLES: [&str; 3] = [
+        "R' U' F L2 D' B2 U' F2 D' L2 B2 R2 U' R F' U2 L D' B2 R U R' U' F",
+        "D2 R2 U' B2 D' L2 U F2 U2 R2 B' L' B R' D' L' R2 F' U2 R' F",
+        "B2 U2 L2 F2 D' R2 D' F2 U R2 U' L' F' R' U2 B L' D2 F' U' R'",
+    ];
+
+    #[test]
+    fn solutions_get_strictly_shorter() {
+        let configs = dsl::parse_steps("EO > DR > HTR").unwrap();
+        let mut tables = PruningTables333::new();
+        solver::gen_tables(&configs, &mut tables);
+        let steps = solver::build_steps(configs, &tables).unwrap();
+        let cancel_token = CancelToken::default();
+        for scramble in SCRAMBLES {
+            let mut cube = Cube333::default();
+            cube.apply_alg(&Algorithm::from_str(scramble).unwrap());
+            let lengths: Vec<usize> = BoundedSolutionIterator::new(cube, &steps, Some(22), &cancel_token)
+                .map(|solution| solution.len())
+                .collect();
+            assert!(!lengths.is_empty(), "No solution for {scramble}");
+            assert!(lengths[0] <= 22, "{scramble}: {lengths:?}");
+            assert!(lengths.windows(2).all(|w| w[1] < w[0]), "{scramble}: {lengths:?}");
+        }
+    }
+}
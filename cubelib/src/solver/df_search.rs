@@ -32,7 +32,7 @@ impl Default for CancelToken {
 pub fn dfs_iter<'a, S: StepVariant + ?Sized>(
     step: &'a S,
     mut cube: Cube333,
-    search_opts: DefaultStepOptions,
+    mut search_opts: DefaultStepOptions,
     mut previous_normal: Option<Turn333>,
     mut previous_inverse: Option<Turn333>,
     starts_on_normal: bool,
@@ -48,6 +48,14 @@ pub fn dfs_iter<'a, S: StepVariant + ?Sized>(
         return None;
     }
 
+    //Before switches at most once, when the step starts. Always starts on the normal side, which is a switch if the previous step
+    //ended on the inverse, and can switch to the inverse once more. Restricting the NISS type enforces the remaining switches
+    search_opts.niss_type = match (search_opts.max_niss_switches, search_opts.niss_type) {
+        (Some(0), _) => NissSwitchType::Never,
+        (Some(1), NissSwitchType::Always) if !starts_on_normal => NissSwitchType::Before,
+        (_, niss_type) => niss_type,
+    };
+
     //Return immediately if the cube is solved. This avoids the issue where we return two solutions if the NISS type is AtStart.
     if step.heuristic(&cube, search_opts.min_moves, search_opts.niss_type != NissSwitchType::Never) == 0 {
         //Only return a solution if we are allowed to return zero length solutions
@@ -67,7 +75,7 @@ pub fn dfs_iter<'a, S: StepVariant + ?Sized>(
                         Box::new(
                             next_dfs_level(
                                 step,
-                                cube.clone(),
+                                cube,
                                 depth,
                                 false,
                                 false,
@@ -80,7 +88,7 @@ pub fn dfs_iter<'a, S: StepVariant + ?Sized>(
                         )
                     },
                     NissSwitchType::Never => {
-                        let mut inv_cube = cube.clone();
+                        let mut inv_cube = cube;
                         inv_cube.invert();
                         Box::new(
                             next_dfs_level(
@@ -108,7 +116,7 @@ pub fn dfs_iter<'a, S: StepVariant + ?Sized>(
                         Box::new(
                             next_dfs_level(
                                 step,
-                                cube.clone(),
+                                cube,
                                 depth,
                                 true,
                                 true,
@@ -123,7 +131,7 @@ pub fn dfs_iter<'a, S: StepVariant + ?Sized>(
                     NissSwitchType::Before => {
                         let no_niss = next_dfs_level(
                             step,
-                            cube.clone(),
+                            cube,
                             depth,
                             true,
                             false,
@@ -133,7 +141,7 @@ pub fn dfs_iter<'a, S: StepVariant + ?Sized>(
                             cancel_token,
                         )
                         .map(|alg| alg.reverse());
-                        let mut inverted = cube.clone();
+                        let mut inverted = cube;
                         inverted.invert();
                         let only_niss = next_dfs_level(
                             step,
@@ -177,9 +185,14 @@ fn next_dfs_level<'a, S: StepVariant + ?Sized>(
     previous_inverse: Option<Turn333>,
     cancel_token: &'a CancelToken,
 ) -> Box<dyn Iterator<Item = Algorithm> + 'a> {
+    //Parallel searches collect all solutions of a depth before returning any, so they can only be stopped here.
+    //Serial searches stop here as well, so they don't return the rest of the current depth after the token is cancelled
+    if cancel_token.is_cancelled() {
+        return Box::new(vec![].into_iter());
+    }
     let lower_bound = step.heuristic(&cube, depth_left, invert_allowed);
     trace!("[{}]{}DFS depth {depth_left}, lower bound {lower_bound}, invert {invert_allowed}, {previous_normal:?}, {previous_inverse:?}", step.name(), " ".repeat(10 - depth_left as usize));
-    let mut inverse = cube.clone();
+    let mut inverse = cube;
     let normal_solutions: Box<dyn Iterator<Item = Algorithm>> = if depth_left == 0 && lower_bound == 0 {
         Box::new(vec![Algorithm::new()].into_iter())
     } else if lower_bound == 0 || lower_bound > depth_left {
@@ -288,4 +301,40 @@ fn next_dfs_level<'a, S: StepVariant + ?Sized>(
     } else {
         return normal_solutions;
     };
-}
\ No newline at end of file
+}
+#[cfg(all(test, feature = "333dr"))]
+mod tests {
+    use std::str::FromStr;
+
+    use crate::algs::Algorithm;
+    use crate::cube::Cube333;
+    use crate::cube::turn::ApplyAlgorithm;
+    use crate::solver;
+    use crate::solver::df_search::CancelToken;
+    use crate::steps::{dsl, solver as step_solver};
+    use crate::steps::tables::PruningTables333;
+
+    #[test]
+    fn niss_switch_limits_are_enforced() {
+        let mut cube = Cube333::default();
+        cube.apply_alg(&Algorithm::from_str("R' U' F L2 D' B2 U' F2 D' L2 B2 R2 U' R F' U2 L D' B2 R U R' U' F").unwrap());
+        let cancel_token = CancelToken::default();
+        for (config, max_total) in [("EO[niss=always;max_switches=1] > DR[niss=always;max_switches=1]", None), ("EO[niss=always] > DR[niss=always]", Some(1))] {
+            let configs = dsl::parse_steps(config).unwrap();
+            let mut tables = PruningTables333::new();
+            step_solver::gen_tables(&configs, &mut tables);
+            let mut steps = step_solver::build_steps(configs, &tables).unwrap();
+            for (_, search_opts) in steps.iter_mut() {
+                search_opts.max_total_niss_switches = max_total;
+            }
+            let solutions: Vec<_> = solver::solve_steps(cube, &steps, &cancel_token).take(100).collect();
+            assert!(!solutions.is_empty());
+            for solution in solutions {
+                assert!(solution.steps.iter().all(|s| s.niss_switches <= 1), "{config}: {solution}");
+                if max_total.is_some() {
+                    assert!(solution.niss_switches() <= 1, "{config}: {solution}");
+                }
+            }
+        }
+    }
+}
@@ -1,26 +1,36 @@
 use std::collections::HashMap;
+use std::collections::hash_map::Entry;
 use std::fmt::Debug;
 #[cfg(feature = "fs")]
 use std::fs;
 #[cfg(feature = "fs")]
 use std::fs::File;
-use std::hash::Hash;
 #[cfg(feature = "fs")]
-use std::io::{Read, Write};
+use std::io::Write;
+#[cfg(feature = "fs")]
+use std::path::Path;
 use std::marker::PhantomData;
+use std::ops::{Deref, DerefMut};
 #[cfg(feature = "fs")]
-use home::home_dir;
-use log::{debug, warn};
-use num_traits::{ToPrimitive};
+use std::sync::Arc;
 #[cfg(feature = "fs")]
-use num_traits::{FromPrimitive};
+use memmap2::Mmap;
+use log::{debug, warn};
 use crate::cube::*;
 use crate::cube::turn::{TransformableMut, TurnableMut};
+use crate::Error;
 use crate::solver::moveset::MoveSet;
+use crate::solver::move_table::MoveTable;
+use crate::solver::table_header;
+#[cfg(feature = "fs")]
+use crate::solver::table_header::Compression;
+use crate::solver::table_header::HEADER_SIZE;
+use crate::solver::table_header::TableId;
+use crate::solver::table_stats::TableStats;
+#[cfg(feature = "fs")]
+use crate::solver::table_store::TableStore;
 use crate::steps::coord::Coord;
 
-const VERSION: u8 = 1;
-
 #[derive(Copy, Clone, Eq, PartialEq, Debug)]
 #[repr(u8)]
 #[derive(FromPrimitive, ToPrimitive)]
@@ -28,48 +38,168 @@ pub enum TableType {
     Uncompressed = 0u8,
     Compressed = 1u8,
     Niss = 2u8,
+    //Distances modulo 3, 2 bits per entry
+    ModThree = 3u8,
+}
+
+//Table entries are either owned or a read-only view into a memory-mapped table file.
+//Mapped entries are shared with every other process that maps the same file through the page cache.
+//Writing to mapped entries copies them into memory first.
+#[derive(Clone)]
+pub enum TableEntries {
+    Owned(Box<[u8]>),
+    #[cfg(feature = "fs")]
+    Mapped(Arc<Mmap>),
+}
+
+impl TableEntries {
+    pub fn filled(size: usize, val: u8) -> Self {
+        TableEntries::Owned(vec![val; size].into_boxed_slice())
+    }
+
+    pub fn is_mapped(&self) -> bool {
+        !matches!(self, TableEntries::Owned(_))
+    }
+}
+
+impl Deref for TableEntries {
+    type Target = [u8];
+
+    fn deref(&self) -> &[u8] {
+        match self {
+            TableEntries::Owned(entries) => entries,
+            #[cfg(feature = "fs")]
+            TableEntries::Mapped(mmap) => &mmap[HEADER_SIZE..],
+        }
+    }
+}
+
+impl DerefMut for TableEntries {
+    fn deref_mut(&mut self) -> &mut [u8] {
+        #[cfg(feature = "fs")]
+        if let TableEntries::Mapped(mmap) = self {
+            *self = TableEntries::Owned(mmap[HEADER_SIZE..].into());
+        }
+        match self {
+            TableEntries::Owned(entries) => entries,
+            #[cfg(feature = "fs")]
+            TableEntries::Mapped(_) => unreachable!(),
+        }
+    }
 }
 
 #[derive(Clone)]
 pub struct LookupTable<const C_SIZE: usize, C: Coord<C_SIZE>> {
-    entries: Box<[u8; C_SIZE]>,
+    entries: TableEntries,
     coord_type: PhantomData<C>,
     compressed: bool,
 }
 
 #[derive(Clone)]
 pub struct NissLookupTable<const C_SIZE: usize, C: Coord<C_SIZE>> {
-    entries: Box<[u8]>,
+    entries: TableEntries,
+    coord_type: PhantomData<C>,
+}
+
+//Stores the distance of every coordinate modulo 3, which needs a quarter of the memory of an uncompressed table.
+//The exact distance is recovered by walking towards the goal, because the neighbours of a coordinate are always
+//one move closer, equally far or one move further away. This only holds if every move can be undone on the coordinate level
+#[derive(Clone)]
+pub struct ModThreeLookupTable<const C_SIZE: usize, C: Coord<C_SIZE>> {
+    entries: TableEntries,
     coord_type: PhantomData<C>,
 }
 
-impl <const C_SIZE: usize, C: Coord<C_SIZE>> Into<Vec<u8>> for &LookupTable<C_SIZE, C> {
-    fn into(self) -> Vec<u8> {
-        let table_type = if self.compressed {
+//Uncompressed table for coordinates whose size is only known at runtime
+#[derive(Clone)]
+pub struct DynamicLookupTable {
+    entries: TableEntries,
+}
+
+//Raw contents of a table, as they are stored on disk
+pub trait TableData {
+    fn table_type(&self) -> TableType;
+    fn entries(&self) -> &[u8];
+    //Number of coordinates the entries describe
+    fn size(&self) -> usize;
+
+    fn stats(&self) -> TableStats {
+        TableStats::new(self.table_type(), self.size(), self.entries())
+    }
+}
+
+impl <const C_SIZE: usize, C: Coord<C_SIZE>> TableData for LookupTable<C_SIZE, C> {
+    fn table_type(&self) -> TableType {
+        if self.compressed {
             TableType::Compressed
         } else {
             TableType::Uncompressed
-        };
-        let mut ser = vec![VERSION, table_type.to_u8().unwrap()];
-        ser.extend(self.entries.iter());
-        ser
+        }
+    }
+
+    fn entries(&self) -> &[u8] {
+        &self.entries
+    }
+
+    fn size(&self) -> usize {
+        C_SIZE
     }
 }
 
-impl <const C_SIZE: usize, C: Coord<C_SIZE>> Into<Vec<u8>> for &NissLookupTable<C_SIZE, C> {
-    fn into(self) -> Vec<u8> {
-        let mut ser = vec![VERSION, TableType::Niss.to_u8().unwrap()];
-        ser.extend(self.entries.iter());
-        ser
+impl <const C_SIZE: usize, C: Coord<C_SIZE>> TableData for NissLookupTable<C_SIZE, C> {
+    fn table_type(&self) -> TableType {
+        TableType::Niss
+    }
+
+    fn entries(&self) -> &[u8] {
+        &self.entries
+    }
+
+    fn size(&self) -> usize {
+        C_SIZE
     }
 }
 
+impl <const C_SIZE: usize, C: Coord<C_SIZE>> TableData for ModThreeLookupTable<C_SIZE, C> {
+    fn table_type(&self) -> TableType {
+        TableType::ModThree
+    }
+
+    fn entries(&self) -> &[u8] {
+        &self.entries
+    }
+
+    fn size(&self) -> usize {
+        C_SIZE
+    }
+}
+
+impl TableData for DynamicLookupTable {
+    fn table_type(&self) -> TableType {
+        TableType::Uncompressed
+    }
+
+    fn entries(&self) -> &[u8] {
+        &self.entries
+    }
+
+    fn size(&self) -> usize {
+        self.entries.len()
+    }
+}
+
+//Name of the coordinate type without its module path
+fn coord_name<C>() -> &'static str {
+    let name = std::any::type_name::<C>();
+    name.rsplit("::").next().unwrap_or(name)
+}
+
 impl<const C_SIZE: usize, C: Coord<C_SIZE>> LookupTable<C_SIZE, C> {
     pub fn new(compressed: bool) -> Self {
         let entries = if compressed {
-            vec![0xFF; (C_SIZE + 1) / 2].into_boxed_slice().try_into().unwrap()
+            TableEntries::filled(C_SIZE.div_ceil(2), 0xFF)
         } else {
-            vec![0xFF; C_SIZE].into_boxed_slice().try_into().unwrap()
+            TableEntries::filled(C_SIZE, 0xFF)
         };
         LookupTable {
             entries,
@@ -78,15 +208,12 @@ impl<const C_SIZE: usize, C: Coord<C_SIZE>> LookupTable<C_SIZE, C> {
         }
     }
 
-    pub fn get_bytes(&self) -> Vec<u8> {
-        let table_type = if self.compressed {
-            TableType::Compressed
-        } else {
-            TableType::Uncompressed
-        };
-        let mut ser = vec![VERSION, table_type.to_u8().unwrap()];
-        ser.extend(self.entries.iter());
-        ser
+    pub fn table_id(move_set: &MoveSet, generator: u32) -> TableId {
+        TableId::new(coord_name::<C>(), C_SIZE, move_set, generator)
+    }
+
+    pub fn is_mapped(&self) -> bool {
+        self.entries.is_mapped()
     }
 
     pub fn get(&self, id: C) -> u8 {
@@ -116,7 +243,7 @@ impl<const C_SIZE: usize, C: Coord<C_SIZE>> LookupTable<C_SIZE, C> {
 impl<const C_SIZE: usize, C: Coord<C_SIZE>> NissLookupTable<C_SIZE, C> {
     pub fn new() -> Self {
         NissLookupTable {
-            entries: vec![0xFF; C_SIZE].into_boxed_slice().try_into().unwrap(),
+            entries: TableEntries::filled(C_SIZE, 0xFF),
             coord_type: PhantomData,
         }
     }
@@ -125,10 +252,12 @@ impl<const C_SIZE: usize, C: Coord<C_SIZE>> NissLookupTable<C_SIZE, C> {
         0x0F
     }
 
-    pub fn get_bytes(&self) -> Vec<u8> {
-        let mut ser = vec![VERSION, TableType::Niss.to_u8().unwrap()];
-        ser.extend(self.entries.iter());
-        ser
+    pub fn table_id(move_set: &MoveSet, generator: u32) -> TableId {
+        TableId::new(coord_name::<C>(), C_SIZE, move_set, generator)
+    }
+
+    pub fn is_mapped(&self) -> bool {
+        self.entries.is_mapped()
     }
 
     pub fn get(&self, id: C) -> (u8, u8) {
@@ -148,6 +277,97 @@ impl<const C_SIZE: usize, C: Coord<C_SIZE>> NissLookupTable<C_SIZE, C> {
     }
 }
 
+impl<const C_SIZE: usize, C: Coord<C_SIZE>> ModThreeLookupTable<C_SIZE, C> {
+    const EMPTY: u8 = 0x03;
+
+    pub fn new() -> Self {
+        ModThreeLookupTable {
+            entries: TableEntries::filled(C_SIZE.div_ceil(4), 0xFF),
+            coord_type: PhantomData,
+        }
+    }
+
+    pub fn table_id(move_set: &MoveSet, generator: u32) -> TableId {
+        TableId::new(coord_name::<C>(), C_SIZE, move_set, generator)
+    }
+
+    pub fn is_mapped(&self) -> bool {
+        self.entries.is_mapped()
+    }
+
+    //The distance modulo 3, or EMPTY if the coordinate can't be reached
+    pub fn get(&self, id: C) -> u8 {
+        let id: usize = id.into();
+        (self.entries[id >> 2] >> ((id & 3) << 1)) & 0x03
+    }
+
+    //Takes the full distance and stores it modulo 3
+    pub fn set(&mut self, id: C, distance: u8) {
+        let id: usize = id.into();
+        let shift = (id & 3) << 1;
+        let value = self.entries[id >> 2] & !(0x03 << shift);
+        self.entries[id >> 2] = value | ((distance % 3) << shift);
+    }
+
+    //Recovers the exact distance of the cube by repeatedly applying a move that gets one step closer to the goal.
+    //The moves have to be the auxiliary and regular moves of the move set the table was generated with.
+    //Returns u8::MAX if the coordinate can't be reached
+    pub fn distance(&self, cube: &Cube333, moves: &[Turn333]) -> u8 where C: for<'a> From<&'a Cube333> {
+        let mut cube = *cube;
+        let mut value = self.get(C::from(&cube));
+        if value == Self::EMPTY {
+            return u8::MAX;
+        }
+        let mut distance = 0u8;
+        //Goal states are the only coordinates without a neighbour that is one move closer
+        'walk: while distance < u8::MAX - 1 {
+            let closer = (value + 2) % 3;
+            for m in moves {
+                let mut next = cube;
+                next.turn(*m);
+                if self.get(C::from(&next)) == closer {
+                    cube = next;
+                    value = closer;
+                    distance += 1;
+                    continue 'walk;
+                }
+            }
+            break;
+        }
+        distance
+    }
+}
+
+impl<const C_SIZE: usize, C: Coord<C_SIZE>> Default for ModThreeLookupTable<C_SIZE, C> {
+    fn default() -> Self {
+        Self::new()
+    }
+}
+
+impl DynamicLookupTable {
+    pub fn new(size: usize) -> Self {
+        DynamicLookupTable {
+            entries: TableEntries::filled(size, 0xFF),
+        }
+    }
+
+    pub fn len(&self) -> usize {
+        self.entries.len()
+    }
+
+    pub fn is_empty(&self) -> bool {
+        self.entries.is_empty()
+    }
+
+    pub fn get(&self, id: usize) -> u8 {
+        self.entries[id]
+    }
+
+    pub fn set(&mut self, id: usize, entry: u8) {
+        self.entries[id] = entry
+    }
+}
+
 pub trait EmptyVal {
     fn empty_val(&self) -> u8;
 }
@@ -168,83 +388,151 @@ impl <const C_SIZE: usize, C: Coord<C_SIZE>> EmptyVal for NissLookupTable<C_SIZE
     }
 }
 
+impl <const C_SIZE: usize, C: Coord<C_SIZE>> EmptyVal for ModThreeLookupTable<C_SIZE, C> {
+    fn empty_val(&self) -> u8 {
+        Self::EMPTY
+    }
+}
+
+impl EmptyVal for DynamicLookupTable {
+    fn empty_val(&self) -> u8 {
+        0xFF
+    }
+}
+
 #[cfg(feature = "fs")]
 pub trait SaveToDisk {
-    fn save_to_disk(&self, puzzle_id: &str, table_type: &str) -> std::io::Result<()>;
+    fn save_to_file(&self, path: &Path, id: &TableId, compression: Compression) -> Result<(), Error>;
+
+    //Saves the table to the default TableStore
+    fn save_to_disk(&self, puzzle_id: &str, table_type: &str, id: &TableId, compression: Compression) -> Result<(), Error> where Self: TableData + Sized {
+        TableStore::from_env().save(puzzle_id, table_type, self, id, compression).map(|_| ())
+    }
 }
 
-#[cfg(feature = "fs")]
+//Loading from bytes doesn't need the fs feature, e.g. for tables embedded in the binary or cached by a browser
 pub trait LoadFromDisk {
-    fn load(data: Box<Vec<u8>>) -> Result<Self, String> where Self: Sized;
+    //Creates the table from the entries of a file with the given type
+    fn from_entries(table_type: TableType, entries: TableEntries) -> Result<Self, Error> where Self: Sized;
 
-    fn load_from_disk(puzzle_id: &str, table_type: &str) -> Result<Self, String> where Self: Sized {
-        let mut dir = home_dir().unwrap();
-        dir.push(".cubelib");
-        dir.push("tables");
-        dir.push(puzzle_id);
-        dir.push(format!("{table_type}.tbl"));
-        debug!("Loading {puzzle_id} {table_type} table from {dir:?}");
-        let mut file = File::open(dir).map_err(|e|e.to_string())?;
-        let mut buffer = Box::new(Vec::new());
-        file.read_to_end(&mut buffer).map_err(|e|e.to_string())?;
-        Self::load(buffer)
+    //Loads a table file. Files that were written for a different table or are corrupt are rejected
+    fn load(data: Box<Vec<u8>>, id: &TableId) -> Result<Self, Error> where Self: Sized {
+        Self::load_bytes(&data, id)
+    }
+
+    //Same as load, for the contents of a table file that aren't owned
+    fn load_bytes(data: &[u8], id: &TableId) -> Result<Self, Error> where Self: Sized {
+        let (header, decompressed) = table_header::deserialize(data, id)?;
+        let entries = match decompressed {
+            Some(entries) => entries.into_boxed_slice(),
+            None => data[HEADER_SIZE..].into(),
+        };
+        Self::from_entries(header.table_type, TableEntries::Owned(entries))
+    }
+
+    //Maps the table file into memory instead of reading it. Several processes using the same table share a single copy.
+    //Compressed tables can't be mapped and are decompressed into memory instead
+    #[cfg(feature = "fs")]
+    fn load_from_file(path: &Path, id: &TableId) -> Result<Self, Error> where Self: Sized {
+        debug!("Mapping table from {path:?}");
+        let file = File::open(path)?;
+        //Safety: SaveToDisk replaces table files instead of writing to them, so the mapped file never changes
+        let mmap = unsafe { Mmap::map(&file)? };
+        //Lookups jump all over the table, so reading ahead only wastes memory
+        #[cfg(unix)]
+        mmap.advise(memmap2::Advice::Random)?;
+        let (header, decompressed) = table_header::deserialize(&mmap, id)?;
+        let entries = match decompressed {
+            Some(entries) => TableEntries::Owned(entries.into_boxed_slice()),
+            None => TableEntries::Mapped(Arc::new(mmap)),
+        };
+        Self::from_entries(header.table_type, entries)
+    }
+
+    //Loads the table from the default TableStore
+    #[cfg(feature = "fs")]
+    fn load_from_disk(puzzle_id: &str, table_type: &str, id: &TableId) -> Result<Self, Error> where Self: Sized {
+        TableStore::from_env().load(puzzle_id, table_type, id)
     }
 }
 
-#[cfg(feature = "fs")]
 impl <const C_SIZE: usize, C: Coord<C_SIZE>> LoadFromDisk for LookupTable<C_SIZE, C> {
-    fn load(mut data: Box<Vec<u8>>) -> Result<Self, String> {
-        let version = data[0];
-        if version != VERSION {
-            return Err("Invalid version".to_string())
+    fn from_entries(table_type: TableType, entries: TableEntries) -> Result<Self, Error> {
+        match table_type {
+            TableType::Niss => return Err(Error::CorruptTable("Expected a normal table, found a NISS table".to_string())),
+            TableType::ModThree => return Err(Error::CorruptTable("Expected a normal table, found a mod 3 table".to_string())),
+            TableType::Uncompressed | TableType::Compressed => {}
         }
-        let table_type: TableType = TableType::from_u8(data[1]).unwrap();
-        assert_ne!(table_type, TableType::Niss);
-        data.drain(0..2);
-        if table_type == TableType::Compressed {
-            assert_eq!(data.len(), (C_SIZE + 1) / 2);
-        } else {
-            assert_eq!(data.len(), C_SIZE);
+        let expected_len = table_header::entries_len(table_type, C_SIZE);
+        if entries.len() != expected_len {
+            return Err(Error::CorruptTable(format!("Invalid table size {}, expected {expected_len}", entries.len())))
         }
 
         Ok(LookupTable {
-            entries: data.into_boxed_slice().try_into().unwrap(),
+            entries,
             coord_type: PhantomData,
             compressed: table_type == TableType::Compressed
         })
     }
 }
 
-#[cfg(feature = "fs")]
 impl <const C_SIZE: usize, C: Coord<C_SIZE>> LoadFromDisk for NissLookupTable<C_SIZE, C> {
-    fn load(mut data: Box<Vec<u8>>) -> Result<Self, String> {
-        let version = data[0];
-        if version != VERSION {
-            return Err("Invalid version".to_string())
+    fn from_entries(table_type: TableType, entries: TableEntries) -> Result<Self, Error> {
+        if table_type != TableType::Niss {
+            return Err(Error::CorruptTable("Expected a NISS table".to_string()))
+        }
+        if entries.len() != C_SIZE {
+            return Err(Error::CorruptTable(format!("Invalid table size {}, expected {C_SIZE}", entries.len())))
         }
-        let table_type: TableType = TableType::from_u8(data[1]).unwrap();
-        assert_eq!(table_type, TableType::Niss);
-        data.drain(0..2);
-        assert_eq!(data.len(), C_SIZE);
 
         Ok(NissLookupTable {
-            entries: data.into_boxed_slice().try_into().unwrap(),
+            entries,
+            coord_type: PhantomData,
+        })
+    }
+}
+
+impl <const C_SIZE: usize, C: Coord<C_SIZE>> LoadFromDisk for ModThreeLookupTable<C_SIZE, C> {
+    fn from_entries(table_type: TableType, entries: TableEntries) -> Result<Self, Error> {
+        if table_type != TableType::ModThree {
+            return Err(Error::CorruptTable("Expected a mod 3 table".to_string()))
+        }
+        let expected_len = table_header::entries_len(table_type, C_SIZE);
+        if entries.len() != expected_len {
+            return Err(Error::CorruptTable(format!("Invalid table size {}, expected {expected_len}", entries.len())))
+        }
+
+        Ok(ModThreeLookupTable {
+            entries,
             coord_type: PhantomData,
         })
     }
 }
 
+impl LoadFromDisk for DynamicLookupTable {
+    fn from_entries(table_type: TableType, entries: TableEntries) -> Result<Self, Error> {
+        if table_type != TableType::Uncompressed {
+            return Err(Error::CorruptTable("Expected an uncompressed table".to_string()))
+        }
+        Ok(DynamicLookupTable {
+            entries,
+        })
+    }
+}
+
+//Tables are written to a temporary file that replaces the old one, so processes that still map the old file keep a consistent copy
 #[cfg(feature = "fs")]
-impl <T> SaveToDisk for T where for<'a> &'a T: Into<Vec<u8>> {
-    fn save_to_disk(&self, puzzle_id: &str, table_type: &str) -> std::io::Result<()> {
-        let mut dir = home_dir().unwrap();
-        dir.push(".cubelib");
-        dir.push("tables");
-        dir.push(puzzle_id);
-        fs::create_dir_all(dir.clone())?;
-        dir.push(format!("{table_type}.tbl"));
-        let mut file = File::create(dir)?;
-        file.write_all(Into::<Vec<u8>>::into(self).as_slice())?;
+impl <T: TableData> SaveToDisk for T {
+    fn save_to_file(&self, path: &Path, id: &TableId, compression: Compression) -> Result<(), Error> {
+        if let Some(dir) = path.parent() {
+            fs::create_dir_all(dir)?;
+        }
+        let mut tmp = path.as_os_str().to_owned();
+        tmp.push(format!(".{}.tmp", std::process::id()));
+        let mut file = File::create(&tmp)?;
+        file.write_all(&table_header::serialize(id, self.table_type(), compression, self.entries()))?;
+        file.sync_all()?;
+        fs::rename(tmp, path)?;
         Ok(())
     }
 }
@@ -252,143 +540,393 @@ impl <T> SaveToDisk for T where for<'a> &'a T: Into<Vec<u8>> {
 pub fn generate<
     const COORD_SIZE: usize,
     Mapper,
-    Table: EmptyVal,
+    Table,
     Init,
-    Getter,
     Setter,
-    CoordParam: Coord<COORD_SIZE> + Copy + Hash + Eq + Debug,
+    CoordParam: Coord<COORD_SIZE> + Debug + Send + Sync,
 >(
     move_set: &MoveSet,
     mapper: &Mapper,
     init: &Init,
-    getter: &Getter,
     setter: &Setter,
 ) -> Table
 where
-    Mapper: Fn(&Cube333) -> CoordParam,
+    Mapper: Fn(&Cube333) -> CoordParam + Sync,
+    Init: Fn() -> Table,
+    Setter: Fn(&mut Table, CoordParam, u8),
+{
+    generate_sized(CoordParam::size(), move_set, mapper, init, setter)
+}
+
+//Same as generate, for coordinates that only know their size at runtime
+pub fn generate_sized<
+    const COORD_SIZE: usize,
+    Mapper,
+    Table,
+    Init,
+    Setter,
+    CoordParam: Coord<COORD_SIZE> + Debug + Send + Sync,
+>(
+    size: usize,
+    move_set: &MoveSet,
+    mapper: &Mapper,
+    init: &Init,
+    setter: &Setter,
+) -> Table
+where
+    Mapper: Fn(&Cube333) -> CoordParam + Sync,
+    Init: Fn() -> Table,
+    Setter: Fn(&mut Table, CoordParam, u8),
+{
+    generate_with_rotations(size, move_set, &[Cube333::default()], &Transformation333::ALL, mapper, init, setter)
+}
+
+//Same as generate_sized, for goals that are reached by more cubes than the solved one, e.g. goals that only care about
+//the orientation of some pieces. Every goal state has distance 0. Unlike the solved cube, the goal states aren't rotated
+pub fn generate_from_goal_states<
+    const COORD_SIZE: usize,
+    Mapper,
+    Table,
+    Init,
+    Setter,
+    CoordParam: Coord<COORD_SIZE> + Debug + Send + Sync,
+>(
+    size: usize,
+    move_set: &MoveSet,
+    goal_states: &[Cube333],
+    mapper: &Mapper,
+    init: &Init,
+    setter: &Setter,
+) -> Table
+where
+    Mapper: Fn(&Cube333) -> CoordParam + Sync,
+    Init: Fn() -> Table,
+    Setter: Fn(&mut Table, CoordParam, u8),
+{
+    generate_with_rotations(size, move_set, goal_states, &[], mapper, init, setter)
+}
+
+fn generate_with_rotations<
+    const COORD_SIZE: usize,
+    Mapper,
+    Table,
+    Init,
+    Setter,
+    CoordParam: Coord<COORD_SIZE> + Debug + Send + Sync,
+>(
+    size: usize,
+    move_set: &MoveSet,
+    goal_states: &[Cube333],
+    rotations: &[Transformation333],
+    mapper: &Mapper,
+    init: &Init,
+    setter: &Setter,
+) -> Table
+where
+    Mapper: Fn(&Cube333) -> CoordParam + Sync,
     Init: Fn() -> Table,
     Setter: Fn(&mut Table, CoordParam, u8),
-    Getter: Fn(&Table, CoordParam) -> u8
 {
-    let start = Cube333::default();
-    let mut visited = HashMap::new();
-    let mut to_check = vec![start.clone()];
-    visited.insert(mapper(&start), start);
-    while !to_check.is_empty() {
-        to_check = pre_gen_coset_0(&move_set, mapper, &mut visited, &to_check);
+    if parallel_generation() {
+        generate_with_move_table(size, move_set, goal_states, rotations, mapper, init, setter)
+    } else {
+        generate_with_cubes(size, move_set, goal_states, rotations, mapper, init, setter)
     }
+}
 
-    let mut to_check = HashMap::new();
+//Same as generate, for coordinates that combine two parts that are turned independently, like a corner and an edge
+//coordinate. The move table is built from the much smaller tables of the parts, so no cubes are turned per coordinate
+//and it's used on every platform, no matter how many CPUs there are
+pub fn generate_from_parts<
+    const COORD_SIZE: usize,
+    First,
+    Second,
+    Table,
+    Init,
+    Setter,
+    CoordParam: Coord<COORD_SIZE> + Debug + Send + Sync,
+>(
+    move_set: &MoveSet,
+    first: (usize, &First),
+    second: (usize, &Second),
+    combine: fn(usize, usize) -> CoordParam,
+    init: &Init,
+    setter: &Setter,
+) -> Table
+where
+    First: Fn(&Cube333) -> usize + Sync,
+    Second: Fn(&Cube333) -> usize + Sync,
+    Init: Fn() -> Table,
+    Setter: Fn(&mut Table, CoordParam, u8),
+{
+    let move_table = MoveTable::from_parts(CoordParam::size(), move_set, first, second, combine);
+    if move_table.len() != CoordParam::size() {
+        warn!("Expected {} cubes in table but got {}. The coordinate may be malformed", CoordParam::size(), move_table.len());
+    }
+    generate_from_move_table(&move_table, init, setter)
+}
+
+//Exploring a move table with cubes only pays off if it can be split across threads. It also needs a lot more memory than the table
+fn parallel_generation() -> bool {
+    !cfg!(target_arch = "wasm32") && std::thread::available_parallelism().map_or(1, |n| n.get()) > 1
+}
+
+fn generate_with_move_table<
+    const COORD_SIZE: usize,
+    Mapper,
+    Table,
+    Init,
+    Setter,
+    CoordParam: Coord<COORD_SIZE> + Debug + Send + Sync,
+>(
+    size: usize,
+    move_set: &MoveSet,
+    goal_states: &[Cube333],
+    rotations: &[Transformation333],
+    mapper: &Mapper,
+    init: &Init,
+    setter: &Setter,
+) -> Table
+where
+    Mapper: Fn(&Cube333) -> CoordParam + Sync,
+    Init: Fn() -> Table,
+    Setter: Fn(&mut Table, CoordParam, u8),
+{
+    let move_table = MoveTable::build(size, move_set, goal_states, rotations, mapper);
+    if move_table.len() != size {
+        warn!("Expected {size} cubes in table but got {}. The coordinate may be malformed", move_table.len());
+    }
+    generate_from_move_table(&move_table, init, setter)
+}
+
+//Breadth-first search that turns one cube per coordinate of the current depth and fills the table as it goes
+fn generate_with_cubes<
+    const COORD_SIZE: usize,
+    Mapper,
+    Table,
+    Init,
+    Setter,
+    CoordParam: Coord<COORD_SIZE> + Debug,
+>(
+    size: usize,
+    move_set: &MoveSet,
+    goal_states: &[Cube333],
+    rotations: &[Transformation333],
+    mapper: &Mapper,
+    init: &Init,
+    setter: &Setter,
+) -> Table
+where
+    Mapper: Fn(&Cube333) -> CoordParam,
+    Init: Fn() -> Table,
+    Setter: Fn(&mut Table, CoordParam, u8),
+{
     let mut table = init();
-    for (start_coord, start_cube) in visited {
-        setter(&mut table, start_coord, 0);
-        to_check.insert(start_coord, start_cube);
+    let mut distances = vec![u8::MAX; size];
+
+    //The goal states are the given cubes and everything reachable from them with auxiliary moves and rotations
+    let mut to_check = HashMap::new();
+    let mut layer = vec![];
+    for cube in goal_states {
+        if let Entry::Vacant(entry) = to_check.entry(mapper(cube)) {
+            entry.insert(*cube);
+            layer.push(*cube);
+        }
+    }
+    while let Some(cube) = layer.pop() {
+        let aux_moves = move_set.aux_moves.iter().map(|m| {
+            let mut cube = cube;
+            cube.turn(*m);
+            cube
+        });
+        let rotations = rotations.iter().map(|t| {
+            let mut cube = cube;
+            cube.transform(*t);
+            cube
+        });
+        for cube in aux_moves.chain(rotations) {
+            if let Entry::Vacant(entry) = to_check.entry(mapper(&cube)) {
+                entry.insert(cube);
+                layer.push(cube);
+            }
+        }
     }
     if to_check.len() > 1 {
         debug!("Found {} variations of the goal state", to_check.len());
     }
-    let mut total_checked = 0;
-    for depth in 0..20 {
-        total_checked += to_check.len();
+    for coord in to_check.keys() {
+        distances[coord.val()] = 0;
+        setter(&mut table, *coord, 0);
+    }
+
+    let mut filled = 0;
+    for depth in 0..u8::MAX - 1 {
+        filled += to_check.len();
         debug!(
-            "Checked {:width$}/{} cubes at depth {depth} (new {})",
-            total_checked,
-            CoordParam::size(),
+            "Filled {:width$}/{} coordinates at depth {depth} (new {})",
+            filled,
+            size,
             to_check.len(),
-            width = CoordParam::size().to_string().len(),
+            width = size.to_string().len(),
         );
-        to_check = fill_table(&move_set, &mut table, depth, mapper, getter, setter, to_check);
+        let mut next = HashMap::new();
+        for cube in to_check.into_values() {
+            for m in move_set.aux_moves.iter().chain(move_set.st_moves.iter()) {
+                let mut cube = cube;
+                cube.turn(*m);
+                let coord = mapper(&cube);
+                if distances[coord.val()] == u8::MAX {
+                    distances[coord.val()] = depth + 1;
+                    setter(&mut table, coord, depth + 1);
+                    next.insert(coord, cube);
+                }
+            }
+        }
+        to_check = next;
         if to_check.is_empty() {
             break;
         }
     }
-    total_checked += to_check.len();
-    if total_checked != CoordParam::size() {
-        warn!(
-            "Expected {} cubes in table but got {total_checked}. The coordinate may be malformed",
-            CoordParam::size()
-        );
+    if filled != size {
+        warn!("Expected {size} cubes in table but got {filled}. The coordinate may be malformed");
     }
     table
 }
 
-fn pre_gen_coset_0<
+//Generates a table that stores distances modulo 3. Coordinates with moves that can't be undone would make
+//ModThreeLookupTable::distance return wrong values, so they get a warning
+pub fn generate_mod_three<
     const COORD_SIZE: usize,
     Mapper,
-    CoordParam: Coord<COORD_SIZE> + Copy + Hash + Eq + Debug,
+    CoordParam: Coord<COORD_SIZE> + Debug + Send + Sync,
 >(
     move_set: &MoveSet,
     mapper: &Mapper,
-    visited: &mut HashMap<CoordParam, Cube333>,
-    to_check: &Vec<Cube333>,
-) -> Vec<Cube333>
+) -> ModThreeLookupTable<COORD_SIZE, CoordParam>
 where
-    Mapper: Fn(&Cube333) -> CoordParam,
+    Mapper: Fn(&Cube333) -> CoordParam + Sync,
 {
-    let mut check_next = vec![];
-    for cube in to_check {
-        for m in move_set.aux_moves.iter().cloned() {
-            let mut cube = cube.clone();
-            cube.turn(m);
-            let coord = mapper(&cube);
-            if visited.contains_key(&coord) {
-                continue;
-            }
-            visited.insert(coord, cube.clone());
-            check_next.push(cube);
-        }
-        for m in Transformation333::ALL {
-            let mut cube = cube.clone();
-            cube.transform(m);
-            let coord = mapper(&cube);
-            if visited.contains_key(&coord) {
-                continue;
-            }
-            visited.insert(coord, cube.clone());
-            check_next.push(cube);
-        }
+    let move_table = MoveTable::new(move_set, mapper);
+    if move_table.len() != COORD_SIZE {
+        warn!("Expected {COORD_SIZE} cubes in table but got {}. The coordinate may be malformed", move_table.len());
     }
-    check_next
+    if !move_table.is_symmetric() {
+        warn!("Not every move can be undone on the coordinate. Distances can't be recovered from a mod 3 table");
+    }
+    generate_from_move_table(&move_table, &ModThreeLookupTable::new, &|table: &mut ModThreeLookupTable<COORD_SIZE, CoordParam>, coord, distance| table.set(coord, distance))
 }
 
-fn fill_table<
+//Fills a table with the distances of the move table, without touching any cubes
+pub fn generate_from_move_table<
     const COORD_SIZE: usize,
-    Mapper,
-    Table: EmptyVal,
-    Getter,
+    Table,
+    Init,
     Setter,
-    CoordParam: Coord<COORD_SIZE> + Copy + Hash + Eq + Debug,
+    CoordParam: Coord<COORD_SIZE> + Debug + Send + Sync,
 >(
-    move_set: &MoveSet,
-    table: &mut Table,
-    depth: u8,
-    mapper: &Mapper,
-    getter: &Getter,
+    move_table: &MoveTable<COORD_SIZE, CoordParam>,
+    init: &Init,
     setter: &Setter,
-    to_check: HashMap<CoordParam, Cube333>,
-) -> HashMap<CoordParam, Cube333>
+) -> Table
 where
-    Mapper: Fn(&Cube333) -> CoordParam,
+    Init: Fn() -> Table,
     Setter: Fn(&mut Table, CoordParam, u8),
-    Getter: Fn(&Table, CoordParam) -> u8
 {
-    let mut next_cubes: HashMap<CoordParam, Cube333> = HashMap::new();
-    for (_coord, cube) in to_check.into_iter() {
-        for m in move_set
-            .aux_moves
-            .into_iter()
-            .chain(move_set.st_moves.into_iter())
-            .cloned()
-        {
-            let mut cube = cube.clone();
-            cube.turn(m);
-            let coord = mapper(&cube);
-            let stored = getter(&table, coord);
-            if stored == table.empty_val() {
-                setter(table, coord, depth + 1);
-                next_cubes.insert(coord, cube);
+    let mut table = init();
+    for (coord, distance) in move_table.coords().iter().zip(move_table.distances()) {
+        setter(&mut table, *coord, distance);
+    }
+    table
+}
+
+#[cfg(all(test, feature = "333htr"))]
+mod tests {
+    use std::collections::HashMap;
+
+    use crate::cube::{Cube333, Transformation333};
+    use crate::cube::turn::{TransformableMut, TurnableMut};
+    use crate::solver::moveset::MoveSet;
+    use crate::steps::coord::Coord;
+    use crate::steps::dr::coords::{DRUDEOFB_SIZE, DRUDEOFBCoord};
+    use crate::steps::dr::dr_config::{DR_UD_EO_FB_MOVESET, DRPruningTable, HTR_DR_UD_MOVESET};
+    use crate::steps::eo::coords::EOCoordFB;
+    use crate::steps::eo::eo_config::{EO_FB_MOVESET, EOPruningTable};
+    #[cfg(feature = "333finish")]
+    use crate::steps::finish::coords::{HTR_FINISH_SIZE, HTRFinishCoord};
+    #[cfg(feature = "333finish")]
+    use crate::steps::finish::finish_config::HTR_FINISH_MOVESET;
+    use crate::steps::htr::coords::{HTRDRUD_SIZE, HTRDRUDCoord};
+    use crate::steps::htr::htr_config::HTRPruningTable;
+
+    use super::{generate, generate_from_parts, generate_mod_three, generate_with_cubes, generate_with_move_table, LookupTable, TableData};
+
+    fn assert_same_tables<const C_SIZE: usize, C, T>(move_set: &MoveSet, mapper: &(dyn Fn(&Cube333) -> C + Sync), init: &dyn Fn() -> T, setter: &dyn Fn(&mut T, C, u8))
+        where C: Coord<C_SIZE> + std::fmt::Debug, T: TableData {
+        let with_cubes = generate_with_cubes(C_SIZE, move_set, &[Cube333::default()], &Transformation333::ALL, &mapper, &init, &setter);
+        let with_move_table = generate_with_move_table(C_SIZE, move_set, &[Cube333::default()], &Transformation333::ALL, &mapper, &init, &setter);
+        assert!(with_cubes.entries() == with_move_table.entries());
+    }
+
+    #[test]
+    fn move_table_generates_the_same_tables() {
+        assert_same_tables::<2048, _, _>(&EO_FB_MOVESET, &|c| EOCoordFB::from(c), &|| EOPruningTable::new(false), &|table, coord, val| table.set(coord, val));
+        assert_same_tables(&DR_UD_EO_FB_MOVESET, &|c| DRUDEOFBCoord::from(c), &|| DRPruningTable::new(false), &|table, coord, val| table.set(coord, val));
+        assert_same_tables(&HTR_DR_UD_MOVESET, &|c| HTRDRUDCoord::from(c), &HTRPruningTable::new, &|table, coord, val| table.set(coord, val));
+    }
+
+    #[test]
+    fn tables_from_parts_are_the_same() {
+        let with_cubes = generate_with_cubes(DRUDEOFB_SIZE, &DR_UD_EO_FB_MOVESET, &[Cube333::default()], &Transformation333::ALL, &|c: &Cube333| DRUDEOFBCoord::from(c), &|| DRPruningTable::new(false), &|table, coord, val| table.set(coord, val));
+        let from_parts = generate_from_parts(&DR_UD_EO_FB_MOVESET, (DRUDEOFBCoord::CORNERS_SIZE, &DRUDEOFBCoord::corners), (DRUDEOFBCoord::EDGES_SIZE, &DRUDEOFBCoord::edges), DRUDEOFBCoord::from_parts, &|| DRPruningTable::new(false), &|table: &mut DRPruningTable, coord, val| table.set(coord, val));
+        assert!(with_cubes.entries() == from_parts.entries());
+
+        let with_cubes = generate_with_cubes(HTRDRUD_SIZE, &HTR_DR_UD_MOVESET, &[Cube333::default()], &Transformation333::ALL, &|c: &Cube333| HTRDRUDCoord::from(c), &HTRPruningTable::new, &|table, coord, val| table.set(coord, val));
+        let from_parts = generate_from_parts(&HTR_DR_UD_MOVESET, (HTRDRUDCoord::CORNERS_SIZE, &HTRDRUDCoord::corners), (HTRDRUDCoord::EDGES_SIZE, &HTRDRUDCoord::edges), HTRDRUDCoord::from_parts, &HTRPruningTable::new, &|table: &mut HTRPruningTable, coord, val| table.set(coord, val));
+        assert!(with_cubes.entries() == from_parts.entries());
+    }
+
+    //One cube for every coordinate that the moves or rotations of the solved cube lead to
+    fn cubes<const C_SIZE: usize, C: Coord<C_SIZE>>(move_set: &MoveSet, mapper: &dyn Fn(&Cube333) -> C) -> HashMap<C, Cube333> {
+        let mut cubes = HashMap::new();
+        cubes.insert(mapper(&Cube333::default()), Cube333::default());
+        let mut layer = vec![Cube333::default()];
+        while let Some(cube) = layer.pop() {
+            let turns = move_set.aux_moves.iter().chain(move_set.st_moves.iter()).map(|m| {
+                let mut cube = cube;
+                cube.turn(*m);
+                cube
+            });
+            let rotations = Transformation333::ALL.into_iter().map(|t| {
+                let mut cube = cube;
+                cube.transform(t);
+                cube
+            });
+            for next in turns.chain(rotations) {
+                cubes.entry(mapper(&next)).or_insert_with(|| {
+                    layer.push(next);
+                    next
+                });
             }
         }
+        cubes
+    }
+
+    fn assert_mod_three_distances<const C_SIZE: usize, C>(move_set: &MoveSet, mapper: &(dyn Fn(&Cube333) -> C + Sync))
+        where C: Coord<C_SIZE> + std::fmt::Debug + for<'a> From<&'a Cube333> {
+        let table = generate(move_set, &mapper, &|| LookupTable::<C_SIZE, C>::new(false), &|table, coord, val| table.set(coord, val));
+        let mod_three = generate_mod_three(move_set, &mapper);
+        let moves: Vec<_> = move_set.aux_moves.iter().chain(move_set.st_moves.iter()).cloned().collect();
+        let cubes = cubes(move_set, &mapper);
+        assert_eq!(cubes.len(), C_SIZE);
+        for (coord, cube) in cubes {
+            assert_eq!(mod_three.distance(&cube, &moves), table.get(coord), "{coord:?}");
+        }
+    }
+
+    #[test]
+    fn mod_three_tables_recover_distances() {
+        assert_mod_three_distances::<2048, _>(&EO_FB_MOVESET, &|c| EOCoordFB::from(c));
+        #[cfg(feature = "333finish")]
+        assert_mod_three_distances::<{ HTR_FINISH_SIZE }, _>(&HTR_FINISH_MOVESET, &|c| HTRFinishCoord::from(c));
     }
-    next_cubes
 }
@@ -7,20 +7,49 @@ use crate::solver::solution::Solution;
 use crate::steps;
 
 pub mod lookup_table;
+pub mod table_header;
+pub mod table_stats;
+#[cfg(feature = "fs")]
+pub mod table_store;
 pub mod stream;
 pub mod solution;
 pub mod df_search;
 pub mod moveset;
+pub mod move_table;
+pub mod bounded;
+pub mod scoring;
 use crate::solver::df_search::CancelToken;
 use crate::steps::step::{DefaultStepOptions, Step};
 
 pub fn solve_steps<'a>(puzzle: Cube333, steps: &'a Vec<(Step<'a>, DefaultStepOptions)>, cancel_token: &'a CancelToken) -> impl Iterator<Item = Solution> + 'a {
-    let first_step: Box<dyn Iterator<Item = Solution>> = Box::new(vec![Solution::new()].into_iter());
+    solve_steps_from(puzzle, Solution::new(), steps, cancel_token)
+}
+
+//Like solve_steps, but continues the given partial solution instead of starting from an empty one.
+//The puzzle is the scrambled cube, without the moves of the partial solution applied.
+pub fn solve_steps_from<'a>(puzzle: Cube333, solution: Solution, steps: &'a Vec<(Step<'a>, DefaultStepOptions)>, cancel_token: &'a CancelToken) -> impl Iterator<Item = Solution> + 'a {
+    solve_steps_with_threads(puzzle, solution, steps, 1, cancel_token)
+}
+
+//Like solve_steps, but every step searches its variants and the solutions of the previous step on multiple threads.
+//The solutions are returned in the same order as solve_steps returns them. Cancelling the token stops all workers.
+//Each thread searches whole units, so a step with a single variant after another step with a single solution only uses one thread
+pub fn solve_steps_parallel<'a>(puzzle: Cube333, steps: &'a Vec<(Step<'a>, DefaultStepOptions)>, threads: usize, cancel_token: &'a CancelToken) -> impl Iterator<Item = Solution> + 'a {
+    solve_steps_with_threads(puzzle, Solution::new(), steps, threads, cancel_token)
+}
+
+fn solve_steps_with_threads<'a>(puzzle: Cube333, solution: Solution, steps: &'a Vec<(Step<'a>, DefaultStepOptions)>, threads: usize, cancel_token: &'a CancelToken) -> impl Iterator<Item = Solution> + 'a {
+    let first_step: Box<dyn Iterator<Item = Solution>> = Box::new(vec![solution].into_iter());
 
     let solutions: Box<dyn Iterator<Item=Solution>> = steps.iter()
         .fold(first_step, |acc, (step, search_opts)|{
             debug!("Step {} with options {:?}", step.kind(), search_opts);
-            let next = steps::step::next_step(acc, step, search_opts.clone(), puzzle.clone(), cancel_token)
+            let next: Box<dyn Iterator<Item = Solution>> = if threads > 1 {
+                Box::new(steps::step::next_step_parallel(acc, step, *search_opts, puzzle, threads, cancel_token))
+            } else {
+                Box::new(steps::step::next_step(acc, step, *search_opts, puzzle, cancel_token))
+            };
+            let next = next
                 .zip(0..)
                 .take_while(|(_, count)| search_opts.step_limit.map(|limit| limit > *count).unwrap_or(true))
                 .map(|(sol, _)|sol);
@@ -30,6 +59,12 @@ pub fn solve_steps<'a>(puzzle: Cube333, steps: &'a Vec<(Step<'a>, DefaultStepOpt
     solutions
 }
 
+//Searches the whole pipeline at once instead of limiting each step individually. Every solution returned is strictly shorter than the previous one,
+//so the last solution before the iterator ends (or the cancel token fires) is the best one found. Per step limits are ignored.
+pub fn solve_steps_bounded<'a>(puzzle: Cube333, steps: &'a Vec<(Step<'a>, DefaultStepOptions)>, max_length: Option<usize>, cancel_token: &'a CancelToken) -> impl Iterator<Item = Solution> + 'a {
+    bounded::BoundedSolutionIterator::new(puzzle, steps, max_length, cancel_token)
+}
+
 pub struct SolutionIterator<'a> {
     #[allow(unused)]
     steps: Vec<(Step<'a>, DefaultStepOptions)>,
@@ -43,3 +78,63 @@ impl Iterator for SolutionIterator<'_> {
         self.solutions.next()
     }
 }
+
+#[cfg(all(test, feature = "333htr"))]
+mod tests {
+    use std::str::FromStr;
+
+    use crate::algs::Algorithm;
+    use crate::cube::Cube333;
+    use crate::cube::turn::ApplyAlgorithm;
+    use crate::solver::df_search::CancelToken;
+    use crate::steps::dsl;
+    use crate::steps::step::{DefaultStepOptions, Step};
+    use crate::steps::tables::PruningTables333;
+
+    use super::{solve_steps, solve_steps_parallel};
+
+    fn steps() -> Vec<(Step<'static>, DefaultStepOptions)> {
+        #[cfg(feature = "fs")]
+        let mut tables = PruningTables333::with_store(crate::solver::table_store::TableStore::memory());
+        #[cfg(not(feature = "fs"))]
+        let mut tables = PruningTables333::new();
+        let configs = dsl::parse_steps("EO > DR > HTR").unwrap();
+        crate::steps::solver::gen_tables(&configs, &mut tables);
+        crate::steps::solver::build_steps(configs, tables).unwrap()
+    }
+
+    fn scrambled() -> Cube333 {
+        let mut cube = Cube333::default();
+        cube.apply_alg(&Algorithm::from_str("R' U' F L2 D' B2 U' F2 D' L2 B2 R2 U' R F' U2 L D' B2 R U R' U' F").unwrap());
+        cube
+    }
+
+    #[test]
+    fn parallel_solutions_are_the_same_as_serial_ones() {
+        let steps = steps();
+        let cube = scrambled();
+        let cancel_token = CancelToken::default();
+        let serial: Vec<_> = solve_steps(cube, &steps, &cancel_token).collect();
+        assert!(serial.len() > 1);
+        for threads in 2..=4 {
+            let parallel: Vec<_> = solve_steps_parallel(cube, &steps, threads, &cancel_token).collect();
+            assert_eq!(parallel, serial, "{threads} threads");
+        }
+    }
+
+    #[test]
+    fn cancelled_parallel_searches_stop() {
+        let steps = steps();
+        let cube = scrambled();
+        let cancel_token = CancelToken::default();
+        let total = solve_steps(cube, &steps, &cancel_token).count();
+
+        let mut solutions = solve_steps_parallel(cube, &steps, 4, &cancel_token);
+        assert!(solutions.next().is_some());
+        cancel_token.cancel();
+        //Solutions that the workers already found can still be returned, but no new searches are started
+        assert!(solutions.count() < total / 2);
+
+        assert_eq!(solve_steps_parallel(cube, &steps, 4, &cancel_token).count(), 0);
+    }
+}
@@ -0,0 +1,457 @@
+use std::collections::HashSet;
+use std::fmt::Debug;
+use std::hash::Hash;
+
+use log::debug;
+
+use crate::cube::*;
+use crate::cube::turn::{Invertible, TransformableMut, TurnableMut};
+use crate::solver::moveset::MoveSet;
+use crate::steps::coord::{Coord, IndexCoord};
+
+//Maps every coordinate that can be reached with a move set to the coordinates its moves lead to.
+//Coordinates are numbered in the order they are reached, starting with the goal states, and the transitions refer to
+//these positions instead of coordinate values.
+//
+//Coordinates can only be computed from cubes, so small coordinates are explored with one representative cube per
+//coordinate, and the transitions are stored with 4 bytes per coordinate and move.
+//Coordinates that combine a corner and an edge part, like the DR and HTR coordinates, are built from the tables of
+//their parts instead. Only the parts are explored with cubes, and the transitions of the combined coordinate are
+//looked up in the tables of the parts whenever they are needed, so nothing is stored per move.
+pub struct MoveTable<const C_SIZE: usize, C> {
+    //Auxiliary moves followed by the regular moves of the move set
+    moves: Vec<Turn333>,
+    coords: Vec<C>,
+    //Position of every coordinate value, or UNREACHED
+    positions: Vec<u32>,
+    transitions: Transitions<C>,
+    goal_states: usize,
+    //Whether every move can be undone on the coordinate level, see is_symmetric
+    symmetric: bool,
+}
+
+enum Transitions<C> {
+    //moves.len() entries per position
+    Stored(Vec<u32>),
+    //The positions of both parts for every position. The parts use the same moves as the combined table
+    Parts {
+        first: Box<MoveTable<0, IndexCoord>>,
+        second: Box<MoveTable<0, IndexCoord>>,
+        pairs: Vec<(u32, u32)>,
+        combine: fn(usize, usize) -> C,
+    },
+}
+
+const UNREACHED: u32 = u32::MAX;
+const UNFILLED: u8 = u8::MAX;
+
+//Layers smaller than this are expanded on the calling thread
+#[cfg(not(target_arch = "wasm32"))]
+const PARALLEL_LAYER_SIZE: usize = 4096;
+
+//Coordinates reached by every move of a range of cubes, and the cubes of coordinates that weren't reached before
+struct Expansion<C> {
+    targets: Vec<C>,
+    candidates: Vec<(C, Cube333)>,
+}
+
+impl<const C_SIZE: usize, C: Coord<C_SIZE> + Debug + Send + Sync> MoveTable<C_SIZE, C> {
+    pub fn new<Mapper>(move_set: &MoveSet, mapper: &Mapper) -> Self
+    where
+        Mapper: Fn(&Cube333) -> C + Sync,
+    {
+        Self::new_sized(C::size(), move_set, mapper)
+    }
+
+    //Same as new, for coordinates that only know their size at runtime
+    pub fn new_sized<Mapper>(size: usize, move_set: &MoveSet, mapper: &Mapper) -> Self
+    where
+        Mapper: Fn(&Cube333) -> C + Sync,
+    {
+        Self::build(size, move_set, &[Cube333::default()], &Transformation333::ALL, mapper)
+    }
+
+    //Same as new_sized, for goals that are reached by more cubes than the solved one. The goal states are
+    //not rotated, because rotating a cube that is only partially solved usually leaves the goal
+    pub fn with_goal_states<Mapper>(size: usize, move_set: &MoveSet, goal_states: &[Cube333], mapper: &Mapper) -> Self
+    where
+        Mapper: Fn(&Cube333) -> C + Sync,
+    {
+        Self::build(size, move_set, goal_states, &[], mapper)
+    }
+
+    pub(crate) fn build<Mapper>(size: usize, move_set: &MoveSet, goal_states: &[Cube333], rotations: &[Transformation333], mapper: &Mapper) -> Self
+    where
+        Mapper: Fn(&Cube333) -> C + Sync,
+    {
+        let mut table = MoveTable {
+            moves: move_set.aux_moves.iter().chain(move_set.st_moves.iter()).cloned().collect(),
+            coords: vec![],
+            positions: vec![UNREACHED; size],
+            transitions: Transitions::Stored(vec![]),
+            goal_states: 0,
+            symmetric: false,
+        };
+
+        let mut layer = goal_cubes(move_set, goal_states, rotations, mapper);
+        for cube in layer.iter() {
+            table.reach(mapper(cube));
+        }
+        table.goal_states = table.coords.len();
+        if table.goal_states > 1 {
+            debug!("Found {} variations of the goal state", table.goal_states);
+        }
+
+        //Every layer is expanded against the coordinates reached before it. The candidates are merged in order,
+        //so the first cube that reaches a coordinate becomes its representative, no matter how many threads are used
+        let mut transitions = vec![];
+        while !layer.is_empty() {
+            let expansions = expand_layer(&table.moves, &table.positions, mapper, &layer);
+            let mut next_layer = vec![];
+            for (coord, cube) in expansions.iter().flat_map(|e| e.candidates.iter()) {
+                if table.reach(*coord) {
+                    next_layer.push(*cube);
+                }
+            }
+            for coord in expansions.iter().flat_map(|e| e.targets.iter()) {
+                transitions.push(table.positions[coord.val()]);
+            }
+            layer = next_layer;
+        }
+        table.transitions = Transitions::Stored(transitions);
+        table.symmetric = table.check_symmetric();
+        debug!("Found {}/{} coordinates", table.coords.len(), size);
+        table
+    }
+
+    //Builds the table of a coordinate that combines two parts, which are turned without affecting each other.
+    //The mappers return the values of the parts, and combine turns them back into the coordinate.
+    //Only the parts are explored with cubes, the combined coordinate is explored with the tables of the parts alone
+    pub fn from_parts<First, Second>(size: usize, move_set: &MoveSet, first: (usize, &First), second: (usize, &Second), combine: fn(usize, usize) -> C) -> Self
+    where
+        First: Fn(&Cube333) -> usize + Sync,
+        Second: Fn(&Cube333) -> usize + Sync,
+    {
+        let (first_size, first) = first;
+        let (second_size, second) = second;
+        let mapper = |c: &Cube333| combine(first(c), second(c));
+        //The parts start from the same cubes, so every goal state is in both tables
+        let goal_states = goal_cubes(move_set, &[Cube333::default()], &Transformation333::ALL, &mapper);
+        let first_table = MoveTable::build(first_size, move_set, &goal_states, &[], &|c: &Cube333| IndexCoord(first(c)));
+        let second_table = MoveTable::build(second_size, move_set, &goal_states, &[], &|c: &Cube333| IndexCoord(second(c)));
+        let mut table = MoveTable {
+            moves: first_table.moves.clone(),
+            coords: vec![],
+            positions: vec![UNREACHED; size],
+            transitions: Transitions::Stored(vec![]),
+            goal_states: 0,
+            symmetric: first_table.symmetric && second_table.symmetric,
+        };
+
+        let mut pairs = vec![];
+        for cube in goal_states.iter() {
+            table.reach(mapper(cube));
+            pairs.push((first_table.positions[first(cube)], second_table.positions[second(cube)]));
+        }
+        table.goal_states = table.coords.len();
+        if table.goal_states > 1 {
+            debug!("Found {} variations of the goal state", table.goal_states);
+        }
+
+        //The pairs are added in the order their coordinates are reached, so walking them is a breadth-first search
+        let mut next = 0;
+        while next < pairs.len() {
+            let (first_position, second_position) = pairs[next];
+            next += 1;
+            for move_index in 0..table.moves.len() {
+                let first_target = first_table.target(first_position as usize, move_index);
+                let second_target = second_table.target(second_position as usize, move_index);
+                let coord = combine(first_table.coords[first_target as usize].0, second_table.coords[second_target as usize].0);
+                if table.reach(coord) {
+                    pairs.push((first_target, second_target));
+                }
+            }
+        }
+        debug!("Found {}/{} coordinates", table.coords.len(), size);
+        table.transitions = Transitions::Parts {
+            first: Box::new(first_table),
+            second: Box::new(second_table),
+            pairs,
+            combine,
+        };
+        table
+    }
+
+    //Number of coordinates that can be reached
+    pub fn len(&self) -> usize {
+        self.coords.len()
+    }
+
+    pub fn is_empty(&self) -> bool {
+        self.coords.is_empty()
+    }
+
+    pub fn moves(&self) -> &[Turn333] {
+        &self.moves
+    }
+
+    pub fn goal_states(&self) -> &[C] {
+        &self.coords[..self.goal_states]
+    }
+
+    pub fn coords(&self) -> &[C] {
+        &self.coords
+    }
+
+    //The coordinate reached by applying the move with the given index in moves, or None if the coordinate is unreachable
+    pub fn turn(&self, coord: C, move_index: usize) -> Option<C> {
+        let position = *self.positions.get(coord.val())?;
+        if position == UNREACHED {
+            return None;
+        }
+        Some(self.coords[self.target(position as usize, move_index) as usize])
+    }
+
+    //Whether every move can be undone on the coordinate level. Only then a coordinate's distance can be
+    //determined from the coordinates its moves lead to. Malformed coordinates usually aren't symmetric.
+    //Tables built from parts are symmetric if both parts are
+    pub fn is_symmetric(&self) -> bool {
+        self.symmetric
+    }
+
+    fn check_symmetric(&self) -> bool {
+        let inverses: Option<Vec<usize>> = self.moves.iter()
+            .map(|m| self.moves.iter().position(|x| *x == m.invert()))
+            .collect();
+        let Some(inverses) = inverses else {
+            return false;
+        };
+        (0..self.coords.len()).all(|position| {
+            self.neighbours(position).zip(inverses.iter()).all(|(target, inverse)| {
+                self.target(target as usize, *inverse) as usize == position
+            })
+        })
+    }
+
+    //Breadth-first search over the coordinates, returning the distance to the goal states for every position.
+    //Once more than half of the coordinates are filled, the remaining ones are checked against the last depth instead,
+    //which is a lot faster when only few coordinates are left
+    pub fn distances(&self) -> Vec<u8> {
+        self.search().0
+    }
+
+    //Distances of every position, and the number of depths that were filled backwards
+    fn search(&self) -> (Vec<u8>, usize) {
+        let len = self.coords.len();
+        let mut distances = vec![UNFILLED; len];
+        distances[..self.goal_states].fill(0);
+        let mut filled = self.goal_states;
+        let mut depth = 0;
+        let mut backward_depths = 0;
+        while filled < len && depth < UNFILLED - 1 {
+            let mut found = 0;
+            if self.symmetric && filled > len / 2 {
+                for position in 0..len {
+                    if distances[position] == UNFILLED && self.neighbours(position).any(|t| distances[t as usize] == depth) {
+                        distances[position] = depth + 1;
+                        found += 1;
+                    }
+                }
+                backward_depths += 1;
+            } else {
+                for position in 0..len {
+                    if distances[position] != depth {
+                        continue;
+                    }
+                    for target in self.neighbours(position) {
+                        if distances[target as usize] == UNFILLED {
+                            distances[target as usize] = depth + 1;
+                            found += 1;
+                        }
+                    }
+                }
+            }
+            if found == 0 {
+                break;
+            }
+            filled += found;
+            depth += 1;
+            debug!(
+                "Filled {:width$}/{} coordinates at depth {depth} (new {found})",
+                filled,
+                len,
+                width = len.to_string().len(),
+            );
+        }
+        (distances, backward_depths)
+    }
+
+    fn neighbours(&self, position: usize) -> impl Iterator<Item = u32> + '_ {
+        (0..self.moves.len()).map(move |move_index| self.target(position, move_index))
+    }
+
+    //Position reached by applying the move with the given index to the coordinate at the given position
+    fn target(&self, position: usize, move_index: usize) -> u32 {
+        match &self.transitions {
+            Transitions::Stored(transitions) => transitions[position * self.moves.len() + move_index],
+            Transitions::Parts { first, second, pairs, combine } => {
+                let (first_position, second_position) = pairs[position];
+                let first_target = first.target(first_position as usize, move_index);
+                let second_target = second.target(second_position as usize, move_index);
+                let coord = combine(first.coords[first_target as usize].0, second.coords[second_target as usize].0);
+                self.positions[coord.val()]
+            }
+        }
+    }
+
+    //Returns true if the coordinate wasn't reached before
+    fn reach(&mut self, coord: C) -> bool {
+        let position = &mut self.positions[coord.val()];
+        if *position != UNREACHED {
+            return false;
+        }
+        *position = self.coords.len() as u32;
+        self.coords.push(coord);
+        true
+    }
+}
+
+//The goal states are the given cubes and everything reachable from them with auxiliary moves and rotations.
+//Returns the first cube found for every coordinate, in the order they were found
+fn goal_cubes<K: Hash + Eq, Mapper>(move_set: &MoveSet, goal_states: &[Cube333], rotations: &[Transformation333], mapper: &Mapper) -> Vec<Cube333>
+where
+    Mapper: Fn(&Cube333) -> K,
+{
+    let mut seen = HashSet::new();
+    let mut cubes = vec![];
+    for cube in goal_states {
+        if seen.insert(mapper(cube)) {
+            cubes.push(*cube);
+        }
+    }
+    let mut next = 0;
+    while next < cubes.len() {
+        let cube = cubes[next];
+        next += 1;
+        let aux_moves = move_set.aux_moves.iter().map(|m| {
+            let mut cube = cube;
+            cube.turn(*m);
+            cube
+        });
+        let rotations = rotations.iter().map(|t| {
+            let mut cube = cube;
+            cube.transform(*t);
+            cube
+        });
+        for cube in aux_moves.chain(rotations) {
+            if seen.insert(mapper(&cube)) {
+                cubes.push(cube);
+            }
+        }
+    }
+    cubes
+}
+
+#[cfg(not(target_arch = "wasm32"))]
+fn expand_layer<const C_SIZE: usize, C: Coord<C_SIZE> + Send + Sync, Mapper>(moves: &[Turn333], positions: &[u32], mapper: &Mapper, layer: &[Cube333]) -> Vec<Expansion<C>>
+where
+    Mapper: Fn(&Cube333) -> C + Sync,
+{
+    let threads = std::thread::available_parallelism().map_or(1, |n| n.get());
+    if threads == 1 || layer.len() < PARALLEL_LAYER_SIZE {
+        return vec![expand_chunk(moves, positions, mapper, layer)];
+    }
+    let chunk_size = layer.len().div_ceil(threads);
+    std::thread::scope(|s| {
+        let workers: Vec<_> = layer.chunks(chunk_size)
+            .map(|chunk| s.spawn(move || expand_chunk(moves, positions, mapper, chunk)))
+            .collect();
+        workers.into_iter()
+            .map(|worker| worker.join().expect("Move table worker panicked"))
+            .collect()
+    })
+}
+
+#[cfg(target_arch = "wasm32")]
+fn expand_layer<const C_SIZE: usize, C: Coord<C_SIZE>, Mapper>(moves: &[Turn333], positions: &[u32], mapper: &Mapper, layer: &[Cube333]) -> Vec<Expansion<C>>
+where
+    Mapper: Fn(&Cube333) -> C,
+{
+    vec![expand_chunk(moves, positions, mapper, layer)]
+}
+
+fn expand_chunk<const C_SIZE: usize, C: Coord<C_SIZE>, Mapper>(moves: &[Turn333], positions: &[u32], mapper: &Mapper, chunk: &[Cube333]) -> Expansion<C>
+where
+    Mapper: Fn(&Cube333) -> C,
+{
+    let mut seen = HashSet::new();
+    let mut expansion = Expansion {
+        targets: Vec::with_capacity(chunk.len() * moves.len()),
+        candidates: vec![],
+    };
+    for cube in chunk {
+        for m in moves {
+            let mut cube = *cube;
+            cube.turn(*m);
+            let coord = mapper(&cube);
+            if positions[coord.val()] == UNREACHED && seen.insert(coord) {
+                expansion.candidates.push((coord, cube));
+            }
+            expansion.targets.push(coord);
+        }
+    }
+    expansion
+}
+
+#[cfg(all(test, feature = "333htr"))]
+mod tests {
+    use std::fmt::Debug;
+
+    use crate::steps::coord::Coord;
+    use crate::steps::dr::coords::{DRUDEOFB_SIZE, DRUDEOFBCoord};
+    use crate::steps::dr::dr_config::{DR_UD_EO_FB_MOVESET, HTR_DR_UD_MOVESET};
+    use crate::steps::eo::coords::EOCoordFB;
+    use crate::steps::eo::eo_config::EO_FB_MOVESET;
+    use crate::steps::htr::coords::{HTRDRUD_SIZE, HTRDRUDCoord};
+
+    use super::{MoveTable, UNFILLED};
+
+    fn dr_table() -> MoveTable<DRUDEOFB_SIZE, DRUDEOFBCoord> {
+        MoveTable::from_parts(DRUDEOFB_SIZE, &DR_UD_EO_FB_MOVESET, (DRUDEOFBCoord::CORNERS_SIZE, &DRUDEOFBCoord::corners), (DRUDEOFBCoord::EDGES_SIZE, &DRUDEOFBCoord::edges), DRUDEOFBCoord::from_parts)
+    }
+
+    fn htr_table() -> MoveTable<HTRDRUD_SIZE, HTRDRUDCoord> {
+        MoveTable::from_parts(HTRDRUD_SIZE, &HTR_DR_UD_MOVESET, (HTRDRUDCoord::CORNERS_SIZE, &HTRDRUDCoord::corners), (HTRDRUDCoord::EDGES_SIZE, &HTRDRUDCoord::edges), HTRDRUDCoord::from_parts)
+    }
+
+    fn assert_searched_backwards<const C_SIZE: usize, C: Coord<C_SIZE> + Debug>(table: &MoveTable<C_SIZE, C>) {
+        assert_eq!(table.len(), C_SIZE);
+        assert!(table.is_symmetric());
+        let (distances, backward_depths) = table.search();
+        assert!(backward_depths > 0);
+        assert!(distances.iter().all(|d| *d != UNFILLED));
+    }
+
+    #[test]
+    fn built_in_tables_are_searched_backwards() {
+        assert_searched_backwards(&MoveTable::new(&EO_FB_MOVESET, &|c| EOCoordFB::from(c)));
+        assert_searched_backwards(&dr_table());
+        assert_searched_backwards(&htr_table());
+    }
+
+    fn assert_same_transitions<const C_SIZE: usize, C: Coord<C_SIZE> + Debug>(from_parts: &MoveTable<C_SIZE, C>, explored: &MoveTable<C_SIZE, C>) {
+        assert_eq!(from_parts.moves(), explored.moves());
+        assert_eq!(from_parts.len(), explored.len());
+        for coord in explored.coords() {
+            for move_index in 0..explored.moves().len() {
+                assert_eq!(from_parts.turn(*coord, move_index), explored.turn(*coord, move_index), "{coord:?}");
+            }
+        }
+    }
+
+    #[test]
+    fn tables_from_parts_match_explored_tables() {
+        assert_same_transitions(&dr_table(), &MoveTable::new(&DR_UD_EO_FB_MOVESET, &|c| DRUDEOFBCoord::from(c)));
+        assert_same_transitions(&htr_table(), &MoveTable::new(&HTR_DR_UD_MOVESET, &|c| HTRDRUDCoord::from(c)));
+    }
+}
@@ -1,3 +1,6 @@
+use std::collections::HashMap;
+use std::sync::{LazyLock, Mutex};
+
 use crate::cube::*;
 
 pub struct MoveSet {
@@ -6,6 +9,25 @@ pub struct MoveSet {
     pub transitions: &'static [TransitionTable333],
 }
 
+//Move lists of move sets created at runtime. Every distinct list is only stored once
+static RUNTIME_MOVES: LazyLock<Mutex<HashMap<Vec<Turn333>, &'static [Turn333]>>> = LazyLock::new(Default::default);
+
+impl MoveSet {
+    //Move set for a list of moves that is only known at runtime, e.g. the moves of a custom step, without any
+    //auxiliary moves and with the default transitions. The moves are kept until the program ends, but building the same
+    //list again reuses them, so steps can be rebuilt as often as necessary
+    pub fn from_moves(moves: &[Turn333]) -> MoveSet {
+        let st_moves = *RUNTIME_MOVES.lock().unwrap()
+            .entry(moves.to_vec())
+            .or_insert_with(|| Box::leak(moves.to_vec().into_boxed_slice()));
+        MoveSet {
+            st_moves,
+            aux_moves: &[],
+            transitions: &TransitionTable333::DEFAULT_ALL,
+        }
+    }
+}
+
 pub trait TransitionTable: 'static {
     fn check_move(&self, m: Turn333) -> Transition;
 }
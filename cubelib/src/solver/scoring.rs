@@ -0,0 +1,184 @@
+use std::collections::HashMap;
+
+use crate::defs::StepKind;
+use crate::solver::solution::Solution;
+
+//Lower scores are better. The solver still generates solutions ordered by length, scorers are only used to rank them afterwards
+pub trait SolutionScorer {
+    fn score(&self, solution: &Solution) -> f64;
+}
+
+impl <F: Fn(&Solution) -> f64> SolutionScorer for F {
+    fn score(&self, solution: &Solution) -> f64 {
+        self(solution)
+    }
+}
+
+//Sums the scores of all contained scorers
+impl SolutionScorer for Vec<Box<dyn SolutionScorer>> {
+    fn score(&self, solution: &Solution) -> f64 {
+        self.iter().map(|s| s.score(solution)).sum()
+    }
+}
+
+pub struct LengthScorer;
+
+impl SolutionScorer for LengthScorer {
+    fn score(&self, solution: &Solution) -> f64 {
+        solution.len() as f64
+    }
+}
+
+//Weighted sum of the step lengths. Steps without an explicit weight use the default weight
+pub struct StepWeightScorer {
+    pub weights: HashMap<StepKind, f64>,
+    pub default_weight: f64,
+}
+
+impl StepWeightScorer {
+    pub fn new(weights: HashMap<StepKind, f64>) -> Self {
+        StepWeightScorer { weights, default_weight: 1.0 }
+    }
+}
+
+impl SolutionScorer for StepWeightScorer {
+    fn score(&self, solution: &Solution) -> f64 {
+        solution.steps.iter()
+            .map(|step| step.alg.len() as f64 * self.weights.get(&step.kind).cloned().unwrap_or(self.default_weight))
+            .sum()
+    }
+}
+
+pub struct NissSwitchPenalty(pub f64);
+
+impl SolutionScorer for NissSwitchPenalty {
+    fn score(&self, solution: &Solution) -> f64 {
+        solution.niss_switches() as f64 * self.0
+    }
+}
+
+//Penalizes solutions where the last step ends on the inverse
+pub struct InverseEndPenalty(pub f64);
+
+impl SolutionScorer for InverseEndPenalty {
+    fn score(&self, solution: &Solution) -> f64 {
+        if solution.ends_on_normal() { 0.0 } else { self.0 }
+    }
+}
+
+//Adds the penalty for every move a single step uses beyond the threshold
+pub struct LongStepPenalty {
+    pub threshold: usize,
+    pub penalty: f64,
+}
+
+impl SolutionScorer for LongStepPenalty {
+    fn score(&self, solution: &Solution) -> f64 {
+        solution.steps.iter()
+            .map(|step| step.alg.len().saturating_sub(self.threshold) as f64 * self.penalty)
+            .sum()
+    }
+}
+
+//Returns the best count solutions of the iterator according to the scorer. Solutions with equal scores keep their original order,
+//so ties are still broken by length. This consumes the whole iterator, so it should be limited beforehand.
+pub fn rank_solutions<I: Iterator<Item = Solution>, S: SolutionScorer + ?Sized>(solutions: I, scorer: &S, count: usize) -> Vec<Solution> {
+    let mut scored: Vec<(f64, Solution)> = solutions
+        .map(|sol| (scorer.score(&sol), sol))
+        .collect();
+    scored.sort_by(|(a, _), (b, _)| a.total_cmp(b));
+    scored.into_iter()
+        .take(count)
+        .map(|(_, sol)| sol)
+        .collect()
+}
+
+#[cfg(test)]
+mod tests {
+    use std::collections::HashMap;
+    use std::str::FromStr;
+
+    use crate::algs::Algorithm;
+    use crate::defs::StepKind;
+    use crate::solver::solution::{Solution, SolutionStep};
+
+    use super::*;
+
+    fn solution(steps: &[(StepKind, &str)]) -> Solution {
+        let mut solution = Solution::new();
+        for (kind, alg) in steps {
+            solution.add_step(SolutionStep {
+                kind: kind.clone(),
+                variant: "ud".to_string(),
+                alg: Algorithm::from_str(alg).unwrap(),
+                comment: "".to_string(),
+                niss_switches: 0,
+            });
+        }
+        solution
+    }
+
+    fn algs(solutions: &[Solution]) -> Vec<String> {
+        solutions.iter()
+            .map(|s| s.steps.iter().map(|step| step.alg.to_string()).collect::<Vec<_>>().join(" | "))
+            .collect()
+    }
+
+    #[test]
+    fn rank_solutions_keeps_order_of_ties() {
+        let solutions = vec![
+            solution(&[(StepKind::EO, "R U F")]),
+            solution(&[(StepKind::EO, "F")]),
+            solution(&[(StepKind::EO, "U R")]),
+            solution(&[(StepKind::EO, "D")]),
+            solution(&[(StepKind::EO, "L B")]),
+        ];
+        let ranked = rank_solutions(solutions.into_iter(), &LengthScorer, 4);
+        assert_eq!(algs(&ranked), vec!["F", "D", "U R", "L B"]);
+    }
+
+    #[test]
+    fn rank_solutions_with_closure() {
+        let solutions = vec![
+            solution(&[(StepKind::EO, "R")]),
+            solution(&[(StepKind::EO, "F U")]),
+        ];
+        let scorer = |s: &Solution| -(s.len() as f64);
+        let ranked = rank_solutions(solutions.into_iter(), &scorer, 2);
+        assert_eq!(algs(&ranked), vec!["F U", "R"]);
+    }
+
+    #[test]
+    fn niss_switch_penalty() {
+        assert_eq!(NissSwitchPenalty(2.0).score(&solution(&[(StepKind::EO, "R"), (StepKind::DR, "U")])), 0.0);
+        assert_eq!(NissSwitchPenalty(2.0).score(&solution(&[(StepKind::EO, "(R)"), (StepKind::DR, "U")])), 4.0);
+    }
+
+    #[test]
+    fn inverse_end_penalty() {
+        assert_eq!(InverseEndPenalty(1.5).score(&solution(&[(StepKind::EO, "(R)"), (StepKind::DR, "U")])), 0.0);
+        assert_eq!(InverseEndPenalty(1.5).score(&solution(&[(StepKind::EO, "R"), (StepKind::DR, "(U)")])), 1.5);
+    }
+
+    #[test]
+    fn long_step_penalty() {
+        let scorer = LongStepPenalty { threshold: 2, penalty: 0.5 };
+        assert_eq!(scorer.score(&solution(&[(StepKind::EO, "R U"), (StepKind::DR, "F")])), 0.0);
+        assert_eq!(scorer.score(&solution(&[(StepKind::EO, "R U F D"), (StepKind::DR, "F B L")])), 1.5);
+    }
+
+    #[test]
+    fn step_weight_scorer() {
+        let mut scorer = StepWeightScorer::new(HashMap::from([(StepKind::DR, 2.0)]));
+        let sol = solution(&[(StepKind::EO, "R U"), (StepKind::DR, "F B L")]);
+        assert_eq!(scorer.score(&sol), 8.0);
+        scorer.default_weight = 0.0;
+        assert_eq!(scorer.score(&sol), 6.0);
+    }
+
+    #[test]
+    fn combined_scorers_are_summed() {
+        let scorers: Vec<Box<dyn SolutionScorer>> = vec![Box::new(LengthScorer), Box::new(NissSwitchPenalty(10.0))];
+        assert_eq!(scorers.score(&solution(&[(StepKind::EO, "(R U)")])), 12.0);
+    }
+}
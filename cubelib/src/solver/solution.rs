@@ -17,7 +17,10 @@ pub struct SolutionStep {
     pub kind: StepKind,
     pub variant: String,
     pub alg: Algorithm,
-    pub comment: String
+    pub comment: String,
+    //Number of NISS switches this step makes, including switching away from the side the previous step ended on
+    #[cfg_attr(feature = "serde_support", serde(default))]
+    pub niss_switches: usize,
 }
 
 impl Solution {
@@ -29,13 +32,22 @@ impl Solution {
         self.steps.iter().map(|e| e.alg.len()).sum::<usize>()
     }
 
-    pub fn add_step(&mut self, step: SolutionStep) {
-        self.ends_on_normal = match (step.alg.normal_moves.is_empty(), step.alg.inverse_moves.is_empty()) {
+    //Steps with moves on both sides do the normal moves first, like the search does with NissSwitchType::Always,
+    //so they always end on the inverse. This used to switch to the side the previous step didn't end on, which disagreed
+    //with the search when the previous step ended on the inverse. Steps without any moves stay on the side of the previous step
+    pub fn add_step(&mut self, mut step: SolutionStep) {
+        let ends_on_normal = match (step.alg.normal_moves.is_empty(), step.alg.inverse_moves.is_empty()) {
             (true, true) => self.ends_on_normal,
-            (false, false) => !self.ends_on_normal,
+            (false, false) => false,
             (true, false) => false,
             (false, true) => true,
         };
+        step.niss_switches = match (step.alg.normal_moves.is_empty(), step.alg.inverse_moves.is_empty()) {
+            (true, true) => 0,
+            (false, false) => if self.ends_on_normal { 1 } else { 2 },
+            _ => if ends_on_normal == self.ends_on_normal { 0 } else { 1 },
+        };
+        self.ends_on_normal = ends_on_normal;
         self.steps.push(step);
     }
 
@@ -43,6 +55,11 @@ impl Solution {
         self.ends_on_normal
     }
 
+    //Number of times the solution moves between normal and inverse. Starting on the inverse counts as a switch
+    pub fn niss_switches(&self) -> usize {
+        self.steps.iter().map(|s| s.niss_switches).sum()
+    }
+
     pub fn get_steps(&self) -> &'_ Vec<SolutionStep> {
         &self.steps
     }
@@ -145,12 +162,11 @@ impl Display for Solution {
         } else {
             self.clone().into()
         };
-        writeln!(
-            f,
-            "Solution ({}): {}",
-            total_moves,
-            final_alg
-        )
+        match self.niss_switches() {
+            0 => writeln!(f, "Solution ({}): {}", total_moves, final_alg),
+            1 => writeln!(f, "Solution ({}, 1 NISS switch): {}", total_moves, final_alg),
+            switches => writeln!(f, "Solution ({}, {} NISS switches): {}", total_moves, switches, final_alg),
+        }
     }
 }
 
@@ -173,4 +189,61 @@ impl <C: TurnableMut + InvertibleMut> ApplySolution<C> for C {
         }
         self.invert();
     }
-}
\ No newline at end of file
+}
+#[cfg(test)]
+mod tests {
+    use std::str::FromStr;
+
+    use crate::algs::Algorithm;
+    use crate::defs::StepKind;
+
+    use super::{Solution, SolutionStep};
+
+    fn step(alg: &str) -> SolutionStep {
+        SolutionStep {
+            kind: StepKind::EO,
+            variant: "ud".to_string(),
+            alg: Algorithm::from_str(alg).unwrap(),
+            comment: "".to_string(),
+            niss_switches: 0,
+        }
+    }
+
+    fn switches(steps: &[&str]) -> Vec<usize> {
+        let mut solution = Solution::new();
+        for alg in steps {
+            solution.add_step(step(alg));
+        }
+        solution.steps.iter().map(|s| s.niss_switches).collect()
+    }
+
+    #[test]
+    fn counts_niss_switches() {
+        assert_eq!(switches(&["R U", "F"]), vec![0, 0]);
+        assert_eq!(switches(&["(R U)", "(F)", "D"]), vec![1, 0, 1]);
+        assert_eq!(switches(&["R U (F)", "(D)"]), vec![1, 0]);
+        assert_eq!(switches(&["(R)", "U F (D)"]), vec![1, 2]);
+        assert_eq!(switches(&["(R)", "", "U"]), vec![1, 0, 1]);
+    }
+
+    #[test]
+    fn empty_step_keeps_side() {
+        let mut solution = Solution::new();
+        solution.add_step(step("(R)"));
+        solution.add_step(step(""));
+        assert!(!solution.ends_on_normal());
+        assert_eq!(solution.niss_switches(), 1);
+    }
+
+    #[test]
+    fn steps_on_both_sides_end_on_inverse() {
+        let mut solution = Solution::new();
+        solution.add_step(step("R (U)"));
+        assert!(!solution.ends_on_normal());
+        solution.add_step(step("F (D)"));
+        assert!(!solution.ends_on_normal());
+        assert_eq!(solution.steps.iter().map(|s| s.niss_switches).collect::<Vec<_>>(), vec![1, 2]);
+        solution.add_step(step("B"));
+        assert!(solution.ends_on_normal());
+    }
+}
@@ -1,5 +1,6 @@
-use std::collections::HashSet;
+use std::collections::{BTreeMap, HashSet};
 use std::marker::PhantomData;
+use std::sync::atomic::{AtomicUsize, Ordering};
 
 use crate::algs::Algorithm;
 use crate::solver::df_search::CancelToken;
@@ -27,6 +28,78 @@ where
         })
 }
 
+//Solutions of the previous step that are searched at the same time per thread. Larger batches start fewer threads,
+//but search more solutions that the caller might not need
+const PARALLEL_BATCH_SIZE: usize = 4;
+
+//Like iterated_dfs, but the search of every solution is split into a number of independent units, e.g. one per step variant.
+//Units of solutions with the same length are searched on multiple threads in batches, and the results are returned
+//in the same order as iterated_dfs would return them.
+pub(crate) fn iterated_dfs_parallel<'a, IN: Iterator<Item = Solution> + 'a, F>(
+    current_stage: IN,
+    units: usize,
+    threads: usize,
+    cancel_token: &'a CancelToken,
+    mapper: F,
+) -> impl Iterator<Item = Solution> + 'a
+where
+    F: Fn(&Solution, u8, usize) -> Vec<Solution> + Sync + 'a,
+{
+    let mut solutions = DFSSolutionIter::new(current_stage)
+        .take_while(move |_|!cancel_token.is_cancelled())
+        .take_while(|(_, depth)| *depth < 100)
+        .peekable();
+    let batch_size = threads.max(1) * PARALLEL_BATCH_SIZE;
+    std::iter::from_fn(move || {
+        let (first, depth) = solutions.next()?;
+        let mut batch = vec![first];
+        while batch.len() < batch_size {
+            match solutions.next_if(|(_, d)| *d == depth) {
+                Some((alg, _)) => batch.push(alg),
+                None => break,
+            }
+        }
+        Some(search_batch(&batch, depth, units, threads, cancel_token, &mapper))
+    }).flatten()
+}
+
+fn search_batch<F>(batch: &[Solution], depth: usize, units: usize, threads: usize, cancel_token: &CancelToken, mapper: &F) -> Vec<Solution>
+where
+    F: Fn(&Solution, u8, usize) -> Vec<Solution> + Sync,
+{
+    let tasks = batch.len() * units;
+    let search = |task: usize| {
+        let alg = &batch[task / units];
+        mapper(alg, (depth - alg.len()) as u8, task % units)
+    };
+    if threads <= 1 || tasks <= 1 {
+        return (0..tasks).flat_map(search).collect();
+    }
+    //Units take very different amounts of time, so workers take the next one as soon as they are done
+    let next_task = AtomicUsize::new(0);
+    let mut results = vec![vec![]; tasks];
+    std::thread::scope(|s| {
+        let workers: Vec<_> = (0..threads.min(tasks))
+            .map(|_| s.spawn(|| {
+                let mut done = vec![];
+                loop {
+                    let task = next_task.fetch_add(1, Ordering::Relaxed);
+                    if task >= tasks || cancel_token.is_cancelled() {
+                        return done;
+                    }
+                    done.push((task, search(task)));
+                }
+            }))
+            .collect();
+        for worker in workers {
+            for (task, solutions) in worker.join().expect("Search worker panicked") {
+                results[task] = solutions;
+            }
+        }
+    });
+    results.into_iter().flatten().collect()
+}
+
 pub struct DFSSolutionIter<I> {
     orig: I,
     pos: usize,
@@ -83,32 +156,41 @@ where
     }
 }
 
-struct DistinctSolutions<I, V> {
+struct DistinctSolutions<I, V, F> {
     orig: I,
-    observed: HashSet<Algorithm>,
+    //Keys by the length of the algorithm they were computed from
+    observed: BTreeMap<usize, HashSet<Algorithm>>,
     current_length: usize,
+    key: F,
+    //Keys of algorithms more than this many moves shorter than the current one are dropped. 0 is only valid if the key has
+    //the same length as the algorithm. Otherwise equal keys can be spread over multiple lengths
+    length_window: usize,
     _v: PhantomData<V>,
 }
 
-impl<I, V> DistinctSolutions<I, V>
+impl<I, V, F> DistinctSolutions<I, V, F>
     where
         I: Iterator<Item = V>,
-        V: Into<Algorithm> + Clone
+        V: Into<Algorithm> + Clone,
+        F: Fn(Algorithm) -> Algorithm
 {
-    fn new(iter: I) -> Self {
+    fn new(iter: I, key: F, length_window: usize) -> Self {
         Self {
             orig: iter,
             current_length: 0,
-            observed: HashSet::new(),
+            observed: BTreeMap::new(),
+            key,
+            length_window,
             _v: PhantomData::default(),
         }
     }
 }
 
-impl<I, V> Iterator for DistinctSolutions<I, V>
+impl<I, V, F> Iterator for DistinctSolutions<I, V, F>
     where
         I: Iterator<Item = V>,
-        V: Into<Algorithm> + Clone
+        V: Into<Algorithm> + Clone,
+        F: Fn(Algorithm) -> Algorithm
 {
     type Item = <I as Iterator>::Item;
 
@@ -118,11 +200,14 @@ impl<I, V> Iterator for DistinctSolutions<I, V>
             Some(v) => {
                 let alg: Algorithm = v.clone().into();
                 if alg.len() > self.current_length {
-                    self.observed.clear();
                     self.current_length = alg.len();
-                    self.observed.insert(alg);
-                    Some(v)
-                } else if self.observed.insert(alg) {
+                    let min_length = self.current_length.saturating_sub(self.length_window);
+                    self.observed = self.observed.split_off(&min_length);
+                }
+                let length = alg.len();
+                let key = (self.key)(alg);
+                if self.observed.values().all(|keys| !keys.contains(&key)) {
+                    self.observed.entry(length).or_default().insert(key);
                     Some(v)
                 } else {
                     self.next()
@@ -133,5 +218,19 @@ impl<I, V> Iterator for DistinctSolutions<I, V>
 }
 
 pub fn distinct_algorithms<V: Into<Algorithm> + Clone>(iter: impl Iterator<Item = V>) -> impl Iterator<Item = V> {
-    DistinctSolutions::<_, V>::new(iter)
-}
\ No newline at end of file
+    DistinctSolutions::<_, V, _>::new(iter, |alg| alg, 0)
+}
+
+//Moves that cancel make the canonical form shorter than the algorithm. Solutions only cancel across step or side boundaries,
+//so equal forms more than a few moves apart in length are rare and not worth keeping every key of the search for
+const CANONICAL_LENGTH_WINDOW: usize = 4;
+
+//Also drops algorithms that are equal after sorting commuting moves and applying cancellations. If merge_sides is set, normal and inverse moves
+//are combined first, so solutions that only differ in which step or side a transitional move was assigned to are considered equal as well.
+pub fn distinct_canonical_algorithms<V: Into<Algorithm> + Clone>(iter: impl Iterator<Item = V>, merge_sides: bool) -> impl Iterator<Item = V> {
+    DistinctSolutions::<_, V, _>::new(iter, move |alg: Algorithm| if merge_sides {
+        alg.to_uninverted().canonical()
+    } else {
+        alg.canonical()
+    }, CANONICAL_LENGTH_WINDOW)
+}
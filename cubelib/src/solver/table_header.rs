@@ -0,0 +1,210 @@
+use num_traits::{FromPrimitive, ToPrimitive};
+
+use crate::solver::lookup_table::TableType;
+use crate::solver::moveset::MoveSet;
+use crate::Error;
+
+//Every table file starts with a header that describes which table it contains and how it was generated:
+//
+//   0  magic "CLTB"
+//   4  format version
+//   5  table type
+//   6  compression
+//   7  length of the coordinate name
+//   8  generator version (u32)
+//  12  coordinate size (u64)
+//  20  move set hash (u64)
+//  28  checksum of the uncompressed entries (u64)
+//  36  number of bytes following the header (u64)
+//  44  coordinate name
+//
+//All numbers are little endian. The header is padded to HEADER_SIZE bytes, so the entries of uncompressed tables are page-aligned.
+//Tables are only used if the header matches the TableId of the requested table. Everything else is regenerated.
+
+pub const MAGIC: [u8; 4] = *b"CLTB";
+pub const FORMAT_VERSION: u8 = 3;
+pub const HEADER_SIZE: usize = 4096;
+const NAME_OFFSET: usize = 44;
+
+#[derive(Copy, Clone, Eq, PartialEq, Debug, Default)]
+#[repr(u8)]
+#[derive(FromPrimitive, ToPrimitive)]
+pub enum Compression {
+    #[default]
+    None = 0u8,
+    //Compressed tables are smaller on disk, but have to be decompressed into memory instead of being mapped
+    Lz4 = 1u8,
+}
+
+//Identifies the contents of a table. A table file is only loaded if it was written for the same id
+#[derive(Clone, Debug, PartialEq, Eq)]
+pub struct TableId {
+    pub coord: String,
+    pub size: usize,
+    pub move_set_hash: u64,
+    //Has to change whenever a coordinate or the generation changes without changing the other fields
+    pub generator: u32,
+}
+
+impl TableId {
+    pub fn new(coord: &str, size: usize, move_set: &MoveSet, generator: u32) -> Self {
+        TableId {
+            coord: coord.to_string(),
+            size,
+            move_set_hash: move_set_hash(move_set),
+            generator,
+        }
+    }
+}
+
+#[derive(Clone, Debug, PartialEq, Eq)]
+pub struct TableHeader {
+    pub id: TableId,
+    pub table_type: TableType,
+    pub compression: Compression,
+    pub checksum: u64,
+    pub data_len: u64,
+}
+
+impl TableHeader {
+    pub fn to_bytes(&self) -> Vec<u8> {
+        let name = self.id.coord.as_bytes();
+        let name = &name[..name.len().min(HEADER_SIZE - NAME_OFFSET).min(u8::MAX as usize)];
+        let mut header = Vec::with_capacity(HEADER_SIZE);
+        header.extend(MAGIC);
+        header.extend([FORMAT_VERSION, self.table_type.to_u8().unwrap(), self.compression.to_u8().unwrap(), name.len() as u8]);
+        header.extend(self.id.generator.to_le_bytes());
+        header.extend((self.id.size as u64).to_le_bytes());
+        header.extend(self.id.move_set_hash.to_le_bytes());
+        header.extend(self.checksum.to_le_bytes());
+        header.extend(self.data_len.to_le_bytes());
+        header.extend(name);
+        header.resize(HEADER_SIZE, 0);
+        header
+    }
+
+    pub fn parse(data: &[u8]) -> Result<Self, Error> {
+        if data.len() < 4 || data[0..4] != MAGIC {
+            //Versions before the magic number started with the version byte
+            return match data.first() {
+                Some(version @ (1 | 2)) => Err(Error::OutdatedTable { version: *version, expected: FORMAT_VERSION }),
+                _ => Err(Error::CorruptTable("Not a table file".to_string())),
+            }
+        }
+        if data.len() < HEADER_SIZE {
+            return Err(Error::CorruptTable("Truncated table header".to_string()))
+        }
+        if data[4] != FORMAT_VERSION {
+            return Err(Error::OutdatedTable { version: data[4], expected: FORMAT_VERSION })
+        }
+        let table_type = TableType::from_u8(data[5]).ok_or(Error::CorruptTable(format!("Invalid table type {}", data[5])))?;
+        let compression = Compression::from_u8(data[6]).ok_or(Error::CorruptTable(format!("Invalid compression {}", data[6])))?;
+        let u32_at = |offset: usize| u32::from_le_bytes(data[offset..offset + 4].try_into().unwrap());
+        let u64_at = |offset: usize| u64::from_le_bytes(data[offset..offset + 8].try_into().unwrap());
+        let name = &data[NAME_OFFSET..NAME_OFFSET + data[7] as usize];
+        Ok(TableHeader {
+            id: TableId {
+                coord: String::from_utf8_lossy(name).to_string(),
+                size: u64_at(12) as usize,
+                move_set_hash: u64_at(20),
+                generator: u32_at(8),
+            },
+            table_type,
+            compression,
+            checksum: u64_at(28),
+            data_len: u64_at(36),
+        })
+    }
+
+    //Checks that the header belongs to the expected table
+    pub fn check_id(&self, expected: &TableId) -> Result<(), Error> {
+        let actual = &self.id;
+        let mismatch = if actual.coord != expected.coord {
+            format!("coordinate {}, expected {}", actual.coord, expected.coord)
+        } else if actual.size != expected.size {
+            format!("size {}, expected {}", actual.size, expected.size)
+        } else if actual.move_set_hash != expected.move_set_hash {
+            "generated with a different move set".to_string()
+        } else if actual.generator != expected.generator {
+            format!("generator version {}, expected {}", actual.generator, expected.generator)
+        } else {
+            return Ok(())
+        };
+        Err(Error::MismatchedTable(mismatch))
+    }
+}
+
+//Writes the header and the (optionally compressed) entries of a table
+pub fn serialize(id: &TableId, table_type: TableType, compression: Compression, entries: &[u8]) -> Vec<u8> {
+    let data = match compression {
+        Compression::None => None,
+        Compression::Lz4 => Some(lz4_flex::block::compress(entries)),
+    };
+    let data = data.as_deref().unwrap_or(entries);
+    let header = TableHeader {
+        id: id.clone(),
+        table_type,
+        compression,
+        checksum: checksum(entries),
+        data_len: data.len() as u64,
+    };
+    let mut ser = header.to_bytes();
+    ser.extend_from_slice(data);
+    ser
+}
+
+//Reads the header of a table file and checks it against the expected table.
+//Returns the header and the uncompressed entries if the table is compressed
+pub fn deserialize(data: &[u8], expected: &TableId) -> Result<(TableHeader, Option<Vec<u8>>), Error> {
+    TableHeader::parse(data)?.check_id(expected)?;
+    verify(data)
+}
+
+//Checks that a table file is complete and intact, without knowing which table it should contain
+pub fn verify(data: &[u8]) -> Result<(TableHeader, Option<Vec<u8>>), Error> {
+    let header = TableHeader::parse(data)?;
+    let body = &data[HEADER_SIZE..];
+    if body.len() as u64 != header.data_len {
+        return Err(Error::CorruptTable(format!("Expected {} bytes of entries, found {}", header.data_len, body.len())))
+    }
+    let decompressed = match header.compression {
+        Compression::None => None,
+        Compression::Lz4 => {
+            let size = entries_len(header.table_type, header.id.size);
+            Some(lz4_flex::block::decompress(body, size).map_err(|e| Error::CorruptTable(e.to_string()))?)
+        }
+    };
+    let entries = decompressed.as_deref().unwrap_or(body);
+    if checksum(entries) != header.checksum {
+        return Err(Error::CorruptTable("Checksum mismatch".to_string()))
+    }
+    Ok((header, decompressed))
+}
+
+//Number of bytes needed to store a table of the given type and coordinate size
+pub fn entries_len(table_type: TableType, size: usize) -> usize {
+    match table_type {
+        TableType::Compressed => size.div_ceil(2),
+        TableType::Uncompressed | TableType::Niss => size,
+        TableType::ModThree => size.div_ceil(4),
+    }
+}
+
+//FNV-1a. The std hasher isn't stable between Rust versions, so it can't be used for anything stored on disk
+pub fn checksum(data: &[u8]) -> u64 {
+    let mut hash = 0xcbf29ce484222325u64;
+    for b in data {
+        hash ^= *b as u64;
+        hash = hash.wrapping_mul(0x100000001b3);
+    }
+    hash
+}
+
+fn move_set_hash(move_set: &MoveSet) -> u64 {
+    let moves: Vec<u8> = move_set.st_moves.iter()
+        .map(|m| (m.face as u8) << 4 | m.dir as u8)
+        .chain([0xFF])
+        .chain(move_set.aux_moves.iter().map(|m| (m.face as u8) << 4 | m.dir as u8))
+        .collect();
+    checksum(&moves)
+}
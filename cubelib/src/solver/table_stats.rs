@@ -0,0 +1,96 @@
+use crate::solver::lookup_table::TableType;
+
+//Summary of the entries of a table, e.g. to check that a coordinate still covers the expected subgroup after changing it
+#[derive(Clone, Debug, PartialEq, Eq)]
+pub struct TableStats {
+    pub table_type: TableType,
+    //Number of coordinates
+    pub size: usize,
+    //Bytes used by the entries
+    pub memory: usize,
+    //Number of coordinates at every distance. Mod 3 tables only know the distance modulo 3
+    pub depths: Vec<usize>,
+    //Coordinates that weren't reached while generating the table
+    pub unreached: usize,
+    //Number of coordinates for every NISS distance. Only NISS tables have them, and only for coordinates with a bound
+    pub niss: Option<Vec<usize>>,
+}
+
+impl TableStats {
+    pub fn new(table_type: TableType, size: usize, entries: &[u8]) -> Self {
+        let mut stats = TableStats {
+            table_type,
+            size,
+            memory: entries.len(),
+            depths: vec![],
+            unreached: 0,
+            niss: None,
+        };
+        match table_type {
+            TableType::Uncompressed => {
+                for entry in &entries[..size.min(entries.len())] {
+                    stats.count(*entry, 0xFF);
+                }
+            },
+            TableType::Compressed => {
+                for id in 0..size.min(entries.len() * 2) {
+                    stats.count((entries[id >> 1] >> ((id & 1) << 2)) & 0x0F, 0x0F);
+                }
+            },
+            TableType::ModThree => {
+                for id in 0..size.min(entries.len() * 4) {
+                    stats.count((entries[id >> 2] >> ((id & 3) << 1)) & 0x03, 0x03);
+                }
+            },
+            TableType::Niss => {
+                let mut niss = vec![];
+                for entry in &entries[..size.min(entries.len())] {
+                    stats.count(entry & 0x0F, 0x0F);
+                    let bound = (entry >> 4) as usize;
+                    if entry & 0x0F != 0x0F && bound != 0x0F {
+                        increment(&mut niss, bound);
+                    }
+                }
+                stats.niss = Some(niss);
+            },
+        }
+        stats
+    }
+
+    //Number of coordinates that can be reached
+    pub fn reached(&self) -> usize {
+        self.size - self.unreached
+    }
+
+    //The largest distance of any coordinate, which is the God's number of the subgroup.
+    //Unknown for mod 3 tables
+    pub fn max_depth(&self) -> Option<usize> {
+        if self.table_type == TableType::ModThree {
+            return None;
+        }
+        self.depths.iter().rposition(|count| *count > 0)
+    }
+
+    pub fn average_depth(&self) -> Option<f64> {
+        if self.table_type == TableType::ModThree || self.reached() == 0 {
+            return None;
+        }
+        let total: usize = self.depths.iter().enumerate().map(|(depth, count)| depth * count).sum();
+        Some(total as f64 / self.reached() as f64)
+    }
+
+    fn count(&mut self, value: u8, empty: u8) {
+        if value == empty {
+            self.unreached += 1;
+        } else {
+            increment(&mut self.depths, value as usize);
+        }
+    }
+}
+
+fn increment(histogram: &mut Vec<usize>, index: usize) {
+    if histogram.len() <= index {
+        histogram.resize(index + 1, 0);
+    }
+    histogram[index] += 1;
+}
@@ -0,0 +1,320 @@
+use std::collections::HashMap;
+use std::ffi::OsString;
+use std::fs;
+use std::fs::File;
+use std::io::Read;
+use std::path::{Path, PathBuf};
+use std::sync::{Arc, RwLock};
+
+use home::home_dir;
+use log::{debug, warn};
+
+use crate::solver::lookup_table::{LoadFromDisk, SaveToDisk, TableData};
+use crate::solver::table_header::{Compression, TableId};
+use crate::Error;
+
+//Where pruning tables are loaded from and saved to. Tables are stored per puzzle under a key, e.g. 333/dr.
+//
+//By default tables are stored in ~/.cubelib/tables. This can be changed with environment variables:
+//  CUBELIB_TABLES_DIR   directory of the tables
+//  CUBELIB_TABLES_MODE  read-write (default), read-only or none. Read-only stores never write tables and none disables persistence
+//Without a home directory and without CUBELIB_TABLES_DIR, tables are only kept in memory.
+
+pub const DIR_VAR: &str = "CUBELIB_TABLES_DIR";
+pub const MODE_VAR: &str = "CUBELIB_TABLES_MODE";
+
+#[derive(Clone, Debug)]
+pub enum TableStore {
+    Directory { path: PathBuf, read_only: bool },
+    //Tables are kept in memory and shared by every clone of the store. Useful for tests
+    Memory(Arc<RwLock<HashMap<String, Arc<Vec<u8>>>>>),
+    //Tables are never loaded or saved, so they are always generated
+    None,
+}
+
+impl TableStore {
+    pub fn directory<P: Into<PathBuf>>(path: P) -> Self {
+        TableStore::Directory { path: path.into(), read_only: false }
+    }
+
+    pub fn memory() -> Self {
+        TableStore::Memory(Arc::new(RwLock::new(HashMap::new())))
+    }
+
+    //The default store, configured by the environment variables above
+    pub fn from_env() -> Self {
+        Self::from_vars(std::env::var(MODE_VAR).ok(), std::env::var_os(DIR_VAR))
+    }
+
+    fn from_vars(mode: Option<String>, dir: Option<OsString>) -> Self {
+        let mode = mode.unwrap_or_default().to_lowercase();
+        let read_only = match mode.as_str() {
+            "" | "read-write" => false,
+            "read-only" => true,
+            "none" => return TableStore::None,
+            _ => {
+                warn!("Invalid {MODE_VAR} {mode}. Expected read-write, read-only or none");
+                false
+            }
+        };
+        let path = match dir {
+            Some(dir) => PathBuf::from(dir),
+            None => match home_dir() {
+                Some(home) => home.join(".cubelib").join("tables"),
+                None => {
+                    debug!("Unable to find the home directory, pruning tables won't be saved");
+                    return TableStore::None;
+                }
+            }
+        };
+        TableStore::Directory { path, read_only }
+    }
+
+    //A store that loads tables but never saves them, e.g. for tables that were generated in advance
+    pub fn read_only(self) -> Self {
+        match self {
+            TableStore::Directory { path, .. } => TableStore::Directory { path, read_only: true },
+            store => store,
+        }
+    }
+
+    pub fn is_writable(&self) -> bool {
+        match self {
+            TableStore::Directory { read_only, .. } => !read_only,
+            TableStore::Memory(_) => true,
+            TableStore::None => false,
+        }
+    }
+
+    //Directory of the tables of a puzzle, if the store is backed by the file system
+    pub fn puzzle_dir(&self, puzzle_id: &str) -> Option<PathBuf> {
+        match self {
+            TableStore::Directory { path, .. } => Some(path.join(puzzle_id)),
+            _ => None,
+        }
+    }
+
+    pub fn load<T: LoadFromDisk>(&self, puzzle_id: &str, key: &str, id: &TableId) -> Result<T, Error> {
+        match self {
+            TableStore::Directory { path, .. } => T::load_from_file(&table_path(path, puzzle_id, key), id),
+            TableStore::Memory(tables) => {
+                let data = tables.read().unwrap()
+                    .get(&memory_key(puzzle_id, key))
+                    .cloned()
+                    .ok_or_else(|| not_found(puzzle_id, key))?;
+                T::load_bytes(&data[..], id)
+            },
+            TableStore::None => Err(not_found(puzzle_id, key)),
+        }
+    }
+
+    //Returns false if the store doesn't persist tables
+    pub fn save<T: TableData>(&self, puzzle_id: &str, key: &str, table: &T, id: &TableId, compression: Compression) -> Result<bool, Error> {
+        match self {
+            TableStore::Directory { read_only: true, .. } | TableStore::None => Ok(false),
+            TableStore::Directory { path, .. } => {
+                table.save_to_file(&table_path(path, puzzle_id, key), id, compression)?;
+                Ok(true)
+            },
+            TableStore::Memory(tables) => {
+                let data = crate::solver::table_header::serialize(id, table.table_type(), compression, table.entries());
+                tables.write().unwrap().insert(memory_key(puzzle_id, key), Arc::new(data));
+                Ok(true)
+            }
+        }
+    }
+
+    //Raw contents of a table file, including the header
+    pub fn read(&self, puzzle_id: &str, key: &str) -> Result<Vec<u8>, Error> {
+        match self {
+            TableStore::Directory { path, .. } => {
+                let mut data = vec![];
+                File::open(table_path(path, puzzle_id, key))?.read_to_end(&mut data)?;
+                Ok(data)
+            },
+            TableStore::Memory(tables) => tables.read().unwrap()
+                .get(&memory_key(puzzle_id, key))
+                .map(|data| data.as_ref().clone())
+                .ok_or_else(|| not_found(puzzle_id, key)),
+            TableStore::None => Err(not_found(puzzle_id, key)),
+        }
+    }
+
+    //Keys and sizes in bytes of all tables of a puzzle
+    pub fn list(&self, puzzle_id: &str) -> Result<Vec<(String, u64)>, Error> {
+        let mut tables: Vec<(String, u64)> = match self {
+            TableStore::Directory { path, .. } => {
+                let dir = path.join(puzzle_id);
+                if !dir.exists() {
+                    return Ok(vec![]);
+                }
+                fs::read_dir(dir)?
+                    .filter_map(Result::ok)
+                    .filter_map(|e| {
+                        let name = e.file_name().to_string_lossy().to_string();
+                        Some((name.strip_suffix(".tbl")?.to_string(), e.metadata().ok()?.len()))
+                    })
+                    .collect()
+            },
+            TableStore::Memory(tables) => {
+                let prefix = memory_key(puzzle_id, "");
+                tables.read().unwrap().iter()
+                    .filter_map(|(key, data)| Some((key.strip_prefix(&prefix)?.to_string(), data.len() as u64)))
+                    .collect()
+            },
+            TableStore::None => vec![],
+        };
+        tables.sort();
+        Ok(tables)
+    }
+
+    pub fn remove(&self, puzzle_id: &str, key: &str) -> Result<(), Error> {
+        match self {
+            TableStore::Directory { read_only: true, .. } => Err(Error::Io(std::io::Error::new(std::io::ErrorKind::PermissionDenied, "The table store is read-only"))),
+            TableStore::Directory { path, .. } => Ok(fs::remove_file(table_path(path, puzzle_id, key))?),
+            TableStore::Memory(tables) => {
+                tables.write().unwrap().remove(&memory_key(puzzle_id, key));
+                Ok(())
+            },
+            TableStore::None => Ok(()),
+        }
+    }
+}
+
+impl Default for TableStore {
+    fn default() -> Self {
+        Self::from_env()
+    }
+}
+
+pub fn table_path(dir: &Path, puzzle_id: &str, key: &str) -> PathBuf {
+    dir.join(puzzle_id).join(format!("{key}.tbl"))
+}
+
+fn memory_key(puzzle_id: &str, key: &str) -> String {
+    format!("{puzzle_id}/{key}")
+}
+
+fn not_found(puzzle_id: &str, key: &str) -> Error {
+    Error::Io(std::io::Error::new(std::io::ErrorKind::NotFound, format!("No {puzzle_id} {key} table in the store")))
+}
+
+#[cfg(test)]
+mod tests {
+    use std::ffi::OsString;
+    use std::fs;
+    use std::path::PathBuf;
+
+    use crate::cube::Turn333;
+    use crate::solver::lookup_table::DynamicLookupTable;
+    use crate::solver::moveset::MoveSet;
+    use crate::solver::table_header::{Compression, TableId};
+    use crate::Error;
+
+    use super::{table_path, TableStore};
+
+    fn test_dir(name: &str) -> PathBuf {
+        let dir = std::env::temp_dir().join(format!("cubelib-store-{name}-{}", std::process::id()));
+        let _ = fs::remove_dir_all(&dir);
+        dir
+    }
+
+    fn table() -> (DynamicLookupTable, TableId) {
+        let mut table = DynamicLookupTable::new(10);
+        for i in 0..10 {
+            table.set(i, i as u8 % 4);
+        }
+        let id = TableId::new("test", 10, &MoveSet::from_moves(&[Turn333::R, Turn333::U]), 1);
+        (table, id)
+    }
+
+    fn entries(table: &DynamicLookupTable) -> Vec<u8> {
+        (0..table.len()).map(|i| table.get(i)).collect()
+    }
+
+    #[test]
+    fn parses_the_mode() {
+        let dir = Some(OsString::from("/tables"));
+        let directory = |mode: Option<&str>| TableStore::from_vars(mode.map(str::to_string), dir.clone());
+        assert!(matches!(directory(None), TableStore::Directory { ref path, read_only: false } if path.as_path() == std::path::Path::new("/tables")));
+        assert!(matches!(directory(Some("read-write")), TableStore::Directory { read_only: false, .. }));
+        assert!(matches!(directory(Some("Read-Only")), TableStore::Directory { read_only: true, .. }));
+        assert!(matches!(directory(Some("none")), TableStore::None));
+        assert!(matches!(directory(Some("invalid")), TableStore::Directory { read_only: false, .. }));
+        match TableStore::from_vars(None, None) {
+            TableStore::Directory { path, read_only } => {
+                assert!(path.ends_with(".cubelib/tables"));
+                assert!(!read_only);
+            },
+            store => assert!(matches!(store, TableStore::None)),
+        }
+    }
+
+    #[test]
+    fn read_only_stores_refuse_changes() {
+        let dir = test_dir("read-only");
+        let (table, id) = table();
+        let writable = TableStore::directory(&dir);
+        assert!(writable.save("333", "test", &table, &id, Compression::None).unwrap());
+
+        let read_only = writable.clone().read_only();
+        assert!(!read_only.is_writable());
+        assert!(!read_only.save("333", "other", &table, &id, Compression::None).unwrap());
+        assert!(!table_path(&dir, "333", "other").exists());
+        match read_only.remove("333", "test") {
+            Err(Error::Io(err)) => assert_eq!(err.kind(), std::io::ErrorKind::PermissionDenied),
+            _ => panic!("Expected the read-only store to refuse removing tables"),
+        }
+        assert!(table_path(&dir, "333", "test").exists());
+        let loaded: DynamicLookupTable = read_only.load("333", "test", &id).unwrap();
+        assert_eq!(entries(&loaded), entries(&table));
+        fs::remove_dir_all(dir).unwrap();
+    }
+
+    fn round_trip(store: TableStore) {
+        let (table, id) = table();
+        assert!(store.list("333").unwrap().is_empty());
+        assert!(store.save("333", "test", &table, &id, Compression::None).unwrap());
+        assert!(store.save("333", "compressed", &table, &id, Compression::Lz4).unwrap());
+
+        let listed = store.list("333").unwrap();
+        assert_eq!(listed.iter().map(|(key, _)| key.as_str()).collect::<Vec<_>>(), vec!["compressed", "test"]);
+        assert_eq!(listed[1].1, store.read("333", "test").unwrap().len() as u64);
+        assert!(store.list("222").unwrap().is_empty());
+
+        for key in ["test", "compressed"] {
+            let loaded: DynamicLookupTable = store.load("333", key, &id).unwrap();
+            assert_eq!(entries(&loaded), entries(&table));
+        }
+        let other_id = TableId::new("test", 10, &MoveSet::from_moves(&[Turn333::R, Turn333::U]), 2);
+        assert!(store.load::<DynamicLookupTable>("333", "test", &other_id).is_err());
+
+        store.remove("333", "test").unwrap();
+        assert_eq!(store.list("333").unwrap().len(), 1);
+        assert!(store.read("333", "test").is_err());
+    }
+
+    #[test]
+    fn memory_round_trip() {
+        let store = TableStore::memory();
+        round_trip(store.clone());
+        //Clones share their tables
+        assert_eq!(store.list("333").unwrap().len(), 1);
+    }
+
+    #[test]
+    fn directory_round_trip() {
+        let dir = test_dir("round-trip");
+        round_trip(TableStore::directory(&dir));
+        fs::remove_dir_all(dir).unwrap();
+    }
+
+    #[test]
+    fn disabled_stores_keep_nothing() {
+        let (table, id) = table();
+        let store = TableStore::None;
+        assert!(!store.save("333", "test", &table, &id, Compression::None).unwrap());
+        assert!(store.list("333").unwrap().is_empty());
+        assert!(store.load::<DynamicLookupTable>("333", "test", &id).is_err());
+    }
+}
@@ -1,6 +1,7 @@
 use std::hash::Hash;
 
-pub trait Coord<const SIZE: usize>: Into<usize> + Copy + Clone + Eq + PartialEq + Hash {
+//Coordinates are part of the step variants, which are shared between threads
+pub trait Coord<const SIZE: usize>: Into<usize> + Copy + Clone + Eq + PartialEq + Hash + Send + Sync + 'static {
     fn size() -> usize {
         SIZE
     }
@@ -28,3 +29,19 @@ impl <P> From<&P> for ZeroCoord {
     }
 }
 
+//Index into a table whose size is only known at runtime, see lookup_table::generate_sized
+#[derive(Copy, Clone, PartialEq, Eq, Hash, Debug)]
+pub struct IndexCoord(pub usize);
+
+impl From<IndexCoord> for usize {
+    fn from(coord: IndexCoord) -> Self {
+        coord.0
+    }
+}
+
+impl Coord<0> for IndexCoord {
+    fn val(&self) -> usize {
+        self.0
+    }
+}
+
@@ -0,0 +1,423 @@
+use std::cmp::min;
+use std::sync::Arc;
+
+use log::{debug, info};
+#[cfg(feature = "fs")]
+use log::error;
+#[cfg(not(target_arch = "wasm32"))]
+use std::time::Instant;
+
+use crate::algs::Algorithm;
+use crate::cube::*;
+use crate::defs::*;
+use crate::solver::lookup_table;
+use crate::solver::lookup_table::DynamicLookupTable;
+use crate::solver::moveset::MoveSet;
+#[cfg(feature = "fs")]
+use crate::solver::table_header;
+#[cfg(feature = "fs")]
+use crate::solver::table_header::{Compression, TableId};
+#[cfg(feature = "fs")]
+use crate::solver::table_store::TableStore;
+use crate::steps::coord::{Coord, IndexCoord};
+use crate::steps::registry;
+use crate::steps::registry::StepDefinition;
+use crate::steps::step::{DefaultStepOptions, PostStepCheck, PreStepCheck, Step, StepConfig, StepVariant};
+use crate::steps::tables::PruningTables333;
+use crate::Error;
+
+//Steps that bring the cube into a subgroup defined by a coordinate, e.g. a cross or a 2x2x2 block.
+//The coordinate maps every cube to an index below its size, and the step is solved when the index is the one of a solved cube.
+//Goals that are too large for a single table can be split into several coordinates. All of them have to be solved
+//and the heuristic is the maximum of their tables.
+//
+//    let cross = Arc::new(CustomStepBuilder::new("cross", 190080, cross_index)
+//        .variant("d", vec![])
+//        .variant("u", vec![Transformation333::X2])
+//        .build());
+//    let step = cross.step();
+//
+//Tables are cached on disk under the name of the step, so the name has to change whenever the coordinate does.
+
+pub type StateIndex = Arc<dyn Fn(&Cube333) -> usize + Send + Sync>;
+
+#[derive(Clone)]
+pub struct CustomStepBuilder {
+    name: String,
+    kind: StepKind,
+    //Size, index and the goal states besides the solved cube of every coordinate
+    coords: Vec<(usize, StateIndex, Vec<Cube333>)>,
+    moves: Vec<Turn333>,
+    variants: Vec<(String, Vec<Transformation333>)>,
+    #[cfg(feature = "fs")]
+    store: Option<TableStore>,
+}
+
+impl CustomStepBuilder {
+    pub fn new<F: Fn(&Cube333) -> usize + Send + Sync + 'static>(name: &str, size: usize, index: F) -> Self {
+        CustomStepBuilder {
+            name: name.to_lowercase(),
+            kind: StepKind::Other(name.to_lowercase()),
+            coords: vec![(size, Arc::new(index), vec![])],
+            moves: Turn333::ALL.to_vec(),
+            variants: vec![],
+            #[cfg(feature = "fs")]
+            store: None,
+        }
+    }
+
+    pub fn from_coord<const C_SIZE: usize, C>(name: &str) -> Self where C: Coord<C_SIZE> + for<'a> From<&'a Cube333> {
+        Self::new(name, C_SIZE, |cube| C::from(cube).val())
+    }
+
+    //Adds another coordinate that has to be solved as well
+    pub fn coordinate<F: Fn(&Cube333) -> usize + Send + Sync + 'static>(mut self, size: usize, index: F) -> Self {
+        self.coords.push((size, Arc::new(index), vec![]));
+        self
+    }
+
+    //Cubes that solve the last added coordinate in addition to the solved cube. Everything the coordinate maps them to
+    //counts as solved, e.g. for goals that only care about the orientation of some pieces
+    pub fn goal_states(mut self, cubes: Vec<Cube333>) -> Self {
+        if let Some((_, _, goal_states)) = self.coords.last_mut() {
+            goal_states.extend(cubes);
+        }
+        self
+    }
+
+    //Step kind of the built step. Defaults to StepKind::Other with the name of the step
+    pub fn kind(mut self, kind: StepKind) -> Self {
+        self.kind = kind;
+        self
+    }
+
+    //Moves the step may use. Defaults to all 18 outer turns
+    pub fn moves(mut self, moves: Vec<Turn333>) -> Self {
+        self.moves = moves;
+        self
+    }
+
+    //Adds a variant that solves the subgroup after applying the given transformations, e.g. the same cross on a different face.
+    //Without any variants the step has a single one without transformations
+    pub fn variant(mut self, name: &str, pre_trans: Vec<Transformation333>) -> Self {
+        self.variants.push((name.to_lowercase(), pre_trans));
+        self
+    }
+
+    //Where the tables are cached. Defaults to the store of the PruningTables333 for registered steps
+    //and to TableStore::from_env otherwise
+    #[cfg(feature = "fs")]
+    pub fn store(mut self, store: TableStore) -> Self {
+        self.store = Some(store);
+        self
+    }
+
+    //Loads the table from disk or generates it
+    pub fn build(self) -> CustomStep {
+        let move_set = MoveSet::from_moves(&self.moves);
+        let variants = if self.variants.is_empty() {
+            vec![(self.name.clone(), vec![])]
+        } else {
+            self.variants
+        };
+        #[cfg(feature = "fs")]
+        let store = self.store.unwrap_or_default();
+        let tables = self.coords.into_iter()
+            .enumerate()
+            .map(|(i, (size, index, goal_states))| {
+                let name = if i == 0 { self.name.clone() } else { format!("{}-{i}", self.name) };
+                #[cfg(feature = "fs")]
+                let table = load_or_gen_table(&store, &name, size, &index, &goal_states, &move_set);
+                #[cfg(not(feature = "fs"))]
+                let table = gen_table(&name, size, &index, &goal_states, &move_set);
+                (index, table)
+            })
+            .collect();
+        CustomStep {
+            kind: self.kind,
+            move_set,
+            variants,
+            tables,
+        }
+    }
+
+    //Registers the step so it can be used in build_steps and the step DSL under its name.
+    //The table is only generated once the step is used
+    pub fn register(self, predecessors: Vec<Option<StepKind>>) {
+        let kind = self.kind.clone();
+        let key = table_key(&self.name);
+        let build_key = key.clone();
+        registry::register_step(kind, StepDefinition::new(predecessors,
+            move |tables: &mut PruningTables333| if !tables.has_custom_table(&key) {
+                let builder = self.clone();
+                #[cfg(feature = "fs")]
+                let builder = match builder.store {
+                    Some(_) => builder,
+                    None => builder.store(tables.store().clone()),
+                };
+                tables.insert_custom_table(&key, builder.build());
+            },
+            move |config, tables| tables.shared_custom_table::<CustomStep>(&build_key)
+                .ok_or(Error::MissingTable(build_key.clone()))?
+                .from_step_config(config)
+        ));
+    }
+}
+
+pub struct CustomStep {
+    kind: StepKind,
+    move_set: MoveSet,
+    variants: Vec<(String, Vec<Transformation333>)>,
+    tables: Vec<(StateIndex, DynamicLookupTable)>,
+}
+
+impl CustomStep {
+    pub fn kind(&self) -> &StepKind {
+        &self.kind
+    }
+
+    pub fn tables(&self) -> impl Iterator<Item = &DynamicLookupTable> {
+        self.tables.iter().map(|(_, table)| table)
+    }
+
+    //The longest optimal solution of the step. With multiple coordinates this is only a lower bound
+    pub fn max_depth(&self) -> u8 {
+        self.tables()
+            .flat_map(|table| (0..table.len()).map(|i| table.get(i)))
+            .filter(|d| *d != 0xFF)
+            .max()
+            .unwrap_or(0)
+    }
+
+    pub fn heuristic(&self, cube: &Cube333) -> u8 {
+        self.tables.iter()
+            .map(|(index, table)| table.get(index(cube)))
+            .max()
+            .unwrap_or(0)
+    }
+
+    //The variants keep the step alive, so the step can be used after the PruningTables333 it was stored in is gone
+    pub fn step(self: &Arc<Self>) -> Step<'static> {
+        self.step_variants(self.variants.clone())
+    }
+
+    pub fn from_step_config(self: &Arc<Self>, config: StepConfig) -> Result<(Step<'static>, DefaultStepOptions), Error> {
+        let step = if let Some(substeps) = config.substeps {
+            let variants: Result<Vec<(String, Vec<Transformation333>)>, Error> = substeps.into_iter()
+                .map(|substep| {
+                    let substep = substep.to_lowercase();
+                    self.variants.iter()
+                        .find(|(name, _)| *name == substep)
+                        .cloned()
+                        .ok_or(Error::InvalidConfig(format!("Invalid {} substep {substep}", self.kind)))
+                })
+                .collect();
+            self.step_variants(variants?)
+        } else {
+            self.step()
+        };
+
+        if !config.params.is_empty() {
+            return Err(Error::InvalidConfig(format!("Unrecognized parameters: {:?}", config.params.keys())))
+        }
+
+        let search_opts = DefaultStepOptions::new(
+            config.min.unwrap_or(0),
+            //20 moves solve any cube, which is the only bound we know if the tables only cover parts of the goal
+            config.max.unwrap_or_else(|| if self.tables.len() == 1 { self.max_depth() } else { 20 }),
+            config.absolute_min,
+            config.absolute_max,
+            config.niss.unwrap_or(NissSwitchType::Before),
+            if config.quality == 0 {
+                None
+            } else {
+                config.step_limit.or(Some(config.quality))
+            }
+        );
+        Ok((step, search_opts))
+    }
+
+    fn step_variants(self: &Arc<Self>, variants: Vec<(String, Vec<Transformation333>)>) -> Step<'static> {
+        let variants = variants.into_iter()
+            .map(|(name, pre_trans)| {
+                let variant: Box<dyn StepVariant> = Box::new(CustomStepVariant {
+                    step: self.clone(),
+                    pre_trans,
+                    name,
+                });
+                variant
+            })
+            .collect();
+        Step::new(variants, self.kind.clone(), true)
+    }
+}
+
+struct CustomStepVariant {
+    step: Arc<CustomStep>,
+    pre_trans: Vec<Transformation333>,
+    name: String,
+}
+
+impl PreStepCheck for CustomStepVariant {
+    fn is_cube_ready(&self, _: &Cube333) -> bool {
+        true
+    }
+}
+
+impl PostStepCheck for CustomStepVariant {
+    fn is_solution_admissible(&self, _: &Cube333, _: &Algorithm) -> bool {
+        true
+    }
+}
+
+impl StepVariant for CustomStepVariant {
+    fn move_set(&self, _: &Cube333, _: u8) -> &'_ MoveSet {
+        &self.step.move_set
+    }
+
+    fn pre_step_trans(&self) -> &'_ Vec<Transformation333> {
+        &self.pre_trans
+    }
+
+    fn heuristic(&self, cube: &Cube333, _: u8, can_niss: bool) -> u8 {
+        let heuristic = self.step.heuristic(cube);
+        if can_niss {
+            min(1, heuristic)
+        } else {
+            heuristic
+        }
+    }
+
+    fn name(&self) -> &str {
+        &self.name
+    }
+}
+
+fn table_key(name: &str) -> String {
+    format!("custom-{name}")
+}
+
+#[cfg(feature = "fs")]
+fn load_or_gen_table(store: &TableStore, name: &str, size: usize, index: &StateIndex, goal_states: &[Cube333], move_set: &MoveSet) -> DynamicLookupTable {
+    let key = table_key(name);
+    let id = TableId::new(&key, size, move_set, generator(index, goal_states));
+    match store.load("333", &key, &id) {
+        Ok(table) => {
+            info!("Loaded {name} table from disk");
+            return table;
+        },
+        Err(e) => debug!("Unable to load {name} table from disk: {e}"),
+    }
+    let table = gen_table(name, size, index, goal_states, move_set);
+    match store.save("333", &key, &table, &id, Compression::None) {
+        Ok(true) => info!("Saved {name} table to disk"),
+        Ok(false) => {},
+        Err(e) => error!("Error saving {name} table to disk: {e}"),
+    }
+    table
+}
+
+//Tables with other goal states have different contents, so they get a different id
+#[cfg(feature = "fs")]
+fn generator(index: &StateIndex, goal_states: &[Cube333]) -> u32 {
+    if goal_states.is_empty() {
+        return PruningTables333::VERSION;
+    }
+    let mut goals: Vec<usize> = goal_states.iter().map(|cube| index(cube)).collect();
+    goals.sort();
+    goals.dedup();
+    let bytes: Vec<u8> = goals.iter().flat_map(|goal| (*goal as u64).to_le_bytes()).collect();
+    PruningTables333::VERSION ^ table_header::checksum(&bytes) as u32
+}
+
+fn gen_table(name: &str, size: usize, index: &StateIndex, goal_states: &[Cube333], move_set: &MoveSet) -> DynamicLookupTable {
+    info!("Generating {name} pruning table...");
+    #[cfg(not(target_arch = "wasm32"))]
+    let time = Instant::now();
+    let goal_states: Vec<Cube333> = [Cube333::default()].into_iter().chain(goal_states.iter().cloned()).collect();
+    let table = lookup_table::generate_from_goal_states(size,
+                                                        move_set,
+                                                        &goal_states,
+                                                        &|c: &Cube333| IndexCoord(index(c)),
+                                                        &|| DynamicLookupTable::new(size),
+                                                        &|table, coord, val| table.set(coord.0, val));
+    #[cfg(not(target_arch = "wasm32"))]
+    debug!("Took {}ms", time.elapsed().as_millis());
+    table
+}
+
+#[cfg(all(test, feature = "333eo", feature = "fs"))]
+mod tests {
+    use std::str::FromStr;
+    use std::sync::Arc;
+    use std::sync::atomic::{AtomicUsize, Ordering};
+
+    use crate::algs::Algorithm;
+    use crate::cube::*;
+    use crate::cube::turn::ApplyAlgorithm;
+    use crate::solver::df_search::CancelToken;
+    use crate::solver::moveset::MoveSet;
+    use crate::solver::table_store::TableStore;
+    use crate::steps::coord::Coord;
+    use crate::steps::eo::coords::EOCoordFB;
+    use crate::steps::step::StepConfig;
+
+    use super::CustomStepBuilder;
+
+    fn eo_builder(calls: Arc<AtomicUsize>, store: &TableStore) -> CustomStepBuilder {
+        CustomStepBuilder::new("custom-test-eofb", 2048, move |cube| {
+            calls.fetch_add(1, Ordering::Relaxed);
+            EOCoordFB::from(cube).val()
+        })
+            .variant("fb", vec![])
+            .store(store.clone())
+    }
+
+    #[test]
+    fn solves_with_a_custom_step() {
+        let store = TableStore::memory();
+        let step = Arc::new(eo_builder(Arc::new(AtomicUsize::new(0)), &store).build());
+        assert_eq!(step.max_depth(), 7);
+
+        let (step, search_opts) = step.from_step_config(StepConfig::new(step.kind().clone())).unwrap();
+        let steps = vec![(step, search_opts)];
+        let mut cube = Cube333::default();
+        cube.apply_alg(&Algorithm::from_str("R' U' F L2 D' B2 U' F2 D' L2 B2 R2 U' R F' U2 L D' B2 R U R' U' F").unwrap());
+        let cancel_token = CancelToken::default();
+        let solutions: Vec<_> = crate::solver::solve_steps(cube, &steps, &cancel_token).take(10).collect();
+        assert_eq!(solutions.len(), 10);
+        assert!(solutions.windows(2).all(|w| w[0].len() <= w[1].len()));
+        for solution in solutions {
+            assert_eq!(solution.steps[0].variant, "fb");
+            let mut cube = cube;
+            cube.apply_alg(&solution.into());
+            assert_eq!(EOCoordFB::from(&cube).val(), 0);
+        }
+    }
+
+    #[test]
+    fn tables_are_cached_in_the_store() {
+        let store = TableStore::memory();
+        let calls = Arc::new(AtomicUsize::new(0));
+        let generated = eo_builder(calls.clone(), &store).build();
+        assert!(calls.load(Ordering::Relaxed) > 0);
+        let keys: Vec<String> = store.list("333").unwrap().into_iter().map(|(key, _)| key).collect();
+        assert_eq!(keys, vec!["custom-custom-test-eofb".to_string()]);
+
+        calls.store(0, Ordering::Relaxed);
+        let loaded = eo_builder(calls.clone(), &store).build();
+        assert_eq!(calls.load(Ordering::Relaxed), 0);
+        let generated: Vec<u8> = generated.tables().flat_map(|t| (0..t.len()).map(|i| t.get(i))).collect();
+        let loaded: Vec<u8> = loaded.tables().flat_map(|t| (0..t.len()).map(|i| t.get(i))).collect();
+        assert_eq!(generated, loaded);
+    }
+
+    #[test]
+    fn runtime_move_sets_are_shared() {
+        let moves = [Turn333::R, Turn333::U];
+        let a = MoveSet::from_moves(&moves);
+        let b = MoveSet::from_moves(&moves);
+        assert_eq!(a.st_moves, &moves);
+        assert!(std::ptr::eq(a.st_moves, b.st_moves));
+    }
+}
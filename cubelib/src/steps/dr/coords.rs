@@ -92,13 +92,28 @@ impl From<&EdgeCube333> for UDSliceUnsortedCoord {
     }
 }
 
+impl DRUDEOFBCoord {
+    //Corners and edges are turned independently, so the coordinate can be combined from the move tables of both parts
+    pub(crate) const CORNERS_SIZE: usize = 2187;
+    pub(crate) const EDGES_SIZE: usize = 495;
+
+    pub(crate) fn corners(value: &Cube333) -> usize {
+        COUDCoord::from(&value.corners).val()
+    }
+
+    pub(crate) fn edges(value: &Cube333) -> usize {
+        UDSliceUnsortedCoord::from(&value.edges).val()
+    }
+
+    pub(crate) fn from_parts(co: usize, ud_slice: usize) -> Self {
+        DRUDEOFBCoord((co * Self::EDGES_SIZE + ud_slice) as u32)
+    }
+}
+
 impl From<&Cube333> for DRUDEOFBCoord {
     #[inline]
     fn from(value: &Cube333) -> Self {
-        let ud_slice = UDSliceUnsortedCoord::from(&value.edges).val();
-        let co = COUDCoord::from(&value.corners).val();
-        let index = co * UDSliceUnsortedCoord::size() + ud_slice;
-        DRUDEOFBCoord(index as u32)
+        Self::from_parts(Self::corners(value), Self::edges(value))
     }
 }
 
@@ -1,7 +1,8 @@
-use std::rc::Rc;
+use std::sync::Arc;
 use std::vec;
 
 use itertools::Itertools;
+use crate::Error;
 use crate::cube::*;
 use crate::defs::*;
 use crate::solver::lookup_table::LookupTable;
@@ -62,9 +63,9 @@ pub const DR_UD_EO_FB_MOVESET: MoveSet333 = MoveSet333 {
 };
 
 pub type DRPruningTable = LookupTable<{ DRUDEOFB_SIZE }, DRUDEOFBCoord>;
-pub type DRPruningTableStep<'a> = DefaultPruningTableStep<'a, {DRUDEOFB_SIZE}, DRUDEOFBCoord, 2048, EOCoordFB>;
+pub type DRPruningTableStep = DefaultPruningTableStep<{DRUDEOFB_SIZE}, DRUDEOFBCoord, 2048, EOCoordFB>;
 
-pub fn from_step_config<'a>(table: &'a DRPruningTable, #[cfg(feature = "333htr")] subset_table: &'a HTRSubsetTable, mut config: StepConfig) -> Result<(Step333<'a>, DefaultStepOptions), String> {
+pub fn from_step_config(table: Arc<DRPruningTable>, #[cfg(feature = "333htr")] subset_table: Arc<HTRSubsetTable>, mut config: StepConfig) -> Result<(Step333<'static>, DefaultStepOptions), Error> {
     #[cfg(feature = "333htr")]
     let post_step_filters: Vec<Box<dyn PostStepCheck>> = config.params.remove("subsets")
         .map(|x|x.split(",").map(|x|x.to_string()).collect_vec())
@@ -77,26 +78,26 @@ pub fn from_step_config<'a>(table: &'a DRPruningTable, #[cfg(feature = "333htr")
     #[cfg(not(feature = "333htr"))]
     let post_step_filters: Vec<Box<dyn PostStepCheck>> = vec![];
 
-    let psc = Rc::new(post_step_filters);
+    let psc = Arc::new(post_step_filters);
 
     let step = if let Some(substeps) = config.substeps {
-        let variants: Result<Vec<Vec<Box<dyn StepVariant>>>, String> = substeps.into_iter().map(|step| match step.to_lowercase().as_str() {
-            "ud" | "drud" => Ok(dr_step_variants(table, vec![CubeAxis::FB, CubeAxis::LR], vec![CubeAxis::UD], psc.clone())),
-            "fb" | "drfb" => Ok(dr_step_variants(table, vec![CubeAxis::UD, CubeAxis::LR], vec![CubeAxis::FB], psc.clone())),
-            "lr" | "drlr" => Ok(dr_step_variants(table, vec![CubeAxis::UD, CubeAxis::FB], vec![CubeAxis::LR], psc.clone())),
-
-            "eoud" => Ok(dr_step_variants(table, vec![CubeAxis::UD], vec![CubeAxis::FB, CubeAxis::LR], psc.clone())),
-            "eofb" => Ok(dr_step_variants(table, vec![CubeAxis::FB], vec![CubeAxis::UD, CubeAxis::LR], psc.clone())),
-            "eolr" => Ok(dr_step_variants(table, vec![CubeAxis::LR], vec![CubeAxis::UD, CubeAxis::FB], psc.clone())),
-
-            "drud-eofb" => Ok(dr_step_variants(table, vec![CubeAxis::FB], vec![CubeAxis::UD], psc.clone())),
-            "drud-eolr" => Ok(dr_step_variants(table, vec![CubeAxis::LR], vec![CubeAxis::UD], psc.clone())),
-            "drfb-eoud" => Ok(dr_step_variants(table, vec![CubeAxis::UD], vec![CubeAxis::FB], psc.clone())),
-            "drfb-eolr" => Ok(dr_step_variants(table, vec![CubeAxis::LR], vec![CubeAxis::FB], psc.clone())),
-            "drlr-eoud" => Ok(dr_step_variants(table, vec![CubeAxis::UD], vec![CubeAxis::LR], psc.clone())),
-            "drlr-eofb" => Ok(dr_step_variants(table, vec![CubeAxis::FB], vec![CubeAxis::LR], psc.clone())),
-
-            x => Err(format!("Invalid DR substep {x}"))
+        let variants: Result<Vec<Vec<Box<dyn StepVariant>>>, Error> = substeps.into_iter().map(|step| match step.to_lowercase().as_str() {
+            "ud" | "drud" => Ok(dr_step_variants(table.clone(), vec![CubeAxis::FB, CubeAxis::LR], vec![CubeAxis::UD], psc.clone())),
+            "fb" | "drfb" => Ok(dr_step_variants(table.clone(), vec![CubeAxis::UD, CubeAxis::LR], vec![CubeAxis::FB], psc.clone())),
+            "lr" | "drlr" => Ok(dr_step_variants(table.clone(), vec![CubeAxis::UD, CubeAxis::FB], vec![CubeAxis::LR], psc.clone())),
+
+            "eoud" => Ok(dr_step_variants(table.clone(), vec![CubeAxis::UD], vec![CubeAxis::FB, CubeAxis::LR], psc.clone())),
+            "eofb" => Ok(dr_step_variants(table.clone(), vec![CubeAxis::FB], vec![CubeAxis::UD, CubeAxis::LR], psc.clone())),
+            "eolr" => Ok(dr_step_variants(table.clone(), vec![CubeAxis::LR], vec![CubeAxis::UD, CubeAxis::FB], psc.clone())),
+
+            "drud-eofb" => Ok(dr_step_variants(table.clone(), vec![CubeAxis::FB], vec![CubeAxis::UD], psc.clone())),
+            "drud-eolr" => Ok(dr_step_variants(table.clone(), vec![CubeAxis::LR], vec![CubeAxis::UD], psc.clone())),
+            "drfb-eoud" => Ok(dr_step_variants(table.clone(), vec![CubeAxis::UD], vec![CubeAxis::FB], psc.clone())),
+            "drfb-eolr" => Ok(dr_step_variants(table.clone(), vec![CubeAxis::LR], vec![CubeAxis::FB], psc.clone())),
+            "drlr-eoud" => Ok(dr_step_variants(table.clone(), vec![CubeAxis::UD], vec![CubeAxis::LR], psc.clone())),
+            "drlr-eofb" => Ok(dr_step_variants(table.clone(), vec![CubeAxis::FB], vec![CubeAxis::LR], psc.clone())),
+
+            x => Err(Error::InvalidConfig(format!("Invalid DR substep {x}")))
         }).collect();
         let variants = variants?.into_iter().flat_map(|v|v).collect_vec();
         Step333::new(variants, StepKind::DR, true)
@@ -105,7 +106,7 @@ pub fn from_step_config<'a>(table: &'a DRPruningTable, #[cfg(feature = "333htr")
     };
 
     if !config.params.is_empty() {
-        return Err(format!("Unreognized parameters: {:?}", config.params.keys()))
+        return Err(Error::InvalidConfig(format!("Unrecognized parameters: {:?}", config.params.keys())))
     }
 
     let search_opts = DefaultStepOptions::new(
@@ -123,18 +124,18 @@ pub fn from_step_config<'a>(table: &'a DRPruningTable, #[cfg(feature = "333htr")
     Ok((step, search_opts))
 }
 
-fn dr_step_variants<'a>(table: &'a DRPruningTable, eo_axis: Vec<CubeAxis>, dr_axis: Vec<CubeAxis>, psc: Rc<Vec<Box<dyn PostStepCheck + 'a>>>) -> Vec<Box<dyn StepVariant + 'a>> {
+fn dr_step_variants(table: Arc<DRPruningTable>, eo_axis: Vec<CubeAxis>, dr_axis: Vec<CubeAxis>, psc: Arc<Vec<Box<dyn PostStepCheck>>>) -> Vec<Box<dyn StepVariant>> {
     eo_axis
         .into_iter()
         .flat_map(|eo| dr_axis.clone().into_iter().map(move |dr| (eo, dr)))
         .flat_map(move |x| {
             let x: Option<Box<dyn StepVariant>> = match x {
-                (CubeAxis::UD, CubeAxis::FB) => Some(Box::new(DRPruningTableStep::new(&DR_UD_EO_FB_MOVESET, vec![Transformation333::X], table, psc.clone(), "fb-eoud"))),
-                (CubeAxis::UD, CubeAxis::LR) => Some(Box::new(DRPruningTableStep::new(&DR_UD_EO_FB_MOVESET, vec![Transformation333::X, Transformation333::Z], table, psc.clone(), "lr-eoud"))),
-                (CubeAxis::FB, CubeAxis::UD) => Some(Box::new(DRPruningTableStep::new(&DR_UD_EO_FB_MOVESET, vec![], table, psc.clone(), "ud-eofb"))),
-                (CubeAxis::FB, CubeAxis::LR) => Some(Box::new(DRPruningTableStep::new(&DR_UD_EO_FB_MOVESET, vec![Transformation333::Z], table, psc.clone(), "lr-eofb"))),
-                (CubeAxis::LR, CubeAxis::UD) => Some(Box::new(DRPruningTableStep::new(&DR_UD_EO_FB_MOVESET, vec![Transformation333::Y], table, psc.clone(), "ud-eolr"))),
-                (CubeAxis::LR, CubeAxis::FB) => Some(Box::new(DRPruningTableStep::new(&DR_UD_EO_FB_MOVESET, vec![Transformation333::Y, Transformation333::Z], table, psc.clone(), "fb-eolr"))),
+                (CubeAxis::UD, CubeAxis::FB) => Some(Box::new(DRPruningTableStep::new(&DR_UD_EO_FB_MOVESET, vec![Transformation333::X], table.clone(), psc.clone(), "fb-eoud"))),
+                (CubeAxis::UD, CubeAxis::LR) => Some(Box::new(DRPruningTableStep::new(&DR_UD_EO_FB_MOVESET, vec![Transformation333::X, Transformation333::Z], table.clone(), psc.clone(), "lr-eoud"))),
+                (CubeAxis::FB, CubeAxis::UD) => Some(Box::new(DRPruningTableStep::new(&DR_UD_EO_FB_MOVESET, vec![], table.clone(), psc.clone(), "ud-eofb"))),
+                (CubeAxis::FB, CubeAxis::LR) => Some(Box::new(DRPruningTableStep::new(&DR_UD_EO_FB_MOVESET, vec![Transformation333::Z], table.clone(), psc.clone(), "lr-eofb"))),
+                (CubeAxis::LR, CubeAxis::UD) => Some(Box::new(DRPruningTableStep::new(&DR_UD_EO_FB_MOVESET, vec![Transformation333::Y], table.clone(), psc.clone(), "ud-eolr"))),
+                (CubeAxis::LR, CubeAxis::FB) => Some(Box::new(DRPruningTableStep::new(&DR_UD_EO_FB_MOVESET, vec![Transformation333::Y, Transformation333::Z], table.clone(), psc.clone(), "fb-eolr"))),
                 (_eo, _dr) => None,
             };
             x
@@ -142,12 +143,12 @@ fn dr_step_variants<'a>(table: &'a DRPruningTable, eo_axis: Vec<CubeAxis>, dr_ax
         .collect_vec()
 }
 
-pub fn dr<'a>(table: &'a DRPruningTable, eo_axis: Vec<CubeAxis>, dr_axis: Vec<CubeAxis>, post_step_checks: Rc<Vec<Box<dyn PostStepCheck + 'a>>>) -> Step333<'a> {
+pub fn dr(table: Arc<DRPruningTable>, eo_axis: Vec<CubeAxis>, dr_axis: Vec<CubeAxis>, post_step_checks: Arc<Vec<Box<dyn PostStepCheck>>>) -> Step333<'static> {
     let step_variants = dr_step_variants(table, eo_axis, dr_axis, post_step_checks);
     Step::new(step_variants, StepKind::DR, true)
 }
 
-pub fn dr_any<'a>(table: &'a DRPruningTable, post_step_checks: Rc<Vec<Box<dyn PostStepCheck + 'a>>>) -> Step333<'a> {
+pub fn dr_any(table: Arc<DRPruningTable>, post_step_checks: Arc<Vec<Box<dyn PostStepCheck>>>) -> Step333<'static> {
     dr(table, vec![CubeAxis::UD, CubeAxis::FB, CubeAxis::LR], vec![CubeAxis::UD, CubeAxis::FB, CubeAxis::LR], post_step_checks)
 }
 
@@ -1,12 +1,13 @@
 use std::cmp::min;
 use std::collections::HashMap;
-use std::rc::Rc;
+use std::sync::Arc;
 use std::str::FromStr;
 use std::vec;
 
 use itertools::Itertools;
 use log::{debug, warn, error};
 
+use crate::Error;
 use crate::algs::Algorithm;
 use crate::steps::dr::co::COCountUD;
 use crate::defs::*;
@@ -32,62 +33,56 @@ pub struct DRTriggerStepTable<'a> {
     pre_trigger_move_set: &'a MoveSet333,
     trigger_move_set: &'a MoveSet333,
     pre_trans: Vec<Transformation333>,
-    table: &'a DRPruningTable,
+    table: Arc<DRPruningTable>,
     trigger_types: HashMap<(u8, u8), u8>,
     trigger_variants: Vec<Vec<Turn333>>,
-    post_step_checks: Rc<Vec<Box<dyn PostStepCheck + 'a>>>,
+    post_step_checks: Arc<Vec<Box<dyn PostStepCheck + 'a>>>,
     name: &'a str,
 }
 
-pub fn from_step_config<'a>(table: &'a DRPruningTable, #[cfg(feature = "333htr")] subset_table: &'a HTRSubsetTable, mut config: StepConfig) -> Result<(Step333<'a>, DefaultStepOptions), String> {
+pub fn from_step_config(table: Arc<DRPruningTable>, #[cfg(feature = "333htr")] subset_table: Arc<HTRSubsetTable>, mut config: StepConfig) -> Result<(Step333<'static>, DefaultStepOptions), Error> {
     #[cfg(feature = "333htr")]
     let post_step_filters: Vec<Box<dyn PostStepCheck>> = config.params.remove("subsets")
         .map(|x|x.split(",").map(|x|x.to_string()).collect_vec())
         .and_then(|subsets| crate::steps::htr::subsets::dr_subset_filter(subset_table, &subsets))
         .map(|filter|{
-            let b: Box<dyn PostStepCheck + 'a> = Box::new(filter);
+            let b: Box<dyn PostStepCheck> = Box::new(filter);
             vec![b]
         })
         .unwrap_or(vec![]);
     #[cfg(not(feature = "333htr"))]
     let post_step_filters: Vec<Box<dyn PostStepCheck>> = vec![];
 
-    let psc = Rc::new(post_step_filters);
+    let psc = Arc::new(post_step_filters);
 
     let triggers = config.params
         .remove("triggers")
         .iter()
         .flat_map(move |trig|trig.split(","))
-        .filter_map(move |trig|{
-            let alg = Algorithm::from_str(trig.to_uppercase().as_str());
-            match alg {
-                Err(_) => {
-                    error!("Unable to parse trigger {trig}.");
-                    None
-                },
-                Ok(alg) => Some(alg)
-            }
+        .map(move |trig|{
+            Algorithm::from_str(trig.to_uppercase().as_str())
+                .map_err(|_| Error::InvalidConfig(format!("Unable to parse trigger {trig}")))
         })
-        .collect_vec();
+        .collect::<Result<Vec<Algorithm>, Error>>()?;
 
     let step = if let Some(substeps) = config.substeps {
-        let variants: Result<Vec<Vec<Box<dyn StepVariant>>>, String> = substeps.into_iter().map(|step| match step.to_lowercase().as_str() {
-            "ud" | "drud" => Ok(dr_step_variants(table, vec![CubeAxis::FB, CubeAxis::LR], vec![CubeAxis::UD], triggers.clone(), psc.clone())),
-            "fb" | "drfb" => Ok(dr_step_variants(table, vec![CubeAxis::UD, CubeAxis::LR], vec![CubeAxis::FB], triggers.clone(), psc.clone())),
-            "lr" | "drlr" => Ok(dr_step_variants(table, vec![CubeAxis::UD, CubeAxis::FB], vec![CubeAxis::LR], triggers.clone(), psc.clone())),
+        let variants: Result<Vec<Vec<Box<dyn StepVariant>>>, Error> = substeps.into_iter().map(|step| match step.to_lowercase().as_str() {
+            "ud" | "drud" => Ok(dr_step_variants(table.clone(), vec![CubeAxis::FB, CubeAxis::LR], vec![CubeAxis::UD], triggers.clone(), psc.clone())),
+            "fb" | "drfb" => Ok(dr_step_variants(table.clone(), vec![CubeAxis::UD, CubeAxis::LR], vec![CubeAxis::FB], triggers.clone(), psc.clone())),
+            "lr" | "drlr" => Ok(dr_step_variants(table.clone(), vec![CubeAxis::UD, CubeAxis::FB], vec![CubeAxis::LR], triggers.clone(), psc.clone())),
 
-            "eoud" => Ok(dr_step_variants(table, vec![CubeAxis::UD], vec![CubeAxis::FB, CubeAxis::LR], triggers.clone(), psc.clone())),
-            "eofb" => Ok(dr_step_variants(table, vec![CubeAxis::FB], vec![CubeAxis::UD, CubeAxis::LR], triggers.clone(), psc.clone())),
-            "eolr" => Ok(dr_step_variants(table, vec![CubeAxis::LR], vec![CubeAxis::UD, CubeAxis::FB], triggers.clone(), psc.clone())),
+            "eoud" => Ok(dr_step_variants(table.clone(), vec![CubeAxis::UD], vec![CubeAxis::FB, CubeAxis::LR], triggers.clone(), psc.clone())),
+            "eofb" => Ok(dr_step_variants(table.clone(), vec![CubeAxis::FB], vec![CubeAxis::UD, CubeAxis::LR], triggers.clone(), psc.clone())),
+            "eolr" => Ok(dr_step_variants(table.clone(), vec![CubeAxis::LR], vec![CubeAxis::UD, CubeAxis::FB], triggers.clone(), psc.clone())),
 
-            "drud-eofb" => Ok(dr_step_variants(table, vec![CubeAxis::FB], vec![CubeAxis::UD], triggers.clone(), psc.clone())),
-            "drud-eolr" => Ok(dr_step_variants(table, vec![CubeAxis::LR], vec![CubeAxis::UD], triggers.clone(), psc.clone())),
-            "drfb-eoud" => Ok(dr_step_variants(table, vec![CubeAxis::UD], vec![CubeAxis::FB], triggers.clone(), psc.clone())),
-            "drfb-eolr" => Ok(dr_step_variants(table, vec![CubeAxis::LR], vec![CubeAxis::FB], triggers.clone(), psc.clone())),
-            "drlr-eoud" => Ok(dr_step_variants(table, vec![CubeAxis::UD], vec![CubeAxis::LR], triggers.clone(), psc.clone())),
-            "drlr-eofb" => Ok(dr_step_variants(table, vec![CubeAxis::FB], vec![CubeAxis::LR], triggers.clone(), psc.clone())),
+            "drud-eofb" => Ok(dr_step_variants(table.clone(), vec![CubeAxis::FB], vec![CubeAxis::UD], triggers.clone(), psc.clone())),
+            "drud-eolr" => Ok(dr_step_variants(table.clone(), vec![CubeAxis::LR], vec![CubeAxis::UD], triggers.clone(), psc.clone())),
+            "drfb-eoud" => Ok(dr_step_variants(table.clone(), vec![CubeAxis::UD], vec![CubeAxis::FB], triggers.clone(), psc.clone())),
+            "drfb-eolr" => Ok(dr_step_variants(table.clone(), vec![CubeAxis::LR], vec![CubeAxis::FB], triggers.clone(), psc.clone())),
+            "drlr-eoud" => Ok(dr_step_variants(table.clone(), vec![CubeAxis::UD], vec![CubeAxis::LR], triggers.clone(), psc.clone())),
+            "drlr-eofb" => Ok(dr_step_variants(table.clone(), vec![CubeAxis::FB], vec![CubeAxis::LR], triggers.clone(), psc.clone())),
 
-            x => Err(format!("Invalid DR substep {x}"))
+            x => Err(Error::InvalidConfig(format!("Invalid DR substep {x}")))
         }).collect();
         let variants = variants?.into_iter().flat_map(|v|v).collect_vec();
         Step::new(variants, StepKind::DR, true)
@@ -96,7 +91,7 @@ pub fn from_step_config<'a>(table: &'a DRPruningTable, #[cfg(feature = "333htr")
     };
 
     if !config.params.is_empty() {
-        return Err(format!("Unreognized parameters dr: {:?}", config.params.keys()))
+        return Err(Error::InvalidConfig(format!("Unrecognized parameters: {:?}", config.params.keys())))
     }
 
     let search_opts = DefaultStepOptions::new(
@@ -115,38 +110,38 @@ pub fn from_step_config<'a>(table: &'a DRPruningTable, #[cfg(feature = "333htr")
 }
 
 pub fn dr<'a>(
-    table: &'a DRPruningTable,
+    table: Arc<DRPruningTable>,
     eo_axis: Vec<CubeAxis>,
     dr_axis: Vec<CubeAxis>,
     triggers: Vec<Algorithm>,
-    psc: Rc<Vec<Box<dyn PostStepCheck + 'a>>>)
+    psc: Arc<Vec<Box<dyn PostStepCheck + 'a>>>)
 -> Step333<'a> {
     let step_variants = dr_step_variants(table, eo_axis, dr_axis, triggers, psc);
     Step::new(step_variants, StepKind::DR, true)
 }
 
-pub fn dr_any<'a>(table: &'a DRPruningTable, triggers: Vec<Algorithm>, psc: Rc<Vec<Box<dyn PostStepCheck + 'a>>>) -> Step333<'a> {
+pub fn dr_any<'a>(table: Arc<DRPruningTable>, triggers: Vec<Algorithm>, psc: Arc<Vec<Box<dyn PostStepCheck + 'a>>>) -> Step333<'a> {
     dr(table, vec![CubeAxis::UD, CubeAxis::FB, CubeAxis::LR], vec![CubeAxis::UD, CubeAxis::FB, CubeAxis::LR], triggers, psc)
 }
 
 fn dr_step_variants<'a>(
-    table: &'a DRPruningTable,
+    table: Arc<DRPruningTable>,
     eo_axis: Vec<CubeAxis>,
     dr_axis: Vec<CubeAxis>,
     triggers: Vec<Algorithm>,
-    psc: Rc<Vec<Box<dyn PostStepCheck + 'a>>>
+    psc: Arc<Vec<Box<dyn PostStepCheck + 'a>>>
 ) -> Vec<Box<dyn StepVariant + 'a>> {
     eo_axis
         .into_iter()
         .flat_map(|eo| dr_axis.clone().into_iter().map(move |dr| (eo, dr)))
         .flat_map(move |x| {
             let x: Option<Box<dyn StepVariant>> = match x {
-                (CubeAxis::UD, CubeAxis::FB) => Some(Box::new(DRTriggerStepTable::new(vec![Transformation333::X], table, triggers.clone(), psc.clone(), "fb-eoud"))),
-                (CubeAxis::UD, CubeAxis::LR) => Some(Box::new(DRTriggerStepTable::new(vec![Transformation333::X, Transformation333::Z], table, triggers.clone(), psc.clone(), "lr-eoud"))),
-                (CubeAxis::FB, CubeAxis::UD) => Some(Box::new(DRTriggerStepTable::new(vec![], table, triggers.clone(), psc.clone(), "ud-eofb"))),
-                (CubeAxis::FB, CubeAxis::LR) => Some(Box::new(DRTriggerStepTable::new(vec![Transformation333::Z], table, triggers.clone(), psc.clone(), "lr-eofb"))),
-                (CubeAxis::LR, CubeAxis::UD) => Some(Box::new(DRTriggerStepTable::new(vec![Transformation333::Y], table, triggers.clone(), psc.clone(), "ud-eolr"))),
-                (CubeAxis::LR, CubeAxis::FB) => Some(Box::new(DRTriggerStepTable::new(vec![Transformation333::Y, Transformation333::Z], table, triggers.clone(), psc.clone(), "fb-eolr"))),
+                (CubeAxis::UD, CubeAxis::FB) => Some(Box::new(DRTriggerStepTable::new(vec![Transformation333::X], table.clone(), triggers.clone(), psc.clone(), "fb-eoud"))),
+                (CubeAxis::UD, CubeAxis::LR) => Some(Box::new(DRTriggerStepTable::new(vec![Transformation333::X, Transformation333::Z], table.clone(), triggers.clone(), psc.clone(), "lr-eoud"))),
+                (CubeAxis::FB, CubeAxis::UD) => Some(Box::new(DRTriggerStepTable::new(vec![], table.clone(), triggers.clone(), psc.clone(), "ud-eofb"))),
+                (CubeAxis::FB, CubeAxis::LR) => Some(Box::new(DRTriggerStepTable::new(vec![Transformation333::Z], table.clone(), triggers.clone(), psc.clone(), "lr-eofb"))),
+                (CubeAxis::LR, CubeAxis::UD) => Some(Box::new(DRTriggerStepTable::new(vec![Transformation333::Y], table.clone(), triggers.clone(), psc.clone(), "ud-eolr"))),
+                (CubeAxis::LR, CubeAxis::FB) => Some(Box::new(DRTriggerStepTable::new(vec![Transformation333::Y, Transformation333::Z], table.clone(), triggers.clone(), psc.clone(), "fb-eolr"))),
                 _ => None,
             };
             x
@@ -156,7 +151,7 @@ fn dr_step_variants<'a>(
 
 impl<'a> DRTriggerStepTable<'a> {
 
-    fn new(pre_trans: Vec<Transformation333>, table: &'a DRPruningTable, triggers: Vec<Algorithm>, post_step_checks: Rc<Vec<Box<dyn PostStepCheck + 'a>>>, name: &'a str) -> Self {
+    fn new(pre_trans: Vec<Transformation333>, table: Arc<DRPruningTable>, triggers: Vec<Algorithm>, post_step_checks: Arc<Vec<Box<dyn PostStepCheck + 'a>>>, name: &'a str) -> Self {
         let mut trigger_variants = vec![];
         let mut trigger_types: HashMap<(u8, u8), u8> = HashMap::new();
         for trigger in triggers.into_iter() {
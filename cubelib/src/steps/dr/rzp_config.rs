@@ -1,5 +1,6 @@
 use itertools::Itertools;
 
+use crate::Error;
 use crate::algs::Algorithm;
 use crate::cube::*;
 use crate::defs::*;
@@ -62,12 +63,12 @@ pub struct RZPStep<'a> {
     is_any: bool,
 }
 
-pub fn from_step_config<'a>(config: StepConfig) -> Result<(Step333<'a>, DefaultStepOptions), String> {
+pub fn from_step_config<'a>(config: StepConfig) -> Result<(Step333<'a>, DefaultStepOptions), Error> {
     // let step = rzp_any();
     let step = rzp(vec![CubeAxis::X, CubeAxis::Y, CubeAxis::Z]);
 
     if !config.params.is_empty() {
-        return Err(format!("Unreognized parameters: {:?}", config.params.keys()))
+        return Err(Error::InvalidConfig(format!("Unrecognized parameters: {:?}", config.params.keys())))
     }
 
     let search_opts = DefaultStepOptions::new(
@@ -0,0 +1,276 @@
+use std::iter::Peekable;
+use std::str::{Chars, FromStr};
+
+use itertools::Itertools;
+
+use crate::defs::{NissSwitchType, StepKind};
+use crate::steps::step::StepConfig;
+use crate::Error;
+
+//Parser and formatter for step configurations like `EO[ud;fb;max=5] > DR[triggers=R,RUR;niss=before] > HTR`.
+//Values that contain one of the reserved characters `> [ ] ; = " \` have to be quoted, e.g. `key="a;b"`, or escaped with a backslash.
+//Quoted step names are always custom steps, so custom steps can use the name of a built-in step.
+//Steps that don't define niss or quality get the given defaults. format_steps always writes the quality, so parsing its output
+//restores the same configs regardless of the default quality.
+
+const RESERVED: [char; 7] = ['>', '[', ']', ';', '=', '"', '\\'];
+const DEFAULT_QUALITY: usize = 100;
+
+pub fn parse_steps(steps: &str) -> Result<Vec<StepConfig>, Error> {
+    parse_steps_with_defaults(steps, None, DEFAULT_QUALITY)
+}
+
+pub fn parse_steps_with_defaults(steps: &str, niss: Option<NissSwitchType>, quality: usize) -> Result<Vec<StepConfig>, Error> {
+    let mut parser = Parser { chars: steps.chars().peekable(), niss, quality };
+    let mut configs = vec![];
+    loop {
+        configs.push(parser.step()?);
+        parser.skip_whitespace();
+        match parser.chars.next() {
+            None => return Ok(configs),
+            Some('>') => {},
+            Some(c) => return Err(Error::InvalidConfig(format!("Expected > between steps, found {c}"))),
+        }
+    }
+}
+
+pub fn format_steps(steps: &[StepConfig]) -> String {
+    steps.iter()
+        .map(format_step)
+        .join(" > ")
+}
+
+pub fn format_step(step: &StepConfig) -> String {
+    let mut params: Vec<String> = vec![];
+    if let Some(substeps) = &step.substeps {
+        params.extend(substeps.iter().map(|s| quote(s)));
+    }
+    let numbers = [("min", step.min), ("max", step.max), ("min-abs", step.absolute_min), ("max-abs", step.absolute_max)];
+    for (key, value) in numbers {
+        if let Some(value) = value {
+            params.push(format!("{key}={value}"));
+        }
+    }
+    if let Some(limit) = step.step_limit {
+        params.push(format!("limit={limit}"));
+    }
+    if let Some(niss) = step.niss {
+        params.push(format!("niss={}", match niss {
+            NissSwitchType::Always => "always",
+            NissSwitchType::Before => "before",
+            NissSwitchType::Never => "none",
+        }));
+    }
+    if let Some(max_switches) = step.max_niss_switches {
+        params.push(format!("max_switches={max_switches}"));
+    }
+    params.push(format!("quality={}", step.quality));
+    for (key, value) in step.params.iter().sorted() {
+        params.push(format!("{}={}", quote(key), quote(value)));
+    }
+
+    let name = match &step.kind {
+        StepKind::FIN => "FIN".to_string(),
+        StepKind::Other(name) if !matches!(StepKind::from_str(name), Ok(StepKind::Other(_))) => format!("\"{}\"", escape(name)),
+        StepKind::Other(name) => quote(name),
+        kind => kind.to_string().to_uppercase(),
+    };
+    if params.is_empty() {
+        name
+    } else {
+        format!("{name}[{}]", params.join(";"))
+    }
+}
+
+fn quote(value: &str) -> String {
+    let needs_quotes = value.is_empty()
+        || value.contains(RESERVED)
+        || value.starts_with(char::is_whitespace)
+        || value.ends_with(char::is_whitespace);
+    if needs_quotes {
+        format!("\"{}\"", escape(value))
+    } else {
+        value.to_string()
+    }
+}
+
+fn escape(value: &str) -> String {
+    value.replace('\\', "\\\\").replace('"', "\\\"")
+}
+
+struct Parser<'a> {
+    chars: Peekable<Chars<'a>>,
+    niss: Option<NissSwitchType>,
+    quality: usize,
+}
+
+impl Parser<'_> {
+    fn skip_whitespace(&mut self) {
+        while self.chars.next_if(|c| c.is_whitespace()).is_some() {}
+    }
+
+    fn step(&mut self) -> Result<StepConfig, Error> {
+        let (name, quoted) = self.quoted_value()?;
+        if name.is_empty() {
+            return Err(Error::InvalidConfig("Missing step name".to_string()));
+        }
+        let kind = if quoted {
+            StepKind::Other(name)
+        } else {
+            StepKind::from_str(name.as_str()).map_err(Error::InvalidConfig)?
+        };
+        let mut config = StepConfig::new(kind);
+        config.niss = self.niss;
+        config.quality = self.quality;
+
+        self.skip_whitespace();
+        if self.chars.next_if_eq(&'[').is_none() {
+            return Ok(config);
+        }
+        loop {
+            self.skip_whitespace();
+            if self.chars.next_if_eq(&']').is_some() {
+                return Ok(config);
+            }
+            let key = self.value()?;
+            self.skip_whitespace();
+            if self.chars.next_if_eq(&'=').is_some() {
+                let value = self.value()?;
+                set_param(&mut config, key, value)?;
+            } else {
+                config.substeps.get_or_insert_with(Vec::new).push(key);
+            }
+            self.skip_whitespace();
+            match self.chars.next() {
+                Some(';') => {},
+                Some(']') => return Ok(config),
+                Some(c) => return Err(Error::InvalidConfig(format!("Expected ; or ] in the parameters of {}, found {c}", config.kind))),
+                None => return Err(Error::InvalidConfig(format!("Expected step parameters of {} to end with ]", config.kind))),
+            }
+        }
+    }
+
+    fn value(&mut self) -> Result<String, Error> {
+        self.quoted_value().map(|(value, _)| value)
+    }
+
+    //A quoted string or everything up to the next reserved character, with surrounding whitespace removed.
+    //Also returns whether the value was quoted
+    fn quoted_value(&mut self) -> Result<(String, bool), Error> {
+        self.skip_whitespace();
+        let mut value = String::new();
+        if self.chars.next_if_eq(&'"').is_some() {
+            loop {
+                match self.chars.next() {
+                    Some('"') => return Ok((value, true)),
+                    Some('\\') => value.push(self.chars.next().ok_or(Error::InvalidConfig("Unterminated escape sequence".to_string()))?),
+                    Some(c) => value.push(c),
+                    None => return Err(Error::InvalidConfig(format!("Unterminated quoted value \"{value}"))),
+                }
+            }
+        }
+        //Escaped whitespace at the end is part of the value, so only trim what wasn't escaped
+        let mut trimmed_len = 0;
+        while let Some(c) = self.chars.next_if(|c| !RESERVED.contains(c) || *c == '\\') {
+            if c == '\\' {
+                value.push(self.chars.next().ok_or(Error::InvalidConfig("Unterminated escape sequence".to_string()))?);
+                trimmed_len = value.len();
+            } else {
+                value.push(c);
+                if !c.is_whitespace() {
+                    trimmed_len = value.len();
+                }
+            }
+        }
+        value.truncate(trimmed_len);
+        Ok((value, false))
+    }
+}
+
+fn set_param(config: &mut StepConfig, key: String, value: String) -> Result<(), Error> {
+    fn number<T: FromStr>(key: &str, value: &str) -> Result<T, Error> where T::Err: std::fmt::Display {
+        T::from_str(value).map_err(|x| Error::InvalidConfig(format!("Unable to parse value '{value}' for {key}. '{x}'")))
+    }
+    match key.as_str() {
+        "limit" => config.step_limit = Some(number(key.as_str(), value.as_str())?),
+        "min" | "min-rel" => config.min = Some(number(key.as_str(), value.as_str())?),
+        "max" | "max-rel" => config.max = Some(number(key.as_str(), value.as_str())?),
+        "min-abs" => config.absolute_min = Some(number(key.as_str(), value.as_str())?),
+        "max-abs" => config.absolute_max = Some(number(key.as_str(), value.as_str())?),
+        "max_switches" => config.max_niss_switches = Some(number(key.as_str(), value.as_str())?),
+        "quality" => config.quality = number(key.as_str(), value.as_str())?,
+        "niss" => config.niss = Some(match value.as_str() {
+            "always" | "true" => NissSwitchType::Always,
+            "before" => NissSwitchType::Before,
+            "none" | "never" | "false" => NissSwitchType::Never,
+            x => return Err(Error::InvalidConfig(format!("Invalid NISS type {x}. Expected one of 'always', 'before', 'none'"))),
+        }),
+        _ => {
+            config.params.insert(key, value);
+        }
+    }
+    Ok(())
+}
+
+#[cfg(test)]
+mod tests {
+    use crate::defs::{NissSwitchType, StepKind};
+    use crate::steps::step::StepConfig;
+
+    use super::{format_steps, parse_steps, parse_steps_with_defaults};
+
+    fn assert_round_trip(steps: Vec<StepConfig>) {
+        let formatted = format_steps(&steps);
+        assert_eq!(parse_steps(formatted.as_str()).unwrap(), steps, "{formatted}");
+        assert_eq!(parse_steps_with_defaults(formatted.as_str(), Some(NissSwitchType::Always), 5000).unwrap(), steps, "{formatted}");
+    }
+
+    #[test]
+    fn built_in_steps_round_trip() {
+        for kind in [StepKind::EO, StepKind::RZP, StepKind::DR, StepKind::HTR, StepKind::FR, StepKind::FRLS, StepKind::FIN, StepKind::FINLS] {
+            let mut config = StepConfig::new(kind);
+            config.niss = Some(NissSwitchType::Before);
+            assert_round_trip(vec![config]);
+        }
+    }
+
+    #[test]
+    fn parameters_round_trip() {
+        let mut eo = StepConfig::new(StepKind::EO);
+        eo.substeps = Some(vec!["ud".to_string(), "f;b".to_string(), " x ".to_string()]);
+        eo.min = Some(2);
+        eo.max = Some(5);
+        eo.absolute_min = Some(3);
+        eo.absolute_max = Some(9);
+        eo.step_limit = Some(20);
+        eo.niss = Some(NissSwitchType::Always);
+        eo.max_niss_switches = Some(1);
+        eo.quality = 100;
+        let mut dr = StepConfig::new(StepKind::DR);
+        dr.niss = Some(NissSwitchType::Never);
+        dr.quality = 2000;
+        dr.params.insert("triggers".to_string(), "R,RUR".to_string());
+        dr.params.insert("a=b".to_string(), "> [x];\"y\"\\".to_string());
+        dr.params.insert("empty".to_string(), "".to_string());
+        assert_round_trip(vec![eo, dr]);
+    }
+
+    #[test]
+    fn custom_steps_round_trip() {
+        let mut names = ["MyStep", "goal", "EO", "fin", "with space", "x>y", "\"quoted\"", "back\\slash"]
+            .map(|name| StepConfig::new(StepKind::Other(name.to_string())))
+            .to_vec();
+        names[1].params.insert("UF".to_string(), "solved".to_string());
+        for config in names.iter_mut() {
+            config.niss = Some(NissSwitchType::Never);
+        }
+        assert_round_trip(names);
+    }
+
+    #[test]
+    fn unquoted_names_are_case_insensitive() {
+        let steps = parse_steps("eo > Dr > \"Dr\" > MyStep").unwrap();
+        let kinds: Vec<StepKind> = steps.into_iter().map(|s| s.kind).collect();
+        assert_eq!(kinds, vec![StepKind::EO, StepKind::DR, StepKind::Other("Dr".to_string()), StepKind::Other("MyStep".to_string())]);
+    }
+}
@@ -1,5 +1,8 @@
+use std::sync::Arc;
+
 use itertools::Itertools;
 
+use crate::Error;
 use crate::algs::Algorithm;
 use crate::defs::*;
 use crate::solver::lookup_table::LookupTable;
@@ -48,17 +51,17 @@ pub type EOPruningTable = LookupTable<2048, EOCoordFB>;
 pub struct EOStepTable<'a> {
     move_set: &'a MoveSet333,
     pre_trans: Vec<Transformation333>,
-    table: &'a EOPruningTable,
+    table: Arc<EOPruningTable>,
     name: &'a str,
 }
 
-pub fn from_step_config(table: &EOPruningTable, config: StepConfig) -> Result<(Step333, DefaultStepOptions), String> {
+pub fn from_step_config(table: Arc<EOPruningTable>, config: StepConfig) -> Result<(Step333<'static>, DefaultStepOptions), Error> {
     let step = if let Some(substeps) = config.substeps {
-        let axis: Result<Vec<CubeAxis>, String> = substeps.into_iter().map(|step| match step.to_lowercase().as_str() {
+        let axis: Result<Vec<CubeAxis>, Error> = substeps.into_iter().map(|step| match step.to_lowercase().as_str() {
             "eoud" | "ud" => Ok(CubeAxis::UD),
             "eofb" | "fb" => Ok(CubeAxis::FB),
             "eolr" | "lr" => Ok(CubeAxis::LR),
-            x => Err(format!("Invalid EO substep {x}"))
+            x => Err(Error::InvalidConfig(format!("Invalid EO substep {x}")))
         }).collect();
         eo(table, axis?)
     } else {
@@ -66,7 +69,7 @@ pub fn from_step_config(table: &EOPruningTable, config: StepConfig) -> Result<(S
     };
 
     if !config.params.is_empty() {
-        return Err(format!("Unreognized parameters: {:?}", config.params.keys()))
+        return Err(Error::InvalidConfig(format!("Unrecognized parameters: {:?}", config.params.keys())))
     }
 
     let search_opts = DefaultStepOptions::new(
@@ -84,18 +87,18 @@ pub fn from_step_config(table: &EOPruningTable, config: StepConfig) -> Result<(S
     Ok((step, search_opts))
 }
 
-pub fn eo_any(table: &EOPruningTable) -> Step333 {
+pub fn eo_any(table: Arc<EOPruningTable>) -> Step333<'static> {
     eo(table, vec![CubeAxis::UD, CubeAxis::FB, CubeAxis::LR])
 }
 
-pub fn eo(table: &EOPruningTable, eo_axis: Vec<CubeAxis>) -> Step333 {
+pub fn eo(table: Arc<EOPruningTable>, eo_axis: Vec<CubeAxis>) -> Step333<'static> {
     let step_variants = eo_axis
         .into_iter()
         .map(move |x| {
             let x: Box<dyn StepVariant> = match x {
-                CubeAxis::UD => Box::new(EOStepTable::new_ud(&table)),
-                CubeAxis::FB => Box::new(EOStepTable::new_fb(&table)),
-                CubeAxis::LR => Box::new(EOStepTable::new_lr(&table)),
+                CubeAxis::UD => Box::new(EOStepTable::new_ud(table.clone())),
+                CubeAxis::FB => Box::new(EOStepTable::new_fb(table.clone())),
+                CubeAxis::LR => Box::new(EOStepTable::new_lr(table.clone())),
             };
             x
         })
@@ -104,7 +107,7 @@ pub fn eo(table: &EOPruningTable, eo_axis: Vec<CubeAxis>) -> Step333 {
 }
 
 impl<'a> EOStepTable<'a> {
-    fn new_ud(table: &'a EOPruningTable) -> Self {
+    fn new_ud(table: Arc<EOPruningTable>) -> Self {
         EOStepTable {
             move_set: &EO_FB_MOVESET,
             pre_trans: vec![Transformation333::new(CubeAxis::X, Direction::Clockwise)],
@@ -113,7 +116,7 @@ impl<'a> EOStepTable<'a> {
         }
     }
 
-    fn new_lr(table: &'a EOPruningTable) -> Self {
+    fn new_lr(table: Arc<EOPruningTable>) -> Self {
         EOStepTable {
             move_set: &EO_FB_MOVESET,
             pre_trans: vec![Transformation333::new(CubeAxis::Y, Direction::Clockwise)],
@@ -122,7 +125,7 @@ impl<'a> EOStepTable<'a> {
         }
     }
 
-    fn new_fb(table: &'a EOPruningTable) -> Self {
+    fn new_fb(table: Arc<EOPruningTable>) -> Self {
         EOStepTable {
             move_set: &EO_FB_MOVESET,
             pre_trans: vec![],
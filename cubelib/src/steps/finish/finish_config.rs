@@ -1,6 +1,7 @@
-use std::rc::Rc;
+use std::sync::Arc;
 
 use itertools::Itertools;
+use crate::Error;
 use crate::cube::*;
 use crate::defs::*;
 use crate::solver::lookup_table::LookupTable;
@@ -30,23 +31,23 @@ pub const HTR_FINISH_MOVESET: MoveSet333 = MoveSet333 {
     transitions: &finish_transitions(),
 };
 pub type FRFinishPruningTable = LookupTable<{ FR_FINISH_SIZE }, FRUDFinishCoord>;
-pub type FRFinishPruningTableStep<'a> = DefaultPruningTableStep::<'a, { FR_FINISH_SIZE }, FRUDFinishCoord, {FRUD_WITH_SLICE_SIZE}, FRUDWithSliceCoord>;
+pub type FRFinishPruningTableStep = DefaultPruningTableStep::<{ FR_FINISH_SIZE }, FRUDFinishCoord, {FRUD_WITH_SLICE_SIZE}, FRUDWithSliceCoord>;
 
-pub type FRFinishLeaveSlicePruningTableStep<'a> = DefaultPruningTableStep::<'a, { FR_FINISH_SIZE }, FRUDFinishCoord, {FRUD_NO_SLICE_SIZE}, FRUDNoSliceCoord>;
+pub type FRFinishLeaveSlicePruningTableStep = DefaultPruningTableStep::<{ FR_FINISH_SIZE }, FRUDFinishCoord, {FRUD_NO_SLICE_SIZE}, FRUDNoSliceCoord>;
 
 pub type HTRFinishPruningTable = LookupTable<{ HTR_FINISH_SIZE }, HTRFinishCoord>;
-pub type HTRFinishPruningTableStep<'a> = DefaultPruningTableStep::<'a, { HTR_FINISH_SIZE }, HTRFinishCoord, { HTRDRUD_SIZE }, HTRDRUDCoord>;
+pub type HTRFinishPruningTableStep = DefaultPruningTableStep::<{ HTR_FINISH_SIZE }, HTRFinishCoord, { HTRDRUD_SIZE }, HTRDRUDCoord>;
 
 pub type HTRLeaveSliceFinishPruningTable = LookupTable<{ HTR_LEAVE_SLICE_FINISH_SIZE }, HTRLeaveSliceFinishCoord>;
-pub type HTRLeaveSliceFinishPruningTableStep<'a> = DefaultPruningTableStep::<'a, { HTR_LEAVE_SLICE_FINISH_SIZE }, HTRLeaveSliceFinishCoord, { HTRDRUD_SIZE }, HTRDRUDCoord>;
+pub type HTRLeaveSliceFinishPruningTableStep = DefaultPruningTableStep::<{ HTR_LEAVE_SLICE_FINISH_SIZE }, HTRLeaveSliceFinishCoord, { HTRDRUD_SIZE }, HTRDRUDCoord>;
 
-pub fn from_step_config_fr(table: &FRFinishPruningTable, config: StepConfig) -> Result<(Step333, DefaultStepOptions), String> {
+pub fn from_step_config_fr(table: Arc<FRFinishPruningTable>, config: StepConfig) -> Result<(Step333<'static>, DefaultStepOptions), Error> {
     let step = if let Some(substeps) = config.substeps {
-        let axis: Result<Vec<CubeAxis>, String> = substeps.into_iter().map(|step| match step.to_lowercase().as_str() {
+        let axis: Result<Vec<CubeAxis>, Error> = substeps.into_iter().map(|step| match step.to_lowercase().as_str() {
             "finishud" | "finud" | "ud" => Ok(CubeAxis::UD),
             "finishfb" | "finfb" | "fb" => Ok(CubeAxis::FB),
             "finishlr" | "finlr" | "lr" => Ok(CubeAxis::LR),
-            x => Err(format!("Invalid HTR substep {x}"))
+            x => Err(Error::InvalidConfig(format!("Invalid HTR substep {x}")))
         }).collect();
         fr_finish(table, axis?)
     } else {
@@ -54,7 +55,7 @@ pub fn from_step_config_fr(table: &FRFinishPruningTable, config: StepConfig) ->
     };
 
     if !config.params.is_empty() {
-        return Err(format!("Unreognized parameters: {:?}", config.params.keys()))
+        return Err(Error::InvalidConfig(format!("Unrecognized parameters: {:?}", config.params.keys())))
     }
 
     let search_opts = DefaultStepOptions::new(
@@ -72,13 +73,13 @@ pub fn from_step_config_fr(table: &FRFinishPruningTable, config: StepConfig) ->
     Ok((step, search_opts))
 }
 
-pub fn from_step_config_fr_leave_slice(table: &FRFinishPruningTable, config: StepConfig) -> Result<(Step333, DefaultStepOptions), String> {
+pub fn from_step_config_fr_leave_slice(table: Arc<FRFinishPruningTable>, config: StepConfig) -> Result<(Step333<'static>, DefaultStepOptions), Error> {
     let step = if let Some(substeps) = config.substeps {
-        let axis: Result<Vec<CubeAxis>, String> = substeps.into_iter().map(|step| match step.to_lowercase().as_str() {
+        let axis: Result<Vec<CubeAxis>, Error> = substeps.into_iter().map(|step| match step.to_lowercase().as_str() {
             "finishud" | "finud" | "ud" => Ok(CubeAxis::UD),
             "finishfb" | "finfb" | "fb" => Ok(CubeAxis::FB),
             "finishlr" | "finlr" | "lr" => Ok(CubeAxis::LR),
-            x => Err(format!("Invalid HTR substep {x}"))
+            x => Err(Error::InvalidConfig(format!("Invalid HTR substep {x}")))
         }).collect();
         fr_finish_leave_slice(table, axis?)
     } else {
@@ -86,7 +87,7 @@ pub fn from_step_config_fr_leave_slice(table: &FRFinishPruningTable, config: Ste
     };
 
     if !config.params.is_empty() {
-        return Err(format!("Unreognized parameters: {:?}", config.params.keys()))
+        return Err(Error::InvalidConfig(format!("Unrecognized parameters: {:?}", config.params.keys())))
     }
 
     let search_opts = DefaultStepOptions::new(
@@ -104,7 +105,7 @@ pub fn from_step_config_fr_leave_slice(table: &FRFinishPruningTable, config: Ste
     Ok((step, search_opts))
 }
 
-pub fn from_step_config_htr(table: &HTRFinishPruningTable, config: StepConfig) -> Result<(Step333, DefaultStepOptions), String> {
+pub fn from_step_config_htr(table: Arc<HTRFinishPruningTable>, config: StepConfig) -> Result<(Step333<'static>, DefaultStepOptions), Error> {
     let search_opts = DefaultStepOptions::new(
         config.min.unwrap_or(0),
         config.max.unwrap_or(10),
@@ -120,13 +121,13 @@ pub fn from_step_config_htr(table: &HTRFinishPruningTable, config: StepConfig) -
     Ok((htr_finish(table), search_opts))
 }
 
-pub fn from_step_config_htr_leave_slice(table: &HTRLeaveSliceFinishPruningTable, config: StepConfig) -> Result<(Step333, DefaultStepOptions), String> {
+pub fn from_step_config_htr_leave_slice(table: Arc<HTRLeaveSliceFinishPruningTable>, config: StepConfig) -> Result<(Step333<'static>, DefaultStepOptions), Error> {
     let step = if let Some(substeps) = config.substeps {
-        let axis: Result<Vec<CubeAxis>, String> = substeps.into_iter().map(|step| match step.to_lowercase().as_str() {
+        let axis: Result<Vec<CubeAxis>, Error> = substeps.into_iter().map(|step| match step.to_lowercase().as_str() {
             "finishud" | "finud" | "ud" => Ok(CubeAxis::UD),
             "finishfb" | "finfb" | "fb" => Ok(CubeAxis::FB),
             "finishlr" | "finlr" | "lr" => Ok(CubeAxis::LR),
-            x => Err(format!("Invalid leave slice finish axis {x}"))
+            x => Err(Error::InvalidConfig(format!("Invalid leave slice finish axis {x}")))
         }).collect();
         htr_finish_leave_slice(table, axis?)
     } else {
@@ -134,7 +135,7 @@ pub fn from_step_config_htr_leave_slice(table: &HTRLeaveSliceFinishPruningTable,
     };
 
     if !config.params.is_empty() {
-        return Err(format!("Unreognized parameters: {:?}", config.params.keys()))
+        return Err(Error::InvalidConfig(format!("Unrecognized parameters: {:?}", config.params.keys())))
     }
 
     let search_opts = DefaultStepOptions::new(
@@ -152,18 +153,18 @@ pub fn from_step_config_htr_leave_slice(table: &HTRLeaveSliceFinishPruningTable,
     Ok((step, search_opts))
 }
 
-pub fn fr_finish_any(table: &FRFinishPruningTable) -> Step333 {
+pub fn fr_finish_any(table: Arc<FRFinishPruningTable>) -> Step333<'static> {
     fr_finish(table, vec![CubeAxis::UD, CubeAxis::FB, CubeAxis::LR])
 }
 
-pub fn fr_finish<'a>(table: &'a FRFinishPruningTable, fr_axis: Vec<CubeAxis>) -> Step333<'a> {
+pub fn fr_finish(table: Arc<FRFinishPruningTable>, fr_axis: Vec<CubeAxis>) -> Step333<'static> {
     let step_variants = fr_axis
         .into_iter()
         .flat_map(move |x| {
-            let x: Option<Box<dyn StepVariant + 'a>> = match x {
-                CubeAxis::UD => Some(Box::new(FRFinishPruningTableStep::new(&FRUD_FINISH_MOVESET, vec![], table, Rc::new(vec![]), ""))),
-                CubeAxis::FB => Some(Box::new(FRFinishPruningTableStep::new(&FRUD_FINISH_MOVESET, vec![Transformation333::new(CubeAxis::X, Direction::Clockwise)], table, Rc::new(vec![]), ""))),
-                CubeAxis::LR => Some(Box::new(FRFinishPruningTableStep::new(&FRUD_FINISH_MOVESET, vec![Transformation333::new(CubeAxis::Z, Direction::Clockwise)], table, Rc::new(vec![]), ""))),
+            let x: Option<Box<dyn StepVariant>> = match x {
+                CubeAxis::UD => Some(Box::new(FRFinishPruningTableStep::new(&FRUD_FINISH_MOVESET, vec![], table.clone(), Arc::new(vec![]), ""))),
+                CubeAxis::FB => Some(Box::new(FRFinishPruningTableStep::new(&FRUD_FINISH_MOVESET, vec![Transformation333::new(CubeAxis::X, Direction::Clockwise)], table.clone(), Arc::new(vec![]), ""))),
+                CubeAxis::LR => Some(Box::new(FRFinishPruningTableStep::new(&FRUD_FINISH_MOVESET, vec![Transformation333::new(CubeAxis::Z, Direction::Clockwise)], table.clone(), Arc::new(vec![]), ""))),
             };
             x
         })
@@ -171,24 +172,24 @@ pub fn fr_finish<'a>(table: &'a FRFinishPruningTable, fr_axis: Vec<CubeAxis>) ->
     Step::new(step_variants, StepKind::FIN, true)
 }
 
-pub fn htr_finish(table: &HTRFinishPruningTable) -> Step333 {
+pub fn htr_finish(table: Arc<HTRFinishPruningTable>) -> Step333<'static> {
     Step::new(vec![
-        Box::new(HTRFinishPruningTableStep::new(&HTR_FINISH_MOVESET, vec![], table, Rc::new(vec![]), ""))
+        Box::new(HTRFinishPruningTableStep::new(&HTR_FINISH_MOVESET, vec![], table.clone(), Arc::new(vec![]), ""))
     ], StepKind::FIN, true)
 }
 
-pub fn htr_finish_leave_slice_any(table: &HTRLeaveSliceFinishPruningTable) -> Step333 {
+pub fn htr_finish_leave_slice_any(table: Arc<HTRLeaveSliceFinishPruningTable>) -> Step333<'static> {
     htr_finish_leave_slice(table, vec![CubeAxis::UD, CubeAxis::FB, CubeAxis::LR])
 }
 
-pub fn htr_finish_leave_slice<'a>(table: &'a HTRLeaveSliceFinishPruningTable, slice_axis: Vec<CubeAxis>) -> Step333<'a> {
+pub fn htr_finish_leave_slice(table: Arc<HTRLeaveSliceFinishPruningTable>, slice_axis: Vec<CubeAxis>) -> Step333<'static> {
     let step_variants = slice_axis
         .into_iter()
         .flat_map(move |x| {
-            let x: Option<Box<dyn StepVariant + 'a>> = match x {
-                CubeAxis::UD => Some(Box::new(HTRLeaveSliceFinishPruningTableStep::new(&HTR_FINISH_MOVESET, vec![], table, Rc::new(vec![]), "ud"))),
-                CubeAxis::FB => Some(Box::new(HTRLeaveSliceFinishPruningTableStep::new(&HTR_FINISH_MOVESET, vec![Transformation333::new(CubeAxis::X, Direction::Clockwise)], table, Rc::new(vec![]), "fb"))),
-                CubeAxis::LR => Some(Box::new(HTRLeaveSliceFinishPruningTableStep::new(&HTR_FINISH_MOVESET, vec![Transformation333::new(CubeAxis::Z, Direction::Clockwise)], table, Rc::new(vec![]), "lr"))),
+            let x: Option<Box<dyn StepVariant>> = match x {
+                CubeAxis::UD => Some(Box::new(HTRLeaveSliceFinishPruningTableStep::new(&HTR_FINISH_MOVESET, vec![], table.clone(), Arc::new(vec![]), "ud"))),
+                CubeAxis::FB => Some(Box::new(HTRLeaveSliceFinishPruningTableStep::new(&HTR_FINISH_MOVESET, vec![Transformation333::new(CubeAxis::X, Direction::Clockwise)], table.clone(), Arc::new(vec![]), "fb"))),
+                CubeAxis::LR => Some(Box::new(HTRLeaveSliceFinishPruningTableStep::new(&HTR_FINISH_MOVESET, vec![Transformation333::new(CubeAxis::Z, Direction::Clockwise)], table.clone(), Arc::new(vec![]), "lr"))),
             };
             x
         })
@@ -197,18 +198,18 @@ pub fn htr_finish_leave_slice<'a>(table: &'a HTRLeaveSliceFinishPruningTable, sl
 }
 
 
-pub fn fr_finish_leave_slice_any(table: &FRFinishPruningTable) -> Step333 {
+pub fn fr_finish_leave_slice_any(table: Arc<FRFinishPruningTable>) -> Step333<'static> {
     fr_finish_leave_slice(table, vec![CubeAxis::UD, CubeAxis::FB, CubeAxis::LR])
 }
 
-pub fn fr_finish_leave_slice<'a>(table: &'a FRFinishPruningTable, fr_axis: Vec<CubeAxis>) -> Step333<'a> {
+pub fn fr_finish_leave_slice(table: Arc<FRFinishPruningTable>, fr_axis: Vec<CubeAxis>) -> Step333<'static> {
     let step_variants = fr_axis
         .into_iter()
         .flat_map(move |x| {
-            let x: Option<Box<dyn StepVariant + 'a>> = match x {
-                CubeAxis::UD => Some(Box::new(FRFinishLeaveSlicePruningTableStep::new(&FRUD_FINISH_MOVESET, vec![], table, Rc::new(vec![]), "ud"))),
-                CubeAxis::FB => Some(Box::new(FRFinishLeaveSlicePruningTableStep::new(&FRUD_FINISH_MOVESET, vec![Transformation333::new(CubeAxis::X, Direction::Clockwise)], table, Rc::new(vec![]), "fb"))),
-                CubeAxis::LR => Some(Box::new(FRFinishLeaveSlicePruningTableStep::new(&FRUD_FINISH_MOVESET, vec![Transformation333::new(CubeAxis::Z, Direction::Clockwise)], table, Rc::new(vec![]), "lr"))),
+            let x: Option<Box<dyn StepVariant>> = match x {
+                CubeAxis::UD => Some(Box::new(FRFinishLeaveSlicePruningTableStep::new(&FRUD_FINISH_MOVESET, vec![], table.clone(), Arc::new(vec![]), "ud"))),
+                CubeAxis::FB => Some(Box::new(FRFinishLeaveSlicePruningTableStep::new(&FRUD_FINISH_MOVESET, vec![Transformation333::new(CubeAxis::X, Direction::Clockwise)], table.clone(), Arc::new(vec![]), "fb"))),
+                CubeAxis::LR => Some(Box::new(FRFinishLeaveSlicePruningTableStep::new(&FRUD_FINISH_MOVESET, vec![Transformation333::new(CubeAxis::Z, Direction::Clockwise)], table.clone(), Arc::new(vec![]), "lr"))),
             };
             x
         })
@@ -1,7 +1,8 @@
-use std::rc::Rc;
+use std::sync::Arc;
 use std::vec;
 
 use itertools::Itertools;
+use crate::Error;
 use crate::cube::*;
 use crate::defs::*;
 use crate::solver::lookup_table::LookupTable;
@@ -31,16 +32,16 @@ pub const FR_UD_MOVESET: MoveSet333 = MoveSet333 {
 
 pub type FRLeaveSlicePruningTable = LookupTable<{ FRUD_NO_SLICE_SIZE }, FRUDNoSliceCoord>;
 pub type FRPruningTable = LookupTable<{ FRUD_WITH_SLICE_SIZE }, FRUDWithSliceCoord>;
-pub type FRLeaveSlicePruningTableStep<'a> = DefaultPruningTableStep::<'a, {FRUD_NO_SLICE_SIZE}, FRUDNoSliceCoord, { HTRDRUD_SIZE }, HTRDRUDCoord>;
-pub type FRPruningTableStep<'a> = DefaultPruningTableStep::<'a, {FRUD_WITH_SLICE_SIZE}, FRUDWithSliceCoord, { HTRDRUD_SIZE }, HTRDRUDCoord>;
+pub type FRLeaveSlicePruningTableStep = DefaultPruningTableStep::<{FRUD_NO_SLICE_SIZE}, FRUDNoSliceCoord, { HTRDRUD_SIZE }, HTRDRUDCoord>;
+pub type FRPruningTableStep = DefaultPruningTableStep::<{FRUD_WITH_SLICE_SIZE}, FRUDWithSliceCoord, { HTRDRUD_SIZE }, HTRDRUDCoord>;
 
-pub fn from_step_config(table: &FRPruningTable, config: StepConfig) -> Result<(Step333, DefaultStepOptions), String> {
+pub fn from_step_config(table: Arc<FRPruningTable>, config: StepConfig) -> Result<(Step333<'static>, DefaultStepOptions), Error> {
     let step = if let Some(substeps) = config.substeps {
-        let axis: Result<Vec<CubeAxis>, String> = substeps.into_iter().map(|step| match step.to_lowercase().as_str() {
+        let axis: Result<Vec<CubeAxis>, Error> = substeps.into_iter().map(|step| match step.to_lowercase().as_str() {
             "frud" | "ud" => Ok(CubeAxis::UD),
             "frfb" | "fb" => Ok(CubeAxis::FB),
             "frlr" | "lr" => Ok(CubeAxis::LR),
-            x => Err(format!("Invalid FR substep {x}"))
+            x => Err(Error::InvalidConfig(format!("Invalid FR substep {x}")))
         }).collect();
         fr(table, axis?)
     } else {
@@ -48,7 +49,7 @@ pub fn from_step_config(table: &FRPruningTable, config: StepConfig) -> Result<(S
     };
 
     if !config.params.is_empty() {
-        return Err(format!("Unreognized parameters: {:?}", config.params.keys()))
+        return Err(Error::InvalidConfig(format!("Unrecognized parameters: {:?}", config.params.keys())))
     }
 
     let search_opts = DefaultStepOptions::new(
@@ -66,13 +67,13 @@ pub fn from_step_config(table: &FRPruningTable, config: StepConfig) -> Result<(S
     Ok((step, search_opts))
 }
 
-pub fn from_step_config_no_slice(table: &FRLeaveSlicePruningTable, config: StepConfig) -> Result<(Step333, DefaultStepOptions), String> {
+pub fn from_step_config_no_slice(table: Arc<FRLeaveSlicePruningTable>, config: StepConfig) -> Result<(Step333<'static>, DefaultStepOptions), Error> {
     let step = if let Some(substeps) = config.substeps {
-        let axis: Result<Vec<CubeAxis>, String> = substeps.into_iter().map(|step| match step.to_lowercase().as_str() {
+        let axis: Result<Vec<CubeAxis>, Error> = substeps.into_iter().map(|step| match step.to_lowercase().as_str() {
             "frud" | "ud" => Ok(CubeAxis::UD),
             "frfb" | "fb" => Ok(CubeAxis::FB),
             "frlr" | "lr" => Ok(CubeAxis::LR),
-            x => Err(format!("Invalid FRLS substep {x}"))
+            x => Err(Error::InvalidConfig(format!("Invalid FRLS substep {x}")))
         }).collect();
         fr_no_slice(table, axis?)
     } else {
@@ -80,7 +81,7 @@ pub fn from_step_config_no_slice(table: &FRLeaveSlicePruningTable, config: StepC
     };
 
     if !config.params.is_empty() {
-        return Err(format!("Unreognized parameters fr: {:?}", config.params.keys()))
+        return Err(Error::InvalidConfig(format!("Unrecognized parameters: {:?}", config.params.keys())))
     }
 
     let search_opts = DefaultStepOptions::new(
@@ -98,22 +99,22 @@ pub fn from_step_config_no_slice(table: &FRLeaveSlicePruningTable, config: StepC
     Ok((step, search_opts))
 }
 
-pub fn fr_no_slice_any(table: &FRLeaveSlicePruningTable) -> Step333 {
+pub fn fr_no_slice_any(table: Arc<FRLeaveSlicePruningTable>) -> Step333<'static> {
     fr_no_slice(table, vec![CubeAxis::UD, CubeAxis::FB, CubeAxis::LR])
 }
 
-pub fn fr_any(table: &FRPruningTable) -> Step333 {
+pub fn fr_any(table: Arc<FRPruningTable>) -> Step333<'static> {
     fr(table, vec![CubeAxis::UD, CubeAxis::FB, CubeAxis::LR])
 }
 
-pub fn fr_no_slice<'a>(table: &'a FRLeaveSlicePruningTable, fr_axis: Vec<CubeAxis>) -> Step333<'a> {
+pub fn fr_no_slice(table: Arc<FRLeaveSlicePruningTable>, fr_axis: Vec<CubeAxis>) -> Step333<'static> {
     let step_variants = fr_axis
         .into_iter()
         .flat_map(move |x| {
-            let x: Option<Box<dyn StepVariant + 'a>> = match x {
-                CubeAxis::UD => Some(Box::new(FRLeaveSlicePruningTableStep::new(&FR_UD_MOVESET, vec![], table, Rc::new(vec![]), "ud"))),
-                CubeAxis::FB => Some(Box::new(FRLeaveSlicePruningTableStep::new(&FR_UD_MOVESET, vec![Transformation333::new(CubeAxis::X, Direction::Clockwise)], table, Rc::new(vec![]), "fb"))),
-                CubeAxis::LR => Some(Box::new(FRLeaveSlicePruningTableStep::new(&FR_UD_MOVESET, vec![Transformation333::new(CubeAxis::Z, Direction::Clockwise)], table, Rc::new(vec![]), "lr"))),
+            let x: Option<Box<dyn StepVariant>> = match x {
+                CubeAxis::UD => Some(Box::new(FRLeaveSlicePruningTableStep::new(&FR_UD_MOVESET, vec![], table.clone(), Arc::new(vec![]), "ud"))),
+                CubeAxis::FB => Some(Box::new(FRLeaveSlicePruningTableStep::new(&FR_UD_MOVESET, vec![Transformation333::new(CubeAxis::X, Direction::Clockwise)], table.clone(), Arc::new(vec![]), "fb"))),
+                CubeAxis::LR => Some(Box::new(FRLeaveSlicePruningTableStep::new(&FR_UD_MOVESET, vec![Transformation333::new(CubeAxis::Z, Direction::Clockwise)], table.clone(), Arc::new(vec![]), "lr"))),
             };
             x
         })
@@ -121,14 +122,14 @@ pub fn fr_no_slice<'a>(table: &'a FRLeaveSlicePruningTable, fr_axis: Vec<CubeAxi
     Step::new(step_variants, StepKind::FRLS, true)
 }
 
-pub fn fr<'a>(table: &'a FRPruningTable, fr_axis: Vec<CubeAxis>) -> Step333<'a> {
+pub fn fr(table: Arc<FRPruningTable>, fr_axis: Vec<CubeAxis>) -> Step333<'static> {
     let step_variants = fr_axis
         .into_iter()
         .flat_map(move |x| {
-            let x: Option<Box<dyn StepVariant + 'a>> = match x {
-                CubeAxis::UD => Some(Box::new(FRPruningTableStep::new(&FR_UD_MOVESET, vec![], table, Rc::new(vec![]), "ud"))),
-                CubeAxis::FB => Some(Box::new(FRPruningTableStep::new(&FR_UD_MOVESET, vec![Transformation333::new(CubeAxis::X, Direction::Clockwise)], table, Rc::new(vec![]), "fb"))),
-                CubeAxis::LR => Some(Box::new(FRPruningTableStep::new(&FR_UD_MOVESET, vec![Transformation333::new(CubeAxis::Z, Direction::Clockwise)], table, Rc::new(vec![]), "lr"))),
+            let x: Option<Box<dyn StepVariant>> = match x {
+                CubeAxis::UD => Some(Box::new(FRPruningTableStep::new(&FR_UD_MOVESET, vec![], table.clone(), Arc::new(vec![]), "ud"))),
+                CubeAxis::FB => Some(Box::new(FRPruningTableStep::new(&FR_UD_MOVESET, vec![Transformation333::new(CubeAxis::X, Direction::Clockwise)], table.clone(), Arc::new(vec![]), "fb"))),
+                CubeAxis::LR => Some(Box::new(FRPruningTableStep::new(&FR_UD_MOVESET, vec![Transformation333::new(CubeAxis::Z, Direction::Clockwise)], table.clone(), Arc::new(vec![]), "lr"))),
             };
             x
         })
@@ -0,0 +1,431 @@
+use std::collections::HashSet;
+
+use crate::cube::*;
+use crate::cube::turn::TurnableMut;
+use crate::defs::StepKind;
+use crate::steps::custom::{CustomStep, CustomStepBuilder};
+use crate::steps::step::{DefaultStepOptions, Step, StepConfig};
+use crate::steps::tables::PruningTables333;
+use crate::Error;
+
+//Goals that only care about some pieces, e.g. `GOAL[edges=UF,UR,UB;corners=*o]`.
+//Every listed piece has to be solved. A trailing `o` means only its orientation matters (FB axis for edges, UD axis for corners)
+//and a trailing `p` means only its position matters. `*` stands for all pieces.
+//
+//The goal is split into pattern databases of at most MAX_TABLE_SIZE entries, which are generated the first time the goal is used.
+//The search is the usual iterative deepening with the maximum of the pattern databases as heuristic, so it's an IDA* search.
+
+pub const NAME: &str = "goal";
+pub const EDGE_NAMES: [&str; 12] = ["UB", "UR", "UF", "UL", "FR", "FL", "BR", "BL", "DF", "DR", "DB", "DL"];
+pub const CORNER_NAMES: [&str; 8] = ["UBL", "UBR", "UFR", "UFL", "DFL", "DFR", "DBR", "DBL"];
+const MAX_TABLE_SIZE: usize = 4_000_000;
+
+type GroupIndex = Box<dyn Fn(&Cube333) -> usize + Send + Sync>;
+
+#[derive(Copy, Clone, Debug, Default, PartialEq, Eq, Hash)]
+pub struct PieceGoal {
+    pub permutation: bool,
+    pub orientation: bool,
+}
+
+impl PieceGoal {
+    pub const IGNORED: PieceGoal = PieceGoal { permutation: false, orientation: false };
+    pub const SOLVED: PieceGoal = PieceGoal { permutation: true, orientation: true };
+    pub const PERMUTED: PieceGoal = PieceGoal { permutation: true, orientation: false };
+    pub const ORIENTED: PieceGoal = PieceGoal { permutation: false, orientation: true };
+}
+
+#[derive(Copy, Clone, Debug, Default, PartialEq, Eq, Hash)]
+pub struct PieceMask {
+    pub edges: [PieceGoal; 12],
+    pub corners: [PieceGoal; 8],
+}
+
+impl PieceMask {
+    pub fn parse(edges: Option<&str>, corners: Option<&str>) -> Result<Self, Error> {
+        let mut mask = PieceMask::default();
+        if let Some(edges) = edges {
+            parse_pieces(&mut mask.edges, &EDGE_NAMES, edges)?;
+        }
+        if let Some(corners) = corners {
+            parse_pieces(&mut mask.corners, &CORNER_NAMES, corners)?;
+        }
+        Ok(mask)
+    }
+
+    pub fn is_empty(&self) -> bool {
+        self.edges.iter().chain(self.corners.iter()).all(|goal| *goal == PieceGoal::IGNORED)
+    }
+
+    pub fn is_solved(&self, cube: &Cube333) -> bool {
+        fn solved(goals: &[PieceGoal], pieces: &[(u8, u8)]) -> bool {
+            pieces.iter().enumerate().all(|(pos, (id, orientation))| {
+                let goal = goals[*id as usize];
+                (!goal.orientation || *orientation == 0) && (!goal.permutation || *id as usize == pos)
+            })
+        }
+        solved(&self.edges, &edge_pieces(cube)) && solved(&self.corners, &corner_pieces(cube))
+    }
+
+    //Tables are cached on disk, so the name has to be unique for every mask
+    pub fn name(&self) -> String {
+        fn digits(goals: &[PieceGoal]) -> String {
+            goals.iter()
+                .map(|goal| char::from(b'0' + goal.permutation as u8 + 2 * goal.orientation as u8))
+                .collect()
+        }
+        format!("{NAME}-{}-{}", digits(&self.edges), digits(&self.corners))
+    }
+
+    //The pieces of the goal as they are written in the DSL, e.g. UF,URo,UFRp
+    pub fn description(&self) -> String {
+        fn pieces<'a>(goals: &'a [PieceGoal], names: &'a [&str]) -> impl Iterator<Item = String> + 'a {
+            goals.iter().zip(names.iter())
+                .filter_map(|(goal, name)| match *goal {
+                    PieceGoal::SOLVED => Some(name.to_string()),
+                    PieceGoal::ORIENTED => Some(format!("{name}o")),
+                    PieceGoal::PERMUTED => Some(format!("{name}p")),
+                    _ => None,
+                })
+        }
+        pieces(&self.edges, &EDGE_NAMES).chain(pieces(&self.corners, &CORNER_NAMES)).collect::<Vec<_>>().join(",")
+    }
+
+    pub fn step_builder(&self) -> Result<CustomStepBuilder, Error> {
+        if self.is_empty() {
+            return Err(Error::InvalidConfig("The goal has to contain at least one edge or corner".to_string()));
+        }
+        let edge_coords = PieceGroup::split(&self.edges, 2).into_iter()
+            .map(|group| {
+                let mut mask = PieceMask::default();
+                group.set_goals(&mut mask.edges);
+                let goal_states = group.goal_states(&EDGE_ORIENTATION_MOVES, &mask, edge_pieces);
+                (group.size(), Box::new(move |cube: &Cube333| group.index(&edge_pieces(cube))) as GroupIndex, goal_states)
+            });
+        let corner_coords = PieceGroup::split(&self.corners, 3).into_iter()
+            .map(|group| {
+                let mut mask = PieceMask::default();
+                group.set_goals(&mut mask.corners);
+                let goal_states = group.goal_states(&CORNER_ORIENTATION_MOVES, &mask, corner_pieces);
+                (group.size(), Box::new(move |cube: &Cube333| group.index(&corner_pieces(cube))) as GroupIndex, goal_states)
+            });
+        let mut coords = edge_coords.chain(corner_coords);
+        let (size, index, goal_states) = coords.next().unwrap();
+        let builder = CustomStepBuilder::new(&self.name(), size, index)
+            .goal_states(goal_states)
+            .kind(StepKind::Other(NAME.to_string()))
+            .variant(&self.description(), vec![]);
+        Ok(coords.fold(builder, |builder, (size, index, goal_states)| builder.coordinate(size, index).goal_states(goal_states)))
+    }
+}
+
+//Generates the tables for a GOAL step config. Invalid configs are reported by from_step_config
+pub fn gen_tables(config: &StepConfig, tables: &mut PruningTables333) {
+    let Ok(mask) = mask_from_config(config) else {
+        return;
+    };
+    let key = mask.name();
+    if tables.has_custom_table(&key) {
+        return;
+    }
+    if let Ok(builder) = mask.step_builder() {
+        #[cfg(feature = "fs")]
+        let builder = builder.store(tables.store().clone());
+        tables.insert_custom_table(&key, builder.build());
+    }
+}
+
+pub fn from_step_config(tables: &PruningTables333, mut config: StepConfig) -> Result<(Step<'static>, DefaultStepOptions), Error> {
+    let mask = mask_from_config(&config)?;
+    if mask.is_empty() {
+        return Err(Error::InvalidConfig("The goal has to contain at least one edge or corner".to_string()));
+    }
+    config.params.remove("edges");
+    config.params.remove("corners");
+    tables.shared_custom_table::<CustomStep>(&mask.name())
+        .ok_or(Error::MissingTable(mask.name()))?
+        .from_step_config(config)
+}
+
+fn mask_from_config(config: &StepConfig) -> Result<PieceMask, Error> {
+    PieceMask::parse(config.params.get("edges").map(String::as_str), config.params.get("corners").map(String::as_str))
+}
+
+fn parse_pieces(goals: &mut [PieceGoal], names: &[&str], pieces: &str) -> Result<(), Error> {
+    for piece in pieces.split(',').map(str::trim).filter(|p| !p.is_empty()) {
+        let upper = piece.to_uppercase();
+        let (name, goal) = match upper.strip_suffix('O') {
+            Some(name) => (name, PieceGoal::ORIENTED),
+            None => match upper.strip_suffix('P') {
+                Some(name) => (name, PieceGoal::PERMUTED),
+                None => (upper.as_str(), PieceGoal::SOLVED),
+            }
+        };
+        if name == "*" {
+            goals.iter_mut().for_each(|g| *g = goal);
+            continue;
+        }
+        //UFR and FRU are the same corner
+        let faces: HashSet<char> = name.chars().collect();
+        let id = names.iter()
+            .position(|n| n.len() == name.len() && n.chars().collect::<HashSet<char>>() == faces)
+            .ok_or(Error::InvalidConfig(format!("Invalid piece {piece}. Expected one of {}", names.join(","))))?;
+        goals[id] = goal;
+    }
+    Ok(())
+}
+
+//(piece id, orientation) for every position. Orientation 0 means oriented
+fn edge_pieces(cube: &Cube333) -> [(u8, u8); 12] {
+    cube.edges.get_edges().map(|edge| (edge.id, !edge.oriented_fb as u8))
+}
+
+fn corner_pieces(cube: &Cube333) -> [(u8, u8); 8] {
+    cube.corners.get_corners().map(|corner| (corner.id, corner.orientation))
+}
+
+//Moves that keep the orientation of every edge (FB axis) and every corner (UD axis)
+const EDGE_ORIENTATION_MOVES: [Turn333; 14] = [
+    Turn333::U, Turn333::Ui, Turn333::U2, Turn333::D, Turn333::Di, Turn333::D2,
+    Turn333::R, Turn333::Ri, Turn333::R2, Turn333::L, Turn333::Li, Turn333::L2,
+    Turn333::F2, Turn333::B2,
+];
+const CORNER_ORIENTATION_MOVES: [Turn333; 10] = [
+    Turn333::U, Turn333::Ui, Turn333::U2, Turn333::D, Turn333::Di, Turn333::D2,
+    Turn333::R2, Turn333::L2, Turn333::F2, Turn333::B2,
+];
+
+//Pattern database coordinate for a group of pieces of the same type.
+//Permuted pieces are encoded by their exact position, orientation only pieces only by the set of positions they occupy.
+//The positions are needed to know which pieces a move flips, so orientation only pieces are solved in many coordinates.
+#[derive(Clone)]
+struct PieceGroup {
+    positions: usize,
+    orientations: usize,
+    //Piece id and whether the orientation matters
+    permuted: Vec<(u8, bool)>,
+    //Bitmask of piece ids
+    oriented: u32,
+}
+
+impl PieceGroup {
+    fn new(positions: usize, orientations: usize) -> Self {
+        PieceGroup { positions, orientations, permuted: vec![], oriented: 0 }
+    }
+
+    fn is_empty(&self) -> bool {
+        self.permuted.is_empty() && self.oriented == 0
+    }
+
+    fn add(&mut self, id: u8, goal: PieceGoal) {
+        if goal.permutation {
+            self.permuted.push((id, goal.orientation));
+        } else {
+            self.oriented |= 1 << id;
+        }
+    }
+
+    //Greedily fills groups up to the maximum table size. Permuted pieces come first, so the orientation only ones stay together
+    fn split(goals: &[PieceGoal], orientations: usize) -> Vec<PieceGroup> {
+        let pieces = goals.iter().enumerate()
+            .filter(|(_, goal)| goal.permutation)
+            .chain(goals.iter().enumerate().filter(|(_, goal)| !goal.permutation && goal.orientation));
+        let mut groups = vec![];
+        let mut current = PieceGroup::new(goals.len(), orientations);
+        for (id, goal) in pieces {
+            let mut next = current.clone();
+            next.add(id as u8, *goal);
+            if next.size() > MAX_TABLE_SIZE && !current.is_empty() {
+                groups.push(current);
+                current = PieceGroup::new(goals.len(), orientations);
+                current.add(id as u8, *goal);
+            } else {
+                current = next;
+            }
+        }
+        if !current.is_empty() {
+            groups.push(current);
+        }
+        groups
+    }
+
+    //Sets the goals of the pieces of the group, so only they have to be solved
+    fn set_goals(&self, goals: &mut [PieceGoal]) {
+        for (id, orientation) in self.permuted.iter() {
+            goals[*id as usize] = if *orientation { PieceGoal::SOLVED } else { PieceGoal::PERMUTED };
+        }
+        for (id, goal) in goals.iter_mut().enumerate() {
+            if self.oriented & (1 << id) != 0 {
+                *goal = PieceGoal::ORIENTED;
+            }
+        }
+    }
+
+    //One cube for every coordinate that solves the group besides the solved cube. Orientation only pieces are solved in any
+    //set of positions, so these are found with a breadth-first search over the moves that keep every piece oriented
+    fn goal_states<const N: usize>(&self, moves: &[Turn333], mask: &PieceMask, pieces: fn(&Cube333) -> [(u8, u8); N]) -> Vec<Cube333> {
+        if self.oriented == 0 {
+            return vec![];
+        }
+        let start = Cube333::default();
+        let mut visited = HashSet::from([self.index(&pieces(&start))]);
+        let mut layer = vec![start];
+        let mut goal_states = vec![];
+        while !layer.is_empty() {
+            let mut next = vec![];
+            for cube in layer {
+                if mask.is_solved(&cube) && cube != start {
+                    goal_states.push(cube);
+                }
+                for m in moves {
+                    let mut cube = cube;
+                    cube.turn(*m);
+                    if visited.insert(self.index(&pieces(&cube))) {
+                        next.push(cube);
+                    }
+                }
+            }
+            layer = next;
+        }
+        goal_states
+    }
+
+    fn orientation_digits(&self) -> usize {
+        let digits = self.permuted.iter().filter(|(_, o)| *o).count() + self.oriented.count_ones() as usize;
+        //The orientation of the last piece follows from the others if the group contains every piece
+        let all_pieces = self.permuted.len() + self.oriented.count_ones() as usize == self.positions;
+        if all_pieces && self.permuted.iter().all(|(_, o)| *o) {
+            digits - 1
+        } else {
+            digits
+        }
+    }
+
+    fn size(&self) -> usize {
+        let permutations: usize = (0..self.permuted.len()).map(|i| self.positions - i).product();
+        permutations
+            * binomial(self.positions - self.permuted.len(), self.oriented.count_ones() as usize)
+            * self.orientations.pow(self.orientation_digits() as u32)
+    }
+
+    fn index(&self, pieces: &[(u8, u8)]) -> usize {
+        let mut location = [0usize; 12];
+        for (pos, (id, _)) in pieces.iter().enumerate() {
+            location[*id as usize] = pos;
+        }
+        let mut index = 0;
+        let mut used = 0u32;
+        for (i, (id, _)) in self.permuted.iter().enumerate() {
+            let pos = location[*id as usize];
+            let rank = pos - (used & ((1 << pos) - 1)).count_ones() as usize;
+            index = index * (self.positions - i) + rank;
+            used |= 1 << pos;
+        }
+        if self.oriented != 0 {
+            let mut combination = 0;
+            let mut k = 0;
+            for (pos, (id, _)) in pieces.iter().enumerate() {
+                if used & (1 << pos) != 0 || self.oriented & (1 << id) == 0 {
+                    continue;
+                }
+                k += 1;
+                combination += binomial(pos - (used & ((1 << pos) - 1)).count_ones() as usize, k);
+            }
+            index = index * binomial(self.positions - self.permuted.len(), self.oriented.count_ones() as usize) + combination;
+        }
+        let orientations = self.permuted.iter()
+            .filter(|(_, o)| *o)
+            .map(|(id, _)| pieces[location[*id as usize]].1)
+            .chain(pieces.iter().filter(|(id, _)| self.oriented & (1 << id) != 0).map(|(_, o)| *o))
+            .take(self.orientation_digits());
+        for orientation in orientations {
+            index = index * self.orientations + orientation as usize;
+        }
+        index
+    }
+}
+
+fn binomial(n: usize, k: usize) -> usize {
+    if k > n {
+        return 0;
+    }
+    (0..k).fold(1, |acc, i| acc * (n - i) / (i + 1))
+}
+
+#[cfg(test)]
+mod tests {
+    use std::str::FromStr;
+
+    use crate::algs::Algorithm;
+    use crate::cube::Cube333;
+    use crate::cube::turn::ApplyAlgorithm;
+    use crate::solver::df_search::CancelToken;
+    use crate::solver::solution::Solution;
+    use crate::steps::{dsl, solver};
+    use crate::steps::tables::PruningTables333;
+
+    use super::PieceMask;
+
+    fn tables() -> PruningTables333 {
+        #[cfg(feature = "fs")]
+        return PruningTables333::with_store(crate::solver::table_store::TableStore::memory());
+        #[cfg(not(feature = "fs"))]
+        PruningTables333::new()
+    }
+
+    fn cube(scramble: &str) -> Cube333 {
+        let mut cube = Cube333::default();
+        cube.apply_alg(&Algorithm::from_str(scramble).unwrap());
+        cube
+    }
+
+    fn solve(goal: &str, scramble: &str) -> Solution {
+        let configs = dsl::parse_steps(goal).unwrap();
+        let mut tables = tables();
+        solver::gen_tables(&configs, &mut tables);
+        let steps = solver::build_steps(configs, tables).unwrap();
+        let cancel_token = CancelToken::default();
+        let solution = crate::solver::solve_steps(cube(scramble), &steps, &cancel_token).next().unwrap();
+        solution
+    }
+
+    #[test]
+    fn oriented_pieces_can_be_anywhere() {
+        let mask = PieceMask::parse(Some("UFo"), None).unwrap();
+        assert!(mask.is_solved(&cube("U")));
+        assert_eq!(solve("GOAL[edges=UFo]", "U").len(), 0);
+        assert_eq!(solve("GOAL[edges=UFo,URo]", "U2").len(), 0);
+        assert_eq!(solve("GOAL[corners=UFRo,DBLo]", "U R2 D'").len(), 0);
+        assert_eq!(solve("GOAL[edges=UFo]", "F").len(), 1);
+    }
+
+    #[test]
+    fn solved_pieces_have_to_be_in_place() {
+        assert!(!PieceMask::parse(Some("UF"), None).unwrap().is_solved(&cube("U")));
+        let solution: Algorithm = solve("GOAL[edges=UF]", "U").into();
+        assert_eq!(solution.to_string(), "U'");
+        assert_eq!(solve("GOAL[edges=UFp,URo]", "U2").len(), 1);
+    }
+
+    #[test]
+    fn solutions_solve_the_goal() {
+        let scramble = "R' U' F L2 D' B2 U' F2 D' L2 B2 R2 U' R F' U2 L D' B2 R U R' U' F";
+        for (goal, edges, corners) in [
+            ("GOAL[edges=UF,UR,UB;corners=*o]", Some("UF,UR,UB"), Some("*o")),
+            ("GOAL[edges=UFo,URo,DBo;corners=UFRp]", Some("UFo,URo,DBo"), Some("UFRp")),
+            ("GOAL[edges=*o]", Some("*o"), None),
+        ] {
+            let solution = solve(goal, scramble);
+            let mut cube = cube(scramble);
+            cube.apply_alg(&solution.into());
+            assert!(PieceMask::parse(edges, corners).unwrap().is_solved(&cube), "{goal}");
+        }
+    }
+
+    #[test]
+    fn variants_are_named_after_the_pieces() {
+        let mask = PieceMask::parse(Some("UF,URo"), Some("ufrp")).unwrap();
+        assert_eq!(mask.description(), "URo,UF,UFRp");
+        assert_eq!(solve("GOAL[edges=UF,URo;corners=UFRp]", "U").steps[0].variant, "uro,uf,ufrp");
+    }
+}
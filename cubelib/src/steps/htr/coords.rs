@@ -125,16 +125,29 @@ impl From<&CornerCube333> for CPOrbitTwistCoord {
     }
 }
 
-impl From<&Cube333> for HTRDRUDCoord {
-    fn from(value: &Cube333) -> Self {
-        let ep_fbslice_coord = FBSliceUnsortedCoord::from(&value.edges).val();
+impl HTRDRUDCoord {
+    //Corners and edges are turned independently, so the coordinate can be combined from the move tables of both parts
+    pub(crate) const CORNERS_SIZE: usize = 70 * 6;
+    pub(crate) const EDGES_SIZE: usize = 70;
+
+    pub(crate) fn corners(value: &Cube333) -> usize {
         let cp_orbit_coord = CPOrbitUnsortedCoord::from(&value.corners).val();
         let cp_orbit_twist = CPOrbitTwistCoord::from(&value.corners).val();
+        cp_orbit_twist + cp_orbit_coord * CPOrbitTwistCoord::size()
+    }
+
+    pub(crate) fn edges(value: &Cube333) -> usize {
+        FBSliceUnsortedCoord::from(&value.edges).val()
+    }
 
-        let val = cp_orbit_twist
-            + cp_orbit_coord * CPOrbitTwistCoord::size()
-            + ep_fbslice_coord * CPOrbitTwistCoord::size() * CPOrbitUnsortedCoord::size();
-        Self(val as u16)
+    pub(crate) fn from_parts(corners: usize, ep_fbslice_coord: usize) -> Self {
+        Self((corners + ep_fbslice_coord * Self::CORNERS_SIZE) as u16)
+    }
+}
+
+impl From<&Cube333> for HTRDRUDCoord {
+    fn from(value: &Cube333) -> Self {
+        Self::from_parts(Self::corners(value), Self::edges(value))
     }
 }
 
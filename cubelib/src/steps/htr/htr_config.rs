@@ -1,6 +1,7 @@
-use std::rc::Rc;
+use std::sync::Arc;
 
 use itertools::Itertools;
+use crate::Error;
 use crate::cube::*;
 use crate::defs::*;
 use crate::solver::lookup_table::{LookupTable, NissLookupTable};
@@ -12,15 +13,15 @@ use crate::steps::Step333;
 
 pub type HTRPruningTable = NissLookupTable<{ HTRDRUD_SIZE }, HTRDRUDCoord>;
 pub type HTRSubsetTable = LookupTable<{ HTRDRUD_SIZE }, HTRDRUDCoord>;
-pub type HTRPruningTableStep<'a> = DefaultPruningTableStep<'a, {HTRDRUD_SIZE}, HTRDRUDCoord, {DRUDEOFB_SIZE}, DRUDEOFBCoord>;
+pub type HTRPruningTableStep = DefaultPruningTableStep<{HTRDRUD_SIZE}, HTRDRUDCoord, {DRUDEOFB_SIZE}, DRUDEOFBCoord>;
 
-pub fn from_step_config(table: &HTRPruningTable, config: StepConfig) -> Result<(Step333, DefaultStepOptions), String> {
+pub fn from_step_config(table: Arc<HTRPruningTable>, config: StepConfig) -> Result<(Step333<'static>, DefaultStepOptions), Error> {
     let step = if let Some(substeps) = config.substeps {
-        let axis: Result<Vec<CubeAxis>, String> = substeps.into_iter().map(|step| match step.to_lowercase().as_str() {
+        let axis: Result<Vec<CubeAxis>, Error> = substeps.into_iter().map(|step| match step.to_lowercase().as_str() {
             "htrud" | "ud" => Ok(CubeAxis::UD),
             "htrfb" | "fb" => Ok(CubeAxis::FB),
             "htrlr" | "lr" => Ok(CubeAxis::LR),
-            x => Err(format!("Invalid HTR substep {x}"))
+            x => Err(Error::InvalidConfig(format!("Invalid HTR substep {x}")))
         }).collect();
         htr(table, axis?)
     } else {
@@ -28,7 +29,7 @@ pub fn from_step_config(table: &HTRPruningTable, config: StepConfig) -> Result<(
     };
 
     if !config.params.is_empty() {
-        return Err(format!("Unreognized parameters: {:?}", config.params.keys()))
+        return Err(Error::InvalidConfig(format!("Unrecognized parameters: {:?}", config.params.keys())))
     }
 
     let search_opts = DefaultStepOptions::new(
@@ -46,18 +47,18 @@ pub fn from_step_config(table: &HTRPruningTable, config: StepConfig) -> Result<(
     Ok((step, search_opts))
 }
 
-pub fn htr_any(table: &HTRPruningTable) -> Step333 {
+pub fn htr_any(table: Arc<HTRPruningTable>) -> Step333<'static> {
     htr(table, vec![CubeAxis::UD, CubeAxis::FB, CubeAxis::LR])
 }
 
-pub fn htr<'a>(table: &'a HTRPruningTable, dr_axis: Vec<CubeAxis>) -> Step333<'a> {
+pub fn htr(table: Arc<HTRPruningTable>, dr_axis: Vec<CubeAxis>) -> Step333<'static> {
     let step_variants = dr_axis
         .into_iter()
         .flat_map(move |x| {
-            let x: Option<Box<dyn StepVariant + 'a>> = match x {
-                CubeAxis::UD => Some(Box::new(HTRPruningTableStep::new_niss_table(&HTR_DR_UD_MOVESET, vec![], table, Rc::new(vec![]), "ud"))),
-                CubeAxis::FB => Some(Box::new(HTRPruningTableStep::new_niss_table(&HTR_DR_UD_MOVESET, vec![Transformation333::new(CubeAxis::X, Direction::Clockwise)], table, Rc::new(vec![]), "fb"))),
-                CubeAxis::LR => Some(Box::new(HTRPruningTableStep::new_niss_table(&HTR_DR_UD_MOVESET, vec![Transformation333::new(CubeAxis::Z, Direction::Clockwise)], table, Rc::new(vec![]), "lr"))),
+            let x: Option<Box<dyn StepVariant>> = match x {
+                CubeAxis::UD => Some(Box::new(HTRPruningTableStep::new_niss_table(&HTR_DR_UD_MOVESET, vec![], table.clone(), Arc::new(vec![]), "ud"))),
+                CubeAxis::FB => Some(Box::new(HTRPruningTableStep::new_niss_table(&HTR_DR_UD_MOVESET, vec![Transformation333::new(CubeAxis::X, Direction::Clockwise)], table.clone(), Arc::new(vec![]), "fb"))),
+                CubeAxis::LR => Some(Box::new(HTRPruningTableStep::new_niss_table(&HTR_DR_UD_MOVESET, vec![Transformation333::new(CubeAxis::Z, Direction::Clockwise)], table.clone(), Arc::new(vec![]), "lr"))),
             };
             x
         })
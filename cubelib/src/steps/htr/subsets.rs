@@ -1,8 +1,9 @@
 use std::cmp::max;
 use std::collections::HashMap;
 use std::str::FromStr;
+use std::sync::Arc;
 use itertools::Itertools;
-use log::{trace, debug, warn};
+use log::{trace, debug, info, warn};
 use tinyset::Set64;
 use crate::algs::Algorithm;
 use crate::cube::*;
@@ -20,9 +21,9 @@ pub type Subset = crate::steps::util::Subset;
 pub const DR_SUBSETS: [Subset; 48] = crate::steps::util::DR_SUBSETS;
 
 #[derive(Clone)]
-pub struct DRSubsetFilter<'a>(&'a HTRSubsetTable, Set64<u8>);
+pub struct DRSubsetFilter(Arc<HTRSubsetTable>, Set64<u8>);
 
-impl <'a> DRSubsetFilter<'a> {
+impl DRSubsetFilter {
     fn matches_subset(&self, cube: &Cube333) -> bool {
         if DRUDEOFBCoord::from(cube).val() != 0 {
             return false;
@@ -31,7 +32,7 @@ impl <'a> DRSubsetFilter<'a> {
         self.1.contains(subset_id)
     }
 
-    fn new_subset(subset_table: &'a HTRSubsetTable, subsets: &Vec<(Subset, u8)>) -> Self {
+    fn new_subset(subset_table: Arc<HTRSubsetTable>, subsets: &Vec<(Subset, u8)>) -> Self {
         let mut subset_set = Set64::new();
         for (_, id) in subsets {
             subset_set.insert(id.clone());
@@ -40,7 +41,7 @@ impl <'a> DRSubsetFilter<'a> {
     }
 }
 
-pub fn dr_subset_filter<'a>(subset_table: &'a HTRSubsetTable, subsets: &Vec<String>) -> Option<DRSubsetFilter<'a>> {
+pub fn dr_subset_filter(subset_table: Arc<HTRSubsetTable>, subsets: &Vec<String>) -> Option<DRSubsetFilter> {
     let subsets = subsets.iter()
         .flat_map(|subset_name|{
             let matched_subsets = expand_subset_name(subset_name.as_str());
@@ -66,13 +67,13 @@ pub fn dr_subset_filter<'a>(subset_table: &'a HTRSubsetTable, subsets: &Vec<Stri
     }
 }
 
-impl PreStepCheck for DRSubsetFilter<'_> {
+impl PreStepCheck for DRSubsetFilter {
     fn is_cube_ready(&self, cube: &Cube333) -> bool {
         self.matches_subset(cube)
     }
 }
 
-impl PostStepCheck for DRSubsetFilter<'_> {
+impl PostStepCheck for DRSubsetFilter {
     fn is_solution_admissible(&self, cube: &Cube333, alg: &Algorithm) -> bool {
         let mut cube = cube.clone();
         cube.apply_alg(alg);
@@ -12,7 +12,15 @@ pub mod finish;
 pub mod solver;
 #[cfg(any(feature = "333eo", feature = "333dr", feature = "333htr", feature = "333fr", feature = "333finish"))]
 pub mod tables;
+#[cfg(any(feature = "333eo", feature = "333dr", feature = "333htr", feature = "333fr", feature = "333finish"))]
+pub mod registry;
+#[cfg(any(feature = "333eo", feature = "333dr", feature = "333htr", feature = "333fr", feature = "333finish"))]
+pub mod custom;
+#[cfg(any(feature = "333eo", feature = "333dr", feature = "333htr", feature = "333fr", feature = "333finish"))]
+pub mod goal;
 pub mod step;
+pub mod dsl;
+pub mod presets;
 pub mod coord;
 pub mod util;
 
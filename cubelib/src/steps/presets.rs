@@ -0,0 +1,17 @@
+//Named pipelines that ship with cubelib, written in the step configuration syntax of the dsl module.
+//Applications can offer additional presets, e.g. from a config file.
+
+pub const DEFAULT_STEPS: &str = "EO > RZP > DR[triggers=R,RUR,RU'R,RU2R] > HTR > FIN";
+
+pub const PRESETS: [(&str, &str); 4] = [
+    ("default", DEFAULT_STEPS),
+    ("eo-dr-only", "EO > RZP > DR[triggers=R,RUR,RU'R,RU2R]"),
+    ("htr-finish", "EO > DR > HTR > FIN"),
+    ("leave-slice", "EO > RZP > DR[triggers=R,RUR,RU'R,RU2R] > HTR > FRLS > FINLS"),
+];
+
+pub fn preset(name: &str) -> Option<&'static str> {
+    PRESETS.iter()
+        .find(|(preset_name, _)| *preset_name == name)
+        .map(|(_, steps)| *steps)
+}
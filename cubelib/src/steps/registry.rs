@@ -0,0 +1,203 @@
+use std::collections::HashMap;
+use std::sync::{Arc, LazyLock, RwLock};
+
+use crate::defs::StepKind;
+use crate::steps::step::{DefaultStepOptions, Step, StepConfig};
+use crate::steps::tables::PruningTables333;
+use crate::Error;
+
+//Steps defined outside of cubelib. Once registered, a step kind can be used in build_steps, gen_tables and the step DSL
+//just like the built-in ones. Built-in step orders always take precedence over registered ones.
+//
+//Tables of registered steps are stored with PruningTables333::insert_custom_table and looked up in build with shared_custom_table.
+
+pub type TableGenerator = Box<dyn Fn(&mut PruningTables333) + Send + Sync>;
+//Built steps keep their tables with Arc handles instead of borrowing the PruningTables333, see build_steps
+pub type StepBuilder = Box<dyn Fn(StepConfig, &PruningTables333) -> Result<(Step<'static>, DefaultStepOptions), Error> + Send + Sync>;
+
+pub struct StepDefinition {
+    //Step kinds this step can follow. None means the step can be the first step
+    pub predecessors: Vec<Option<StepKind>>,
+    //Loads or generates the tables the step needs. Called by gen_tables, so it should return early if the tables already exist
+    pub gen_tables: TableGenerator,
+    pub build: StepBuilder,
+}
+
+impl StepDefinition {
+    pub fn new<G, B>(predecessors: Vec<Option<StepKind>>, gen_tables: G, build: B) -> Self
+    where
+        G: Fn(&mut PruningTables333) + Send + Sync + 'static,
+        B: Fn(StepConfig, &PruningTables333) -> Result<(Step<'static>, DefaultStepOptions), Error> + Send + Sync + 'static {
+        StepDefinition {
+            predecessors,
+            gen_tables: Box::new(gen_tables),
+            build: Box::new(build),
+        }
+    }
+}
+
+static REGISTRY: LazyLock<RwLock<HashMap<StepKind, Arc<StepDefinition>>>> = LazyLock::new(Default::default);
+
+//Names of custom steps are case insensitive, so they are stored in lowercase
+fn key(kind: &StepKind) -> StepKind {
+    match kind {
+        StepKind::Other(name) => StepKind::Other(name.to_lowercase()),
+        kind => kind.clone(),
+    }
+}
+
+//Registers a step kind, replacing any previous definition. Custom steps use StepKind::Other with the name used in the DSL
+pub fn register_step(kind: StepKind, mut definition: StepDefinition) {
+    definition.predecessors = definition.predecessors.iter().map(|p| p.as_ref().map(key)).collect();
+    REGISTRY.write().unwrap().insert(key(&kind), Arc::new(definition));
+}
+
+pub fn unregister_step(kind: &StepKind) -> bool {
+    REGISTRY.write().unwrap().remove(&key(kind)).is_some()
+}
+
+pub fn is_registered(kind: &StepKind) -> bool {
+    REGISTRY.read().unwrap().contains_key(&key(kind))
+}
+
+pub fn registered_steps() -> Vec<StepKind> {
+    REGISTRY.read().unwrap().keys().cloned().collect()
+}
+
+//Generates the tables of every registered step, for applications that load all tables up front
+pub fn gen_all_tables(tables: &mut PruningTables333) {
+    let definitions: Vec<Arc<StepDefinition>> = REGISTRY.read().unwrap().values().cloned().collect();
+    for definition in definitions {
+        (definition.gen_tables)(tables);
+    }
+}
+
+//The lock is released before returning, so builders and table generators can use the registry themselves
+pub(crate) fn get(kind: &StepKind) -> Option<Arc<StepDefinition>> {
+    REGISTRY.read().unwrap().get(&key(kind)).cloned()
+}
+
+pub(crate) fn build_step(previous: Option<StepKind>, config: StepConfig, tables: &PruningTables333) -> Option<Result<(Step<'static>, DefaultStepOptions), Error>> {
+    let definition = get(&config.kind)?;
+    if !definition.predecessors.contains(&previous.as_ref().map(key)) {
+        return None;
+    }
+    Some((definition.build)(config, tables))
+}
+
+#[cfg(all(test, feature = "333eo"))]
+mod tests {
+    use std::str::FromStr;
+
+    use crate::algs::Algorithm;
+    use crate::cube::Cube333;
+    use crate::cube::turn::ApplyAlgorithm;
+    use crate::defs::StepKind;
+    use crate::solver::df_search::CancelToken;
+    use crate::steps::coord::Coord;
+    use crate::steps::custom::CustomStepBuilder;
+    use crate::steps::eo::coords::EOCoordFB;
+    use crate::steps::{dsl, solver};
+    use crate::steps::step::StepConfig;
+    use crate::steps::tables::PruningTables333;
+    use crate::Error;
+
+    use super::*;
+
+    //The registry is global, so every test uses its own step names
+    fn tables() -> PruningTables333 {
+        #[cfg(feature = "fs")]
+        return PruningTables333::with_store(crate::solver::table_store::TableStore::memory());
+        #[cfg(not(feature = "fs"))]
+        PruningTables333::new()
+    }
+
+    fn register_eo(name: &str, predecessors: Vec<Option<StepKind>>) {
+        CustomStepBuilder::from_coord::<2048, EOCoordFB>(name).register(predecessors);
+    }
+
+    fn failing_definition(predecessors: Vec<Option<StepKind>>, message: &'static str) -> StepDefinition {
+        StepDefinition::new(predecessors, |_| {}, move |_, _| Err(Error::InvalidConfig(message.to_string())))
+    }
+
+    #[test]
+    fn registers_and_unregisters_steps() {
+        let kind = StepKind::Other("registry-test".to_string());
+        register_step(kind.clone(), failing_definition(vec![None], "registered"));
+        assert!(is_registered(&kind));
+        assert!(is_registered(&StepKind::Other("Registry-Test".to_string())));
+        assert!(registered_steps().contains(&kind));
+        assert!(unregister_step(&kind));
+        assert!(!is_registered(&kind));
+        assert!(!unregister_step(&kind));
+    }
+
+    #[test]
+    fn unregistered_steps_are_unsupported() {
+        let configs = dsl::parse_steps("registry-unknown").unwrap();
+        assert!(matches!(solver::build_steps(configs, tables()), Err(Error::UnsupportedStepOrder { previous: None, .. })));
+    }
+
+    #[test]
+    fn registered_steps_are_generated_and_built() {
+        register_eo("registry-eofb", vec![None]);
+        let configs = dsl::parse_steps("registry-eofb").unwrap();
+        let mut tables = tables();
+        assert!(matches!(solver::build_steps(configs.clone(), &tables), Err(Error::MissingTable(_))));
+        solver::gen_tables(&configs, &mut tables);
+        assert!(tables.has_custom_table("custom-registry-eofb"));
+        let steps = solver::build_steps(configs, &tables).unwrap();
+
+        let mut cube = Cube333::default();
+        cube.apply_alg(&Algorithm::from_str("R U F").unwrap());
+        let cancel_token = CancelToken::default();
+        let solution = crate::solver::solve_steps(cube, &steps, &cancel_token).next().unwrap();
+        assert_eq!(solution.steps[0].kind, StepKind::Other("registry-eofb".to_string()));
+        cube.apply_alg(&solution.into());
+        assert_eq!(EOCoordFB::from(&cube).val(), 0);
+    }
+
+    #[test]
+    fn predecessors_are_checked() {
+        register_eo("registry-after-eo", vec![Some(StepKind::EO)]);
+        let mut tables = tables();
+        let first = dsl::parse_steps("registry-after-eo").unwrap();
+        solver::gen_tables(&first, &mut tables);
+        assert!(matches!(
+            solver::build_steps(first, &tables),
+            Err(Error::UnsupportedStepOrder { previous: None, .. })
+        ));
+
+        let after_eo = dsl::parse_steps("EO > registry-after-eo").unwrap();
+        solver::gen_tables(&after_eo, &mut tables);
+        assert_eq!(solver::build_steps(after_eo, &tables).unwrap().len(), 2);
+    }
+
+    #[test]
+    fn built_in_step_orders_take_precedence() {
+        register_step(StepKind::EO, failing_definition(vec![None], "registered"));
+        let configs = vec![StepConfig::new(StepKind::EO)];
+        let mut tables = tables();
+        solver::gen_tables(&configs, &mut tables);
+        let result = solver::build_steps(configs, &tables);
+        unregister_step(&StepKind::EO);
+        assert!(result.is_ok());
+    }
+
+    #[test]
+    fn registering_again_replaces_the_definition() {
+        let kind = StepKind::Other("registry-duplicate".to_string());
+        register_step(kind.clone(), failing_definition(vec![None], "first"));
+        register_step(StepKind::Other("REGISTRY-DUPLICATE".to_string()), failing_definition(vec![Some(StepKind::EO)], "second"));
+        assert_eq!(registered_steps().iter().filter(|k| **k == kind).count(), 1);
+
+        let definition = get(&kind).unwrap();
+        assert_eq!(definition.predecessors, vec![Some(StepKind::EO)]);
+        match (definition.build)(StepConfig::new(kind.clone()), &tables()) {
+            Err(Error::InvalidConfig(message)) => assert_eq!(message, "second"),
+            _ => panic!("Expected the second definition"),
+        }
+        assert!(build_step(None, StepConfig::new(kind.clone()), &tables()).is_none());
+        unregister_step(&kind);
+    }
+}
@@ -1,103 +1,154 @@
+use std::borrow::Borrow;
+use std::sync::Arc;
+
 use itertools::Itertools;
 use crate::defs::StepKind;
 use crate::steps;
+use crate::steps::{goal, registry};
 use crate::steps::step::{DefaultStepOptions, Step, StepConfig};
 use crate::steps::tables::PruningTables333;
+use crate::Error;
 
 pub fn gen_tables(steps: &Vec<StepConfig>, tables: &mut PruningTables333) {
-    let previous = vec![None].into_iter().chain(steps.iter().map(|x|Some(x.kind.clone()))).collect_vec();
+    gen_tables_after(None, steps, tables)
+}
+
+//Like gen_tables, for steps that continue a solution whose last step was of the given kind
+pub fn gen_tables_after(previous: Option<StepKind>, steps: &Vec<StepConfig>, tables: &mut PruningTables333) {
+    let previous = vec![previous].into_iter().chain(steps.iter().map(|x|Some(x.kind.clone()))).collect_vec();
     let steps = steps.into_iter().zip(previous.into_iter()).collect_vec();
 
+    //Built-in tables are collected first, so the missing ones can be generated at the same time
+    let mut keys = vec![];
+    let mut other_steps = vec![];
     for (conf, pre) in steps.iter() {
         match (pre.clone(), conf.kind.clone()) {
             #[cfg(feature = "333eo")]
-            (_, StepKind::EO) => tables.gen_eo(),
+            (_, StepKind::EO) => keys.push("eo"),
             #[cfg(feature = "333dr")]
             (_, StepKind::DR) => {
-                tables.gen_dr();
+                keys.push("dr");
                 #[cfg(feature = "333htr")]
-                tables.gen_htr();
+                keys.push("htr");
             },
             #[cfg(feature = "333htr")]
-            (_, StepKind::HTR) => tables.gen_htr(),
+            (_, StepKind::HTR) => keys.push("htr"),
             #[cfg(feature = "333fr")]
-            (_, StepKind::FR) => tables.gen_fr(),
+            (_, StepKind::FR) => keys.push("fr"),
             #[cfg(feature = "333fr")]
-            (_, StepKind::FRLS) => tables.gen_fr_leave_slice(),
+            (_, StepKind::FRLS) => keys.push("frls"),
             #[cfg(feature = "333finish")]
-            (Some(StepKind::FR), StepKind::FIN) | (Some(StepKind::FRLS), StepKind::FINLS) => tables.gen_fr_finish(),
+            (Some(StepKind::FR), StepKind::FIN) | (Some(StepKind::FRLS), StepKind::FINLS) => keys.push("frfin"),
             #[cfg(feature = "333finish")]
-            (Some(StepKind::HTR), StepKind::FIN) => tables.gen_htr_finish(),
+            (Some(StepKind::HTR), StepKind::FIN) => keys.push("htrfin"),
             #[cfg(feature = "333finish")]
-            (Some(StepKind::HTR), StepKind::FINLS) => tables.gen_htr_leave_slice_finish(),
-            _ => ()
+            (Some(StepKind::HTR), StepKind::FINLS) => keys.push("htrlsfin"),
+            _ => other_steps.push(conf),
+        }
+    }
+    tables.gen_concurrently(&keys);
+
+    for conf in other_steps {
+        match conf.kind.clone() {
+            StepKind::Other(name) if name.eq_ignore_ascii_case(goal::NAME) => goal::gen_tables(conf, tables),
+            kind => if let Some(definition) = registry::get(&kind) {
+                (definition.gen_tables)(tables);
+            }
         }
     }
 }
 
-pub fn build_steps(steps: Vec<StepConfig>, tables: &PruningTables333) -> Result<Vec<(Step, DefaultStepOptions)>, String> {
-    let previous = vec![None].into_iter().chain(steps.iter().map(|x|Some(x.kind.clone()))).collect_vec();
-    let steps = steps.into_iter().zip(previous.into_iter()).collect_vec();
+//The steps share the tables they use instead of borrowing them, so they can be sent to other threads
+//and outlive the PruningTables333. The tables can be passed as Arc<PruningTables333> or by reference
+pub fn build_steps(steps: Vec<StepConfig>, tables: impl Borrow<PruningTables333>) -> Result<Vec<(Step<'static>, DefaultStepOptions)>, Error> {
+    build_steps_after(None, steps, tables)
+}
 
-    let steps = steps.into_iter()
-        .flat_map(|(config, previous)| match (previous, config.kind.clone()) {
-            #[cfg(feature = "333eo")]
-            (None, StepKind::EO) => vec![steps::eo::eo_config::from_step_config(tables.eo().expect("EO table required"), config.clone())].into_iter(),
-            #[cfg(feature = "333dr")]
-            (Some(StepKind::EO), StepKind::RZP)   => vec![steps::dr::rzp_config::from_step_config(config.clone())].into_iter(),
-            #[cfg(feature = "333dr")]
-            (Some(StepKind::EO), StepKind::DR) => {
-                let dr_table = tables.dr().expect("DR table required");
-                if config.params.contains_key("triggers") {
-                    log::warn!("Found explicitly defined DR triggers without RZP. Adding RZP step with default settings.");
-                    let mut rzp_config = StepConfig::new(StepKind::RZP);
-                    rzp_config.quality = config.quality;
-                    rzp_config.max = config.max;
-                    rzp_config.absolute_max = config.absolute_max;
-                    #[cfg(feature = "333htr")]
-                    { vec![steps::dr::rzp_config::from_step_config(rzp_config), steps::dr::dr_trigger_config::from_step_config(dr_table, tables.htr_subset().expect("HTR Subset table required"), config.clone())].into_iter() }
-                    #[cfg(not(feature = "333htr"))]
-                    { vec![steps::dr::rzp_config::from_step_config(rzp_config), steps::dr::dr_trigger_config::from_step_config(dr_table, config.clone())].into_iter() }
-                } else {
-                    #[cfg(feature = "333htr")]
-                    { vec![steps::dr::dr_config::from_step_config(dr_table, tables.htr_subset().expect("HTR Subset table required"), config.clone())].into_iter() }
-                    #[cfg(not(feature = "333htr"))]
-                    { vec![steps::dr::dr_config::from_step_config(dr_table, config.clone())].into_iter() }
-                }
+//Like build_steps, for steps that continue a solution whose last step was of the given kind
+pub fn build_steps_after(previous: Option<StepKind>, steps: Vec<StepConfig>, tables: impl Borrow<PruningTables333>) -> Result<Vec<(Step<'static>, DefaultStepOptions)>, Error> {
+    let tables = tables.borrow();
+    let previous = vec![previous].into_iter().chain(steps.iter().map(|x|Some(x.kind.clone()))).collect_vec();
+    let steps = steps.into_iter().zip(previous).collect_vec();
+
+    let mut built = vec![];
+    for (config, previous) in steps {
+        let max_niss_switches = config.max_niss_switches;
+        for (step, mut search_opts) in build_step(previous, config, tables)? {
+            search_opts.max_niss_switches = max_niss_switches;
+            built.push((step, search_opts));
+        }
+    }
+    Ok(built)
+}
+
+fn table<T>(table: Option<&Arc<T>>, key: &str) -> Result<Arc<T>, Error> {
+    table.cloned().ok_or(Error::MissingTable(key.to_string()))
+}
+
+//A single config can result in multiple steps, e.g. DR with triggers adds an RZP step
+fn build_step(previous: Option<StepKind>, config: StepConfig, tables: &PruningTables333) -> Result<Vec<(Step<'static>, DefaultStepOptions)>, Error> {
+    let step = match (previous, config.kind.clone()) {
+        #[cfg(feature = "333eo")]
+        (None, StepKind::EO) => steps::eo::eo_config::from_step_config(table(tables.eo(), "eo")?, config)?,
+        #[cfg(feature = "333dr")]
+        (Some(StepKind::EO), StepKind::RZP) => steps::dr::rzp_config::from_step_config(config)?,
+        #[cfg(feature = "333dr")]
+        (Some(StepKind::EO), StepKind::DR) => {
+            let dr_table = table(tables.dr(), "dr")?;
+            if config.params.contains_key("triggers") {
+                log::warn!("Found explicitly defined DR triggers without RZP. Adding RZP step with default settings.");
+                let mut rzp_config = StepConfig::new(StepKind::RZP);
+                rzp_config.quality = config.quality;
+                rzp_config.max = config.max;
+                rzp_config.absolute_max = config.absolute_max;
+                let rzp = steps::dr::rzp_config::from_step_config(rzp_config)?;
+                #[cfg(feature = "333htr")]
+                let dr = steps::dr::dr_trigger_config::from_step_config(dr_table, table(tables.htr_subset(), "htr-subset")?, config)?;
+                #[cfg(not(feature = "333htr"))]
+                let dr = steps::dr::dr_trigger_config::from_step_config(dr_table, config)?;
+                return Ok(vec![rzp, dr]);
+            } else {
+                #[cfg(feature = "333htr")]
+                { steps::dr::dr_config::from_step_config(dr_table, table(tables.htr_subset(), "htr-subset")?, config)? }
+                #[cfg(not(feature = "333htr"))]
+                { steps::dr::dr_config::from_step_config(dr_table, config)? }
             }
-            #[cfg(feature = "333dr")]
-            (Some(StepKind::RZP), StepKind::DR) => {
-                let dr_table = tables.dr().expect("DR table required");
-                if !config.params.contains_key("triggers") {
-                    log::warn!("RZP without defining triggers is pointless and slower. Consider deleting the RZP step or adding explicit DR triggers.");
-                    #[cfg(feature = "333htr")]
-                    { vec![steps::dr::dr_config::from_step_config(dr_table, tables.htr_subset().expect("HTR Subset table required"), config.clone())].into_iter() }
-                    #[cfg(not(feature = "333htr"))]
-                    { vec![steps::dr::dr_config::from_step_config(dr_table, config.clone())].into_iter() }
-                } else {
-                    #[cfg(feature = "333htr")]
-                    { vec![steps::dr::dr_trigger_config::from_step_config(dr_table, tables.htr_subset().expect("HTR Subset table required"), config.clone())].into_iter() }
-                    #[cfg(not(feature = "333htr"))]
-                    { vec![steps::dr::dr_trigger_config::from_step_config(dr_table, config.clone())].into_iter() }
-                }
+        }
+        #[cfg(feature = "333dr")]
+        (Some(StepKind::RZP), StepKind::DR) => {
+            let dr_table = table(tables.dr(), "dr")?;
+            if !config.params.contains_key("triggers") {
+                log::warn!("RZP without defining triggers is pointless and slower. Consider deleting the RZP step or adding explicit DR triggers.");
+                #[cfg(feature = "333htr")]
+                { steps::dr::dr_config::from_step_config(dr_table, table(tables.htr_subset(), "htr-subset")?, config)? }
+                #[cfg(not(feature = "333htr"))]
+                { steps::dr::dr_config::from_step_config(dr_table, config)? }
+            } else {
+                #[cfg(feature = "333htr")]
+                { steps::dr::dr_trigger_config::from_step_config(dr_table, table(tables.htr_subset(), "htr-subset")?, config)? }
+                #[cfg(not(feature = "333htr"))]
+                { steps::dr::dr_trigger_config::from_step_config(dr_table, config)? }
             }
-            #[cfg(feature = "333htr")]
-            (Some(StepKind::DR), StepKind::HTR)   => vec![steps::htr::htr_config::from_step_config(tables.htr().expect("HTR table required"), config.clone())].into_iter(),
-            #[cfg(feature = "333fr")]
-            (Some(StepKind::HTR), StepKind::FR)   => vec![steps::fr::fr_config::from_step_config(tables.fr().expect("FR table required"), config.clone())].into_iter(),
-            #[cfg(feature = "333fr")]
-            (Some(StepKind::HTR), StepKind::FRLS)  => vec![steps::fr::fr_config::from_step_config_no_slice(tables.fr_leave_slice().expect("FRLeaveSlice table required"), config.clone())].into_iter(),
-            #[cfg(feature = "333finish")]
-            (Some(StepKind::HTR), StepKind::FIN)   => vec![steps::finish::finish_config::from_step_config_htr(tables.htr_finish().expect("HTRFinish table required"), config.clone())].into_iter(),
-            #[cfg(feature = "333finish")]
-            (Some(StepKind::HTR), StepKind::FINLS)   => vec![steps::finish::finish_config::from_step_config_htr_leave_slice(tables.htr_leave_slice_finish().expect("HTRLSFinish table required"), config.clone())].into_iter(),
-            #[cfg(feature = "333finish")]
-            (Some(StepKind::FR), StepKind::FIN)   => vec![steps::finish::finish_config::from_step_config_fr(tables.fr_finish().expect("FRFinish table required"), config.clone())].into_iter(),
-            #[cfg(feature = "333finish")]
-            (Some(StepKind::FRLS), StepKind::FINLS)   => vec![steps::finish::finish_config::from_step_config_fr_leave_slice(tables.fr_finish().expect("FRFinish table required"), config.clone())].into_iter(),
-            (None, x) => vec![Err(format!("{:?} is not supported as a first step", x))].into_iter(),
-            (Some(x), y) => vec![Err(format!("Unsupported step order {:?} > {:?}", x, y))].into_iter(),
-        })
-        .collect();
-    steps
-}
\ No newline at end of file
+        }
+        #[cfg(feature = "333htr")]
+        (Some(StepKind::DR), StepKind::HTR) => steps::htr::htr_config::from_step_config(table(tables.htr(), "htr")?, config)?,
+        #[cfg(feature = "333fr")]
+        (Some(StepKind::HTR), StepKind::FR) => steps::fr::fr_config::from_step_config(table(tables.fr(), "fr")?, config)?,
+        #[cfg(feature = "333fr")]
+        (Some(StepKind::HTR), StepKind::FRLS) => steps::fr::fr_config::from_step_config_no_slice(table(tables.fr_leave_slice(), "frls")?, config)?,
+        #[cfg(feature = "333finish")]
+        (Some(StepKind::HTR), StepKind::FIN) => steps::finish::finish_config::from_step_config_htr(table(tables.htr_finish(), "htrfin")?, config)?,
+        #[cfg(feature = "333finish")]
+        (Some(StepKind::HTR), StepKind::FINLS) => steps::finish::finish_config::from_step_config_htr_leave_slice(table(tables.htr_leave_slice_finish(), "htrlsfin")?, config)?,
+        #[cfg(feature = "333finish")]
+        (Some(StepKind::FR), StepKind::FIN) => steps::finish::finish_config::from_step_config_fr(table(tables.fr_finish(), "frfin")?, config)?,
+        #[cfg(feature = "333finish")]
+        (Some(StepKind::FRLS), StepKind::FINLS) => steps::finish::finish_config::from_step_config_fr_leave_slice(table(tables.fr_finish(), "frfin")?, config)?,
+        (_, StepKind::Other(name)) if name.eq_ignore_ascii_case(goal::NAME) => goal::from_step_config(tables, config)?,
+        (previous, kind) => match registry::build_step(previous.clone(), config, tables) {
+            Some(step) => step?,
+            None => return Err(Error::UnsupportedStepOrder { previous, kind }),
+        },
+    };
+    Ok(vec![step])
+}
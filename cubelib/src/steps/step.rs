@@ -1,15 +1,15 @@
 use std::cmp::min;
 use std::collections::HashMap;
 use std::marker::PhantomData;
-use std::rc::Rc;
+use std::sync::Arc;
 use log::trace;
 
 use crate::algs::Algorithm;
-use crate::cube::turn::ApplyAlgorithm;
+use crate::cube::turn::{ApplyAlgorithm, InvertibleMut, TransformableMut};
 use crate::defs::*;
 use crate::cube::*;
 use crate::solver::df_search::{CancelToken, dfs_iter};
-use crate::solver::lookup_table::{LookupTable, NissLookupTable};
+use crate::solver::lookup_table::{LookupTable, ModThreeLookupTable, NissLookupTable};
 use crate::solver::moveset::MoveSet;
 use crate::solver::solution::{Solution, SolutionStep};
 use crate::solver::stream;
@@ -34,6 +34,8 @@ pub struct StepConfig {
     pub quality: usize,
     #[cfg_attr(feature = "serde_support", serde(skip_serializing_if = "Option::is_none"))]
     pub niss: Option<NissSwitchType>,
+    #[cfg_attr(feature = "serde_support", serde(skip_serializing_if = "Option::is_none"))]
+    pub max_niss_switches: Option<usize>,
     pub params: HashMap<String, String>,
 }
 
@@ -48,6 +50,7 @@ impl StepConfig {
             absolute_max: None,
             step_limit: None,
             niss: None,
+            max_niss_switches: None,
             quality: 100,
             params: Default::default(),
         }
@@ -61,7 +64,11 @@ pub struct DefaultStepOptions {
     pub max_moves: u8,
     pub absolute_min_moves: Option<u8>,
     pub absolute_max_moves: Option<u8>,
-    pub step_limit: Option<usize>
+    pub step_limit: Option<usize>,
+    //Maximum number of NISS switches within this step
+    pub max_niss_switches: Option<usize>,
+    //Maximum number of NISS switches of the whole solution up to and including this step
+    pub max_total_niss_switches: Option<usize>,
 }
 
 impl DefaultStepOptions {
@@ -73,11 +80,14 @@ impl DefaultStepOptions {
             absolute_max_moves,
             niss_type,
             step_limit,
+            max_niss_switches: None,
+            max_total_niss_switches: None,
         }
     }
 }
 
-pub trait StepVariant: PreStepCheck + PostStepCheck
+//Steps are shared between threads, e.g. by a server that solves several requests with the same pipeline
+pub trait StepVariant: PreStepCheck + PostStepCheck + Send + Sync
 {
     fn move_set(&self, cube: &Cube333, depth_left: u8) -> &'_ MoveSet;
     fn pre_step_trans(&self) -> &'_ Vec<Transformation333>;
@@ -89,30 +99,38 @@ pub trait PreStepCheck {
     fn is_cube_ready(&self, cube: &Cube333) -> bool;
 }
 
-pub trait PostStepCheck {
+pub trait PostStepCheck: Send + Sync {
     fn is_solution_admissible(&self, cube: &Cube333, alg: &Algorithm) -> bool;
 }
 
-trait Heuristic {
+trait Heuristic: Send + Sync {
     fn heuristic(&self, cube: &Cube333, can_niss: bool) -> u8;
 }
 
-struct NissPruningTableHeuristic<'a, const HC_SIZE: usize, HC: Coord<HC_SIZE>>(&'a NissLookupTable<HC_SIZE, HC>);
-struct PruningTableHeuristic<'a, const HC_SIZE: usize, HC: Coord<HC_SIZE>>(&'a LookupTable<HC_SIZE, HC>);
+struct NissPruningTableHeuristic<const HC_SIZE: usize, HC: Coord<HC_SIZE>>(Arc<NissLookupTable<HC_SIZE, HC>>);
+struct PruningTableHeuristic<const HC_SIZE: usize, HC: Coord<HC_SIZE>>(Arc<LookupTable<HC_SIZE, HC>>);
+//Mod 3 tables need the moves the table was generated with to recover the distance
+struct ModThreePruningTableHeuristic<const HC_SIZE: usize, HC: Coord<HC_SIZE>>(Arc<ModThreeLookupTable<HC_SIZE, HC>>, Vec<Turn333>);
 
-impl <'a, const HC_SIZE: usize, HC: Coord<HC_SIZE>> NissPruningTableHeuristic<'a, HC_SIZE, HC> {
-    fn new(table: &'a NissLookupTable<HC_SIZE, HC>) -> Self {
+impl <const HC_SIZE: usize, HC: Coord<HC_SIZE>> NissPruningTableHeuristic<HC_SIZE, HC> {
+    fn new(table: Arc<NissLookupTable<HC_SIZE, HC>>) -> Self {
         Self(table)
     }
 }
 
-impl <'a, const HC_SIZE: usize, HC: Coord<HC_SIZE>> PruningTableHeuristic<'a, HC_SIZE, HC> {
-    fn new(table: &'a LookupTable<HC_SIZE, HC>) -> Self {
+impl <const HC_SIZE: usize, HC: Coord<HC_SIZE>> PruningTableHeuristic<HC_SIZE, HC> {
+    fn new(table: Arc<LookupTable<HC_SIZE, HC>>) -> Self {
         Self(table)
     }
 }
 
-impl <const HC_SIZE: usize, HC: Coord<HC_SIZE>> Heuristic for PruningTableHeuristic<'_, HC_SIZE, HC> where HC: for<'a> From<&'a Cube333> {
+impl <const HC_SIZE: usize, HC: Coord<HC_SIZE>> ModThreePruningTableHeuristic<HC_SIZE, HC> {
+    fn new(table: Arc<ModThreeLookupTable<HC_SIZE, HC>>, move_set: &MoveSet) -> Self {
+        Self(table, move_set.aux_moves.iter().chain(move_set.st_moves.iter()).cloned().collect())
+    }
+}
+
+impl <const HC_SIZE: usize, HC: Coord<HC_SIZE>> Heuristic for PruningTableHeuristic<HC_SIZE, HC> where HC: for<'a> From<&'a Cube333> {
     fn heuristic(&self, cube: &Cube333, can_niss: bool) -> u8 {
         let coord = HC::from(cube);
         let heuristic = self.0.get(coord);
@@ -124,7 +142,22 @@ impl <const HC_SIZE: usize, HC: Coord<HC_SIZE>> Heuristic for PruningTableHeuris
     }
 }
 
-impl <const HC_SIZE: usize, HC: Coord<HC_SIZE>> Heuristic for NissPruningTableHeuristic<'_, HC_SIZE, HC> where HC: for<'a> From<&'a Cube333> {
+impl <const HC_SIZE: usize, HC: Coord<HC_SIZE>> Heuristic for ModThreePruningTableHeuristic<HC_SIZE, HC> where HC: for<'a> From<&'a Cube333> {
+    fn heuristic(&self, cube: &Cube333, can_niss: bool) -> u8 {
+        //Any value other than 0 already means that the cube isn't solved, so walking isn't needed
+        if can_niss && self.0.get(HC::from(cube)) != 0 {
+            return 1;
+        }
+        let distance = self.0.distance(cube, &self.1);
+        if can_niss {
+            min(1, distance)
+        } else {
+            distance
+        }
+    }
+}
+
+impl <const HC_SIZE: usize, HC: Coord<HC_SIZE>> Heuristic for NissPruningTableHeuristic<HC_SIZE, HC> where HC: for<'a> From<&'a Cube333> {
     fn heuristic(&self, cube: &Cube333, can_niss: bool) -> u8 {
         let coord = HC::from(cube);
         let (val, niss) = self.0.get(coord);
@@ -136,31 +169,32 @@ impl <const HC_SIZE: usize, HC: Coord<HC_SIZE>> Heuristic for NissPruningTableHe
     }
 }
 
+//The move set and name are static, so steps built from them are Step<'static> and can be shared between threads.
+//Move sets that are only known at runtime can be created with MoveSet::from_moves
 pub struct DefaultPruningTableStep<
-    'a,
     const HC_SIZE: usize,
     HC: Coord<HC_SIZE>,
     const PC_SIZE: usize,
     PC: Coord<PC_SIZE>,
 >
 {
-    move_set: &'a MoveSet,
+    move_set: &'static MoveSet,
     pre_trans: Vec<Transformation333>,
-    heuristic: Box<dyn Heuristic + 'a>,
-    name: &'a str,
-    post_step_checks: Rc<Vec<Box<dyn PostStepCheck + 'a>>>,
+    heuristic: Box<dyn Heuristic>,
+    name: &'static str,
+    post_step_checks: Arc<Vec<Box<dyn PostStepCheck>>>,
     _hc: PhantomData<HC>,
     _pc: PhantomData<PC>,
 }
 
-impl <'a, const HC_SIZE: usize, HC: Coord<HC_SIZE>, const PC_SIZE: usize, PC: Coord<PC_SIZE>> PreStepCheck for DefaultPruningTableStep<'a, HC_SIZE, HC, PC_SIZE, PC> where PC: for<'b> From<&'b Cube333> {
+impl <const HC_SIZE: usize, HC: Coord<HC_SIZE>, const PC_SIZE: usize, PC: Coord<PC_SIZE>> PreStepCheck for DefaultPruningTableStep<HC_SIZE, HC, PC_SIZE, PC> where PC: for<'b> From<&'b Cube333> {
 
     fn is_cube_ready(&self, cube: &Cube333) -> bool {
         PC::from(cube).val() == 0
     }
 }
 
-impl <'a, const HC_SIZE: usize, HC: Coord<HC_SIZE>, const PC_SIZE: usize, PC: Coord<PC_SIZE>> PostStepCheck for DefaultPruningTableStep<'a, HC_SIZE, HC, PC_SIZE, PC> where PC: for<'b> From<&'b Cube333> {
+impl <const HC_SIZE: usize, HC: Coord<HC_SIZE>, const PC_SIZE: usize, PC: Coord<PC_SIZE>> PostStepCheck for DefaultPruningTableStep<HC_SIZE, HC, PC_SIZE, PC> where PC: for<'b> From<&'b Cube333> {
 
     fn is_solution_admissible(&self, cube: &Cube333, alg: &Algorithm) -> bool {
         self.post_step_checks.iter()
@@ -169,12 +203,11 @@ impl <'a, const HC_SIZE: usize, HC: Coord<HC_SIZE>, const PC_SIZE: usize, PC: Co
 }
 
 impl <
-    'a,
     const HC_SIZE: usize,
     HC: Coord<HC_SIZE>,
     const PC_SIZE: usize,
     PC: Coord<PC_SIZE>>
-StepVariant for DefaultPruningTableStep<'a, HC_SIZE, HC, PC_SIZE, PC> where PC: for<'b> From<&'b Cube333>, HC: for<'b> From<&'b Cube333> {
+StepVariant for DefaultPruningTableStep<HC_SIZE, HC, PC_SIZE, PC> where PC: for<'b> From<&'b Cube333>, HC: for<'b> From<&'b Cube333> {
 
     fn move_set(&self, _: &Cube333, _: u8) -> &'_ MoveSet {
         self.move_set
@@ -194,49 +227,67 @@ StepVariant for DefaultPruningTableStep<'a, HC_SIZE, HC, PC_SIZE, PC> where PC:
 }
 
 impl <
-    'a,
     const HC_SIZE: usize,
     HC: Coord<HC_SIZE>,
     const PC_SIZE: usize,
     PC: Coord<PC_SIZE>
 >
-DefaultPruningTableStep<'a, HC_SIZE, HC, PC_SIZE, PC> where PC: for<'b> From<&'b Cube333>, HC: for<'b> From<&'b Cube333>{
+DefaultPruningTableStep<HC_SIZE, HC, PC_SIZE, PC> where PC: for<'b> From<&'b Cube333>, HC: for<'b> From<&'b Cube333>{
 
-    pub fn new(move_set: &'a MoveSet,
+    pub fn new(move_set: &'static MoveSet,
                pre_trans: Vec<Transformation333>,
-               table: &'a LookupTable<HC_SIZE, HC>,
-               post_step_checker: Rc<Vec<Box<dyn PostStepCheck + 'a>>>,
-               name: &'a str) -> Self {
+               table: Arc<LookupTable<HC_SIZE, HC>>,
+               post_step_checker: Arc<Vec<Box<dyn PostStepCheck>>>,
+               name: &'static str) -> Self {
         DefaultPruningTableStep {
             move_set,
             pre_trans,
             heuristic: Box::new(PruningTableHeuristic::new(table)),
             name,
             post_step_checks: post_step_checker,
-            _hc: PhantomData::default(),
-            _pc: PhantomData::default(),
+            _hc: PhantomData,
+            _pc: PhantomData,
         }
     }
 
-    pub fn new_niss_table(move_set: &'a MoveSet,
+    pub fn new_niss_table(move_set: &'static MoveSet,
                pre_trans: Vec<Transformation333>,
-               table: &'a NissLookupTable<HC_SIZE, HC>,
-               post_step_checker: Rc<Vec<Box<dyn PostStepCheck + 'a>>>,
-               name: &'a str) -> Self {
+               table: Arc<NissLookupTable<HC_SIZE, HC>>,
+               post_step_checker: Arc<Vec<Box<dyn PostStepCheck>>>,
+               name: &'static str) -> Self {
         DefaultPruningTableStep {
             move_set,
             pre_trans,
             heuristic: Box::new(NissPruningTableHeuristic::new(table)),
             name,
             post_step_checks: post_step_checker,
-            _hc: PhantomData::default(),
-            _pc: PhantomData::default(),
+            _hc: PhantomData,
+            _pc: PhantomData,
+        }
+    }
+
+    //The table has to be generated with the same move set
+    pub fn new_mod_three_table(move_set: &'static MoveSet,
+               pre_trans: Vec<Transformation333>,
+               table: Arc<ModThreeLookupTable<HC_SIZE, HC>>,
+               post_step_checker: Arc<Vec<Box<dyn PostStepCheck>>>,
+               name: &'static str) -> Self {
+        DefaultPruningTableStep {
+            move_set,
+            pre_trans,
+            heuristic: Box::new(ModThreePruningTableHeuristic::new(table, move_set)),
+            name,
+            post_step_checks: post_step_checker,
+            _hc: PhantomData,
+            _pc: PhantomData,
         }
     }
 }
 
+//Cloning a step shares its variants, so a built pipeline can be reused with different search options
+#[derive(Clone)]
 pub struct Step<'a> {
-    step_variants: Vec<Box<dyn StepVariant + 'a>>,
+    step_variants: Vec<Arc<dyn StepVariant + 'a>>,
     is_major: bool,
     kind: StepKind,
 }
@@ -247,12 +298,34 @@ impl<'a> Step<'a> {
         kind: StepKind,
         is_major: bool,
     ) -> Self {
-        Step { step_variants, kind, is_major }
+        Step { step_variants: step_variants.into_iter().map(Arc::from).collect(), kind, is_major }
     }
 
     pub fn kind(&self) -> StepKind {
         self.kind.clone()
     }
+
+    //Admissible lower bound for the number of moves this step needs on the given cube, or None if no variant can be applied.
+    //This mirrors the checks dfs_iter does before starting a search.
+    pub fn lower_bound(&self, cube: &Cube333, search_opts: &DefaultStepOptions, starts_on_normal: bool) -> Option<u8> {
+        let can_niss = search_opts.niss_type != NissSwitchType::Never;
+        self.step_variants.iter()
+            .filter_map(|step_variant| {
+                let mut cube = *cube;
+                for t in step_variant.pre_step_trans().iter().cloned() {
+                    cube.transform(t);
+                }
+                if !step_variant.is_cube_ready(&cube) {
+                    return None;
+                }
+                if search_opts.niss_type == NissSwitchType::Never && !starts_on_normal {
+                    cube.invert();
+                }
+                Some(step_variant.heuristic(&cube, search_opts.min_moves, can_niss))
+            })
+            .min()
+            .map(|h| h.max(search_opts.min_moves))
+    }
 }
 
 pub fn first_step<
@@ -281,55 +354,152 @@ pub fn next_step<
     cancel_token: &'a CancelToken,
 ) -> impl Iterator<Item = Solution> + 'a {
     stream::iterated_dfs(algs, cancel_token, move |solution, depth, cancel_token| {
-        let absolute_target_length = solution.len() as u8 + depth;
-        let result: Box<dyn Iterator<Item = Solution>> =
-            if depth < search_opts.min_moves ||
-                depth > search_opts.max_moves ||
-                search_opts.absolute_min_moves.map(|m| m > absolute_target_length).unwrap_or(false) ||
-                search_opts.absolute_max_moves.map(|m| m < absolute_target_length).unwrap_or(false) {
-                Box::new(vec![].into_iter())
-            } else {
-                let mut cube = cube.clone();
-                let alg: Algorithm = solution.clone().into();
-                let ends_on_normal = solution.ends_on_normal();
-                cube.apply_alg(&alg);
-                let stage_opts = DefaultStepOptions::new(depth, depth, None, None, search_opts.niss_type, search_opts.step_limit);
-                let previous_normal = alg.normal_moves.last().cloned();
-                let previous_inverse = alg.inverse_moves.last().cloned();
-
-                trace!("Current solution step {}, depth {depth}, {alg}, normal {}, {previous_normal:?}, {previous_inverse:?}", step.kind, solution.ends_on_normal);
+        let result: Box<dyn Iterator<Item = Solution>> = match StageSearch::new(&solution, depth, &search_opts, cube) {
+            None => Box::new(vec![].into_iter()),
+            Some(stage) => {
+                trace!("Current solution step {}, depth {depth}, {}, normal {}, {:?}, {:?}", step.kind, stage.alg, solution.ends_on_normal, stage.previous_normal, stage.previous_inverse);
                 //Only allow the first variant to use the empty solution, otherwise we get lots of duplicates
                 let values = step
                     .step_variants
                     .iter()
-                    .flat_map(move |step_variant| {
-                        dfs_iter(
-                            step_variant.as_ref(),
-                            cube.clone(),
-                            stage_opts.clone(),
-                            previous_normal,
-                            previous_inverse,
-                            ends_on_normal,
-                            cancel_token,
-                        )
-                        .map(|alg| (step_variant.name(), alg))
-                    })
-                    .flat_map(|(name, iter)| iter.map(move |alg| (name, alg)))
-                    .map(move |(variant_name, step_alg)| {
-                        let mut sol = solution.clone();
-                        if step.is_major || step_alg.len() > 0 {
-                            let sol_step = SolutionStep {
-                                kind: step.kind(),
-                                alg: step_alg,
-                                variant: variant_name.to_string(),
-                                comment: String::default(),
-                            };
-                            sol.add_step(sol_step);
-                        }
-                        sol
-                    });
+                    .flat_map(move |step_variant| stage.variant_solutions(step, step_variant.as_ref(), &solution, cancel_token));
                 Box::new(values)
-            };
+            }
+        };
         result
     })
 }
+
+//Like next_step, but searches the variants of several partial solutions at the same time on the given number of threads.
+//The solutions are returned in the same order as next_step returns them
+pub fn next_step_parallel<
+    'a,
+    'b,
+    IN: Iterator<Item = Solution> + 'a,
+>(
+    algs: IN,
+    step: &'a Step<'b>,
+    search_opts: DefaultStepOptions,
+    cube: Cube333,
+    threads: usize,
+    cancel_token: &'a CancelToken,
+) -> impl Iterator<Item = Solution> + 'a {
+    stream::iterated_dfs_parallel(algs, step.step_variants.len(), threads, cancel_token, move |solution, depth, variant| {
+        match StageSearch::new(solution, depth, &search_opts, cube) {
+            None => vec![],
+            Some(stage) => stage.variant_solutions(step, step.step_variants[variant].as_ref(), solution, cancel_token).collect(),
+        }
+    })
+}
+
+//The search of a step with exactly `depth` moves after a partial solution
+struct StageSearch {
+    cube: Cube333,
+    alg: Algorithm,
+    stage_opts: DefaultStepOptions,
+    previous_normal: Option<Turn333>,
+    previous_inverse: Option<Turn333>,
+    ends_on_normal: bool,
+}
+
+impl StageSearch {
+    //None if the depth is outside of the bounds of the step
+    fn new(solution: &Solution, depth: u8, search_opts: &DefaultStepOptions, mut cube: Cube333) -> Option<Self> {
+        let absolute_target_length = solution.len() as u8 + depth;
+        if depth < search_opts.min_moves ||
+            depth > search_opts.max_moves ||
+            search_opts.absolute_min_moves.map(|m| m > absolute_target_length).unwrap_or(false) ||
+            search_opts.absolute_max_moves.map(|m| m < absolute_target_length).unwrap_or(false) {
+            return None;
+        }
+        let alg: Algorithm = solution.clone().into();
+        cube.apply_alg(&alg);
+        let mut stage_opts = DefaultStepOptions::new(depth, depth, None, None, search_opts.niss_type, search_opts.step_limit);
+        stage_opts.max_niss_switches = [
+            search_opts.max_niss_switches,
+            search_opts.max_total_niss_switches.map(|max| max.saturating_sub(solution.niss_switches()))
+        ].into_iter().flatten().min();
+        Some(StageSearch {
+            cube,
+            previous_normal: alg.normal_moves.last().cloned(),
+            previous_inverse: alg.inverse_moves.last().cloned(),
+            alg,
+            stage_opts,
+            ends_on_normal: solution.ends_on_normal(),
+        })
+    }
+
+    fn variant_solutions<'a, 'b>(&self, step: &'a Step<'b>, step_variant: &'a (dyn StepVariant + 'b), solution: &Solution, cancel_token: &'a CancelToken) -> Box<dyn Iterator<Item = Solution> + 'a> {
+        let solution = solution.clone();
+        let solutions = dfs_iter(
+                step_variant,
+                self.cube,
+                self.stage_opts,
+                self.previous_normal,
+                self.previous_inverse,
+                self.ends_on_normal,
+                cancel_token,
+            )
+            .into_iter()
+            .flatten()
+            .map(move |step_alg| {
+                let mut sol = solution.clone();
+                if step.is_major || step_alg.len() > 0 {
+                    let sol_step = SolutionStep {
+                        kind: step.kind(),
+                        alg: step_alg,
+                        variant: step_variant.name().to_string(),
+                        comment: String::default(),
+                        niss_switches: 0,
+                    };
+                    sol.add_step(sol_step);
+                }
+                sol
+            });
+        Box::new(solutions)
+    }
+}
+
+#[cfg(all(test, feature = "333eo"))]
+mod tests {
+    use std::str::FromStr;
+    use std::sync::Arc;
+
+    use crate::algs::Algorithm;
+    use crate::cube::Cube333;
+    use crate::cube::turn::ApplyAlgorithm;
+    use crate::defs::{NissSwitchType, StepKind};
+    use crate::solver::df_search::CancelToken;
+    use crate::solver::lookup_table;
+    use crate::solver::solve_steps;
+    use crate::steps::coord::ZeroCoord;
+    use crate::steps::eo::coords::EOCoordFB;
+    use crate::steps::eo::eo_config::{EO_FB_MOVESET, EOPruningTable};
+
+    use super::{DefaultPruningTableStep, DefaultStepOptions, Step};
+
+    type EOStep = DefaultPruningTableStep<2048, EOCoordFB, 0, ZeroCoord>;
+
+    fn solution_lengths(step: Step<'static>, cube: Cube333) -> Vec<usize> {
+        let steps = vec![(step, DefaultStepOptions::new(0, 20, None, None, NissSwitchType::Never, None))];
+        let cancel_token = CancelToken::default();
+        solve_steps(cube, &steps, &cancel_token).take(20).map(|s| s.len()).collect()
+    }
+
+    #[test]
+    fn mod_three_tables_find_solutions_of_the_same_length() {
+        let table = Arc::new(lookup_table::generate(&EO_FB_MOVESET, &|c: &Cube333| EOCoordFB::from(c), &|| EOPruningTable::new(false), &|table, coord, val| table.set(coord, val)));
+        let mod_three = Arc::new(lookup_table::generate_mod_three(&EO_FB_MOVESET, &|c: &Cube333| EOCoordFB::from(c)));
+        for scramble in ["R U F", "F B R2 L' D F'", "R' U' F L2 D' B2 U' F2 D' L2 B2 R2 U' R F' U2 L D' B2 R U R' U' F"] {
+            let mut cube = Cube333::default();
+            cube.apply_alg(&Algorithm::from_str(scramble).unwrap());
+
+            let full = Step::new(vec![Box::new(EOStep::new(&EO_FB_MOVESET, vec![], table.clone(), Arc::new(vec![]), "fb"))], StepKind::EO, true);
+            let mod_three = Step::new(vec![Box::new(EOStep::new_mod_three_table(&EO_FB_MOVESET, vec![], mod_three.clone(), Arc::new(vec![]), "fb"))], StepKind::EO, true);
+            let full = solution_lengths(full, cube);
+            assert_eq!(full.len(), 20, "{scramble}");
+            assert_eq!(full[0], table.get(EOCoordFB::from(&cube)) as usize, "{scramble}");
+            assert_eq!(solution_lengths(mod_three, cube), full, "{scramble}");
+        }
+    }
+}
@@ -1,3 +1,6 @@
+use std::any::Any;
+use std::collections::HashMap;
+use std::sync::Arc;
 #[cfg(not(target_arch = "wasm32"))]
 use std::time::Instant;
 
@@ -26,31 +29,46 @@ use crate::steps::htr::coords::HTRDRUDCoord;
 #[cfg(feature = "333htr")]
 use crate::steps::htr::htr_config::{HTR_DR_UD_MOVESET, HTRPruningTable, HTRSubsetTable};
 use crate::solver::lookup_table;
+use crate::solver::lookup_table::{LoadFromDisk, TableData};
 #[cfg(feature = "fs")]
-use crate::solver::lookup_table::{LoadFromDisk, SaveToDisk, LookupTable, NissLookupTable};
+use crate::solver::lookup_table::{LookupTable, NissLookupTable};
+use crate::solver::table_header;
+use crate::solver::table_header::{Compression, TableId};
+use crate::solver::table_stats::TableStats;
+#[cfg(feature = "fs")]
+use crate::solver::table_store::TableStore;
 #[cfg(feature = "fs")]
 use crate::steps::coord::Coord;
+use crate::Error;
 
 #[derive(Clone)]
 pub struct PruningTables333 {
     #[cfg(feature = "333eo")]
-    eo: Option<EOPruningTable>,
+    eo: Option<Arc<EOPruningTable>>,
     #[cfg(feature = "333dr")]
-    dr: Option<DRPruningTable>,
+    dr: Option<Arc<DRPruningTable>>,
     #[cfg(feature = "333htr")]
-    htr: Option<HTRPruningTable>,
+    htr: Option<Arc<HTRPruningTable>>,
     #[cfg(feature = "333htr")]
-    htr_subset: Option<HTRSubsetTable>,
+    htr_subset: Option<Arc<HTRSubsetTable>>,
     #[cfg(feature = "333fr")]
-    frls: Option<FRLeaveSlicePruningTable>,
+    frls: Option<Arc<FRLeaveSlicePruningTable>>,
     #[cfg(feature = "333fr")]
-    fr: Option<FRPruningTable>,
+    fr: Option<Arc<FRPruningTable>>,
     #[cfg(feature = "333finish")]
-    fr_finish: Option<FRFinishPruningTable>,
+    fr_finish: Option<Arc<FRFinishPruningTable>>,
     #[cfg(feature = "333finish")]
-    htr_finish: Option<HTRFinishPruningTable>,
+    htr_finish: Option<Arc<HTRFinishPruningTable>>,
     #[cfg(feature = "333finish")]
-    htr_ls_finish: Option<HTRLeaveSliceFinishPruningTable>
+    htr_ls_finish: Option<Arc<HTRLeaveSliceFinishPruningTable>>,
+    //Tables of steps added through the step registry
+    custom: HashMap<String, Arc<dyn Any + Send + Sync>>,
+    //Where tables are loaded from and saved to
+    #[cfg(feature = "fs")]
+    store: TableStore,
+    //Compression of tables that are saved to disk
+    #[cfg(feature = "fs")]
+    compression: Compression,
 }
 
 impl PruningTables333 {
@@ -76,48 +94,318 @@ impl PruningTables333 {
             #[cfg(feature = "333finish")]
             htr_finish: None,
             #[cfg(feature = "333finish")]
-            htr_ls_finish: None
+            htr_ls_finish: None,
+            custom: HashMap::new(),
+            #[cfg(feature = "fs")]
+            store: TableStore::from_env(),
+            #[cfg(feature = "fs")]
+            compression: Compression::None,
         }
     }
 
+    //Uses the given store instead of the one configured by the environment
+    #[cfg(feature = "fs")]
+    pub fn with_store(store: TableStore) -> PruningTables333 {
+        let mut tables = Self::new();
+        tables.store = store;
+        tables
+    }
+
     #[cfg(feature = "fs")]
-    pub fn save(&self, key: &str) -> std::io::Result<()> {
+    pub fn store(&self) -> &TableStore {
+        &self.store
+    }
+
+    //Compressed tables take less space on disk, but can't be shared between processes
+    #[cfg(feature = "fs")]
+    pub fn set_compression(&mut self, compression: Compression) {
+        self.compression = compression;
+    }
+
+    //Identifies the table stored under the key. Table files that don't match it are regenerated
+    pub fn table_id(key: &str) -> Option<TableId> {
+        match key {
+            #[cfg(feature = "333eo")]
+            "eo" => Some(EOPruningTable::table_id(&EO_FB_MOVESET, Self::VERSION)),
+            #[cfg(feature = "333dr")]
+            "dr" => Some(DRPruningTable::table_id(&DR_UD_EO_FB_MOVESET, Self::VERSION)),
+            #[cfg(feature = "333htr")]
+            "htr" => Some(HTRPruningTable::table_id(&HTR_DR_UD_MOVESET, Self::VERSION)),
+            #[cfg(feature = "333htr")]
+            "htr-subset" => Some(HTRSubsetTable::table_id(&HTR_DR_UD_MOVESET, Self::VERSION)),
+            #[cfg(feature = "333fr")]
+            "fr" => Some(FRPruningTable::table_id(&FR_UD_MOVESET, Self::VERSION)),
+            #[cfg(feature = "333fr")]
+            "frls" => Some(FRLeaveSlicePruningTable::table_id(&FR_UD_MOVESET, Self::VERSION)),
+            #[cfg(feature = "333finish")]
+            "frfin" => Some(FRFinishPruningTable::table_id(&FRUD_FINISH_MOVESET, Self::VERSION)),
+            #[cfg(feature = "333finish")]
+            "htrfin" => Some(HTRFinishPruningTable::table_id(&HTR_FINISH_MOVESET, Self::VERSION)),
+            #[cfg(feature = "333finish")]
+            "htrlsfin" => Some(HTRLeaveSliceFinishPruningTable::table_id(&HTR_FINISH_MOVESET, Self::VERSION)),
+            _ => None,
+        }
+    }
+
+    #[cfg(feature = "fs")]
+    fn load_table<T: LoadFromDisk>(store: &TableStore, key: &str) -> Result<T, Error> {
+        let id = Self::table_id(key).ok_or(Error::MissingTable(key.to_string()))?;
+        store.load("333", key, &id)
+    }
+
+    #[cfg(feature = "fs")]
+    fn save_table<T: TableData>(&self, key: &str, table: &T) -> Result<(), Error> {
+        let id = Self::table_id(key).ok_or(Error::MissingTable(key.to_string()))?;
+        self.store.save("333", key, table, &id, self.compression).map(|_| ())
+    }
+
+    //Read-only stores silently skip saving, so there is nothing to log
+    #[cfg(feature = "fs")]
+    fn save_and_log(&self, key: &str) {
+        if !self.store.is_writable() {
+            return;
+        }
+        if let Err(e) = self.save(key) {
+            error!("Error saving {key} table to disk: {e}");
+        } else {
+            info!("Saved {key} table to disk");
+        }
+    }
+
+    //Loads or generates the tables of all enabled steps, see gen_concurrently
+    pub fn gen_all(&mut self) {
+        let keys: Vec<&str> = vec![
+            #[cfg(feature = "333eo")]
+            "eo",
+            #[cfg(feature = "333dr")]
+            "dr",
+            #[cfg(feature = "333htr")]
+            "htr",
+            #[cfg(feature = "333fr")]
+            "fr",
+            #[cfg(feature = "333fr")]
+            "frls",
+            #[cfg(feature = "333finish")]
+            "frfin",
+            #[cfg(feature = "333finish")]
+            "htrfin",
+            #[cfg(feature = "333finish")]
+            "htrlsfin",
+        ];
+        self.gen_concurrently(&keys);
+    }
+
+    //Loads or generates the tables of the given keys, like calling the gen_ functions one after another.
+    //The tables don't depend on each other, so the missing ones are generated on separate threads.
+    //Only the htr-subset table needs the htr table, so it's generated afterwards
+    #[cfg(not(target_arch = "wasm32"))]
+    pub fn gen_concurrently(&mut self, keys: &[&str]) {
+        //With a single core the threads would only compete for it and keep all tables in memory at once
+        if std::thread::available_parallelism().map_or(1, |n| n.get()) == 1 {
+            self.gen_sequentially(keys);
+            return;
+        }
+        #[cfg(feature = "fs")]
+        for key in keys {
+            self.load_missing(key);
+        }
+        let wanted = |key: &str| keys.contains(&key);
+        let mut generated: Vec<&str> = vec![];
+        std::thread::scope(|s| {
+            #[cfg(feature = "333eo")]
+            let eo = (wanted("eo") && self.eo.is_none()).then(|| s.spawn(gen_eo));
+            #[cfg(feature = "333dr")]
+            let dr = (wanted("dr") && self.dr.is_none()).then(|| s.spawn(gen_dr));
+            #[cfg(feature = "333htr")]
+            let htr = (wanted("htr") && self.htr.is_none()).then(|| s.spawn(gen_htr));
+            #[cfg(feature = "333fr")]
+            let fr = (wanted("fr") && self.fr.is_none()).then(|| s.spawn(gen_fr));
+            #[cfg(feature = "333fr")]
+            let frls = (wanted("frls") && self.frls.is_none()).then(|| s.spawn(gen_fr_leave_slice));
+            #[cfg(feature = "333finish")]
+            let fr_finish = (wanted("frfin") && self.fr_finish.is_none()).then(|| s.spawn(gen_fr_finish));
+            #[cfg(feature = "333finish")]
+            let htr_finish = (wanted("htrfin") && self.htr_finish.is_none()).then(|| s.spawn(gen_htr_finish));
+            #[cfg(feature = "333finish")]
+            let htr_ls_finish = (wanted("htrlsfin") && self.htr_ls_finish.is_none()).then(|| s.spawn(gen_htr_no_slice_finish));
+
+            #[cfg(feature = "333eo")]
+            if let Some(worker) = eo {
+                self.eo = Some(Arc::new(worker.join().expect("EO table generation panicked")));
+                generated.push("eo");
+            }
+            #[cfg(feature = "333dr")]
+            if let Some(worker) = dr {
+                self.dr = Some(Arc::new(worker.join().expect("DR table generation panicked")));
+                generated.push("dr");
+            }
+            #[cfg(feature = "333htr")]
+            if let Some(worker) = htr {
+                self.htr = Some(Arc::new(worker.join().expect("HTR table generation panicked")));
+                generated.push("htr");
+            }
+            #[cfg(feature = "333fr")]
+            if let Some(worker) = fr {
+                self.fr = Some(Arc::new(worker.join().expect("FR table generation panicked")));
+                generated.push("fr");
+            }
+            #[cfg(feature = "333fr")]
+            if let Some(worker) = frls {
+                self.frls = Some(Arc::new(worker.join().expect("FRLS table generation panicked")));
+                generated.push("frls");
+            }
+            #[cfg(feature = "333finish")]
+            if let Some(worker) = fr_finish {
+                self.fr_finish = Some(Arc::new(worker.join().expect("FR finish table generation panicked")));
+                generated.push("frfin");
+            }
+            #[cfg(feature = "333finish")]
+            if let Some(worker) = htr_finish {
+                self.htr_finish = Some(Arc::new(worker.join().expect("HTR finish table generation panicked")));
+                generated.push("htrfin");
+            }
+            #[cfg(feature = "333finish")]
+            if let Some(worker) = htr_ls_finish {
+                self.htr_ls_finish = Some(Arc::new(worker.join().expect("HTR leave slice finish table generation panicked")));
+                generated.push("htrlsfin");
+            }
+        });
+        #[cfg(feature = "fs")]
+        for key in generated.iter() {
+            self.save_and_log(key);
+        }
+        #[cfg(feature = "333htr")]
+        if wanted("htr") {
+            self.gen_htr_subset(generated.contains(&"htr"));
+        }
+    }
+
+    //Threads aren't available, so the tables are generated one after another
+    #[cfg(target_arch = "wasm32")]
+    pub fn gen_concurrently(&mut self, keys: &[&str]) {
+        self.gen_sequentially(keys);
+    }
+
+    fn gen_sequentially(&mut self, keys: &[&str]) {
+        for key in keys {
+            match *key {
+                #[cfg(feature = "333eo")]
+                "eo" => self.gen_eo(),
+                #[cfg(feature = "333dr")]
+                "dr" => self.gen_dr(),
+                #[cfg(feature = "333htr")]
+                "htr" => self.gen_htr(),
+                #[cfg(feature = "333fr")]
+                "fr" => self.gen_fr(),
+                #[cfg(feature = "333fr")]
+                "frls" => self.gen_fr_leave_slice(),
+                #[cfg(feature = "333finish")]
+                "frfin" => self.gen_fr_finish(),
+                #[cfg(feature = "333finish")]
+                "htrfin" => self.gen_htr_finish(),
+                #[cfg(feature = "333finish")]
+                "htrlsfin" => self.gen_htr_leave_slice_finish(),
+                _ => {}
+            }
+        }
+    }
+
+    //Loads the table of the key unless it's already loaded. Tables that can't be loaded are generated by the caller
+    #[cfg(all(feature = "fs", not(target_arch = "wasm32")))]
+    fn load_missing(&mut self, key: &str) {
+        let store = &self.store;
+        match key {
+            #[cfg(feature = "333eo")]
+            "eo" => Self::load_into(store, key, &mut self.eo),
+            #[cfg(feature = "333dr")]
+            "dr" => Self::load_into(store, key, &mut self.dr),
+            #[cfg(feature = "333htr")]
+            "htr" => Self::load_into(store, key, &mut self.htr),
+            #[cfg(feature = "333fr")]
+            "fr" => Self::load_into(store, key, &mut self.fr),
+            #[cfg(feature = "333fr")]
+            "frls" => Self::load_into(store, key, &mut self.frls),
+            #[cfg(feature = "333finish")]
+            "frfin" => Self::load_into(store, key, &mut self.fr_finish),
+            #[cfg(feature = "333finish")]
+            "htrfin" => Self::load_into(store, key, &mut self.htr_finish),
+            #[cfg(feature = "333finish")]
+            "htrlsfin" => Self::load_into(store, key, &mut self.htr_ls_finish),
+            _ => {}
+        }
+    }
+
+    #[cfg(all(feature = "fs", not(target_arch = "wasm32")))]
+    fn load_into<T: LoadFromDisk>(store: &TableStore, key: &str, val: &mut Option<Arc<T>>) {
+        if val.is_some() {
+            return;
+        }
+        match Self::load_table(store, key) {
+            Ok(v) => {
+                *val = Some(Arc::new(v));
+                debug!("Loaded {key} table from disk");
+            },
+            Err(e) => {
+                info!("Unable to load {key} table from disk: {e}");
+            }
+        }
+    }
+
+    pub fn custom_table<T: Any>(&self, key: &str) -> Option<&T> {
+        self.custom.get(key).and_then(|table| table.downcast_ref())
+    }
+
+    //Same as custom_table, for steps that keep the table after the PruningTables333 is gone
+    pub fn shared_custom_table<T: Any + Send + Sync>(&self, key: &str) -> Option<Arc<T>> {
+        self.custom.get(key).cloned().and_then(|table| table.downcast().ok())
+    }
+
+    pub fn insert_custom_table<T: Any + Send + Sync>(&mut self, key: &str, table: T) {
+        self.custom.insert(key.to_string(), Arc::new(table));
+    }
+
+    pub fn has_custom_table(&self, key: &str) -> bool {
+        self.custom.contains_key(key)
+    }
+
+    #[cfg(feature = "fs")]
+    pub fn save(&self, key: &str) -> Result<(), Error> {
         match key {
             #[cfg(feature = "333eo")]
             "eo" => if let Some(tbl) = &self.eo {
-                tbl.save_to_disk("333", key)?
+                self.save_table(key, tbl.as_ref())?
             },
             #[cfg(feature = "333dr")]
             "dr" => if let Some(tbl) = &self.dr {
-                tbl.save_to_disk("333", key)?
+                self.save_table(key, tbl.as_ref())?
             },
             #[cfg(feature = "333htr")]
             "htr" => if let Some(tbl) = &self.htr {
-                tbl.save_to_disk("333", key)?;
+                self.save_table(key, tbl.as_ref())?;
             },
             #[cfg(feature = "333htr")]
             "htr-subset" => if let Some(tbl) = &self.htr_subset {
-                tbl.save_to_disk("333", format!("{key}").as_str())?;
+                self.save_table(key, tbl.as_ref())?;
             },
             #[cfg(feature = "333fr")]
             "fr" => if let Some(tbl) = &self.fr {
-                tbl.save_to_disk("333", key)?
+                self.save_table(key, tbl.as_ref())?
             },
             #[cfg(feature = "333fr")]
             "frls" => if let Some(tbl) = &self.frls {
-                tbl.save_to_disk("333", key)?
+                self.save_table(key, tbl.as_ref())?
             },
             #[cfg(feature = "333finish")]
             "frfin" => if let Some(tbl) = &self.fr_finish {
-                tbl.save_to_disk("333", key)?
+                self.save_table(key, tbl.as_ref())?
             },
             #[cfg(feature = "333finish")]
             "htrfin" => if let Some(tbl) = &self.htr_finish {
-                tbl.save_to_disk("333", key)?
+                self.save_table(key, tbl.as_ref())?
             },
             #[cfg(feature = "333finish")]
             "htrlsfin" => if let Some(tbl) = &self.htr_ls_finish {
-                tbl.save_to_disk("333", key)?
+                self.save_table(key, tbl.as_ref())?
             },
             _ => {}
         }
@@ -125,158 +413,226 @@ impl PruningTables333 {
     }
 
     #[cfg(feature = "fs")]
-    pub fn load(&mut self, key: &str) -> Result<(), String> {
+    pub fn load(&mut self, key: &str) -> Result<(), Error> {
         match key {
             #[cfg(feature = "333eo")]
-            "eo" => self.eo = Some(EOPruningTable::load_from_disk("333", key)?),
+            "eo" => self.eo = Some(Arc::new(Self::load_table(&self.store, key)?)),
             #[cfg(feature = "333dr")]
-            "dr" => self.dr = Some(DRPruningTable::load_from_disk("333", key)?),
+            "dr" => self.dr = Some(Arc::new(Self::load_table(&self.store, key)?)),
             #[cfg(feature = "333htr")]
             "htr" => {
-                self.htr = Some(HTRPruningTable::load_from_disk("333", key)?);
-                self.htr_subset = Some(HTRSubsetTable::load_from_disk("333", format!("{key}-subset").as_str())?)
+                self.htr = Some(Arc::new(Self::load_table(&self.store, key)?));
+                self.htr_subset = Some(Arc::new(Self::load_table(&self.store, &format!("{key}-subset"))?))
             },
             #[cfg(feature = "333fr")]
-            "fr" => self.fr = Some(FRPruningTable::load_from_disk("333", key)?),
+            "fr" => self.fr = Some(Arc::new(Self::load_table(&self.store, key)?)),
+            #[cfg(feature = "333fr")]
+            "frls" => self.frls = Some(Arc::new(Self::load_table(&self.store, key)?)),
+            #[cfg(feature = "333finish")]
+            "frfin" => self.fr_finish = Some(Arc::new(Self::load_table(&self.store, key)?)),
+            #[cfg(feature = "333finish")]
+            "htrfin" => self.htr_finish = Some(Arc::new(Self::load_table(&self.store, key)?)),
+            #[cfg(feature = "333finish")]
+            "htrlsfin" => self.htr_ls_finish = Some(Arc::new(Self::load_table(&self.store, key)?)),
+            _ => {}
+        }
+        Ok(())
+    }
+
+    //Loads a table from the contents of a table file, e.g. one that is embedded in the binary or cached by a browser.
+    //Unlike load, the htr key only loads the htr table and htr-subset has to be loaded separately
+    pub fn load_bytes(&mut self, key: &str, data: &[u8]) -> Result<(), Error> {
+        let id = Self::table_id(key).ok_or(Error::MissingTable(key.to_string()))?;
+        match key {
+            #[cfg(feature = "333eo")]
+            "eo" => self.eo = Some(Arc::new(LoadFromDisk::load_bytes(data, &id)?)),
+            #[cfg(feature = "333dr")]
+            "dr" => self.dr = Some(Arc::new(LoadFromDisk::load_bytes(data, &id)?)),
+            #[cfg(feature = "333htr")]
+            "htr" => self.htr = Some(Arc::new(LoadFromDisk::load_bytes(data, &id)?)),
+            #[cfg(feature = "333htr")]
+            "htr-subset" => self.htr_subset = Some(Arc::new(LoadFromDisk::load_bytes(data, &id)?)),
+            #[cfg(feature = "333fr")]
+            "fr" => self.fr = Some(Arc::new(LoadFromDisk::load_bytes(data, &id)?)),
             #[cfg(feature = "333fr")]
-            "frls" => self.frls = Some(FRLeaveSlicePruningTable::load_from_disk("333", key)?),
+            "frls" => self.frls = Some(Arc::new(LoadFromDisk::load_bytes(data, &id)?)),
+            #[cfg(feature = "333finish")]
+            "frfin" => self.fr_finish = Some(Arc::new(LoadFromDisk::load_bytes(data, &id)?)),
             #[cfg(feature = "333finish")]
-            "frfin" => self.fr_finish = Some(FRFinishPruningTable::load_from_disk("333", key)?),
+            "htrfin" => self.htr_finish = Some(Arc::new(LoadFromDisk::load_bytes(data, &id)?)),
             #[cfg(feature = "333finish")]
-            "htrfin" => self.htr_finish = Some(HTRFinishPruningTable::load_from_disk("333", key)?),
+            "htrlsfin" => self.htr_ls_finish = Some(Arc::new(LoadFromDisk::load_bytes(data, &id)?)),
             _ => {}
         }
         Ok(())
     }
 
+    //The contents of a table file for the table of the key, or None if the table isn't available. See load_bytes
+    pub fn to_bytes(&self, key: &str, compression: Compression) -> Option<Vec<u8>> {
+        let id = Self::table_id(key)?;
+        let table = self.table_data(key)?;
+        Some(table_header::serialize(&id, table.table_type(), compression, table.entries()))
+    }
+
+    //Size, memory use and depth distribution of the table of the key, or None if the table isn't available
+    pub fn stats(&self, key: &str) -> Option<TableStats> {
+        self.table_data(key).map(|table| table.stats())
+    }
+
+    fn table_data(&self, key: &str) -> Option<&dyn TableData> {
+        let table: &dyn TableData = match key {
+            #[cfg(feature = "333eo")]
+            "eo" => self.eo.as_deref()?,
+            #[cfg(feature = "333dr")]
+            "dr" => self.dr.as_deref()?,
+            #[cfg(feature = "333htr")]
+            "htr" => self.htr.as_deref()?,
+            #[cfg(feature = "333htr")]
+            "htr-subset" => self.htr_subset.as_deref()?,
+            #[cfg(feature = "333fr")]
+            "fr" => self.fr.as_deref()?,
+            #[cfg(feature = "333fr")]
+            "frls" => self.frls.as_deref()?,
+            #[cfg(feature = "333finish")]
+            "frfin" => self.fr_finish.as_deref()?,
+            #[cfg(feature = "333finish")]
+            "htrfin" => self.htr_finish.as_deref()?,
+            #[cfg(feature = "333finish")]
+            "htrlsfin" => self.htr_ls_finish.as_deref()?,
+            _ => return None,
+        };
+        Some(table)
+    }
+
     #[cfg(feature = "fs")]
-    pub fn load_and_gen_normal<const C_SIZE: usize, C: Coord<C_SIZE>>(key: &str, val: &mut Option<LookupTable<C_SIZE, C>>, gen_f: &dyn Fn() -> LookupTable<C_SIZE, C>, load_f: &dyn Fn() -> Result<LookupTable<C_SIZE, C>, String>) -> bool {
+    pub fn load_and_gen_normal<const C_SIZE: usize, C: Coord<C_SIZE>>(key: &str, val: &mut Option<Arc<LookupTable<C_SIZE, C>>>, gen_f: &dyn Fn() -> LookupTable<C_SIZE, C>, load_f: &dyn Fn() -> Result<LookupTable<C_SIZE, C>, Error>) -> bool {
         if val.is_none() {
             let res = load_f();
             match res {
                 Ok(v) => {
-                    *val = Some(v);
+                    *val = Some(Arc::new(v));
                     debug!("Loaded {key} table from disk");
                 },
-                Err(_) => {
-                    info!("{key} table not found on disk");
+                Err(e) => {
+                    info!("Unable to load {key} table from disk: {e}");
                 }
             }
         }
         if val.is_none() {
-            let table = gen_f();
-            *val = Some(table);
+            *val = Some(Arc::new(gen_f()));
             return true;
         }
         false
     }
 
     #[cfg(feature = "fs")]
-    pub fn load_and_gen_niss<const C_SIZE: usize, C: Coord<C_SIZE>>(key: &str, val: &mut Option<NissLookupTable<C_SIZE, C>>, gen_f: &dyn Fn() -> NissLookupTable<C_SIZE, C>, load_f: &dyn Fn() -> Result<NissLookupTable<C_SIZE, C>, String>) -> bool {
+    pub fn load_and_gen_niss<const C_SIZE: usize, C: Coord<C_SIZE>>(key: &str, val: &mut Option<Arc<NissLookupTable<C_SIZE, C>>>, gen_f: &dyn Fn() -> NissLookupTable<C_SIZE, C>, load_f: &dyn Fn() -> Result<NissLookupTable<C_SIZE, C>, Error>) -> bool {
         if val.is_none() {
             let res = load_f();
             match res {
                 Ok(v) => {
-                    *val = Some(v);
+                    *val = Some(Arc::new(v));
                     debug!("Loaded {key} table from disk");
                 },
-                Err(_) => {
-                    info!("Error loading {key} table from disk");
+                Err(e) => {
+                    info!("Unable to load {key} table from disk: {e}");
                 }
             }
         }
         if val.is_none() {
-            let table = gen_f();
-            *val = Some(table);
+            *val = Some(Arc::new(gen_f()));
             return true;
         }
         false
     }
 
     #[cfg(feature = "fs")]
-    pub fn load_and_save_normal<const C_SIZE: usize, C: Coord<C_SIZE>>(&mut self, key: &str, mut_f: &dyn Fn(&mut Self) -> &mut Option<LookupTable<C_SIZE, C>>, gen_f: &dyn Fn() -> LookupTable<C_SIZE, C>, load_f: &dyn Fn() -> Result<LookupTable<C_SIZE, C>, String>) -> bool {
+    pub fn load_and_save_normal<const C_SIZE: usize, C: Coord<C_SIZE>>(&mut self, key: &str, mut_f: &dyn Fn(&mut Self) -> &mut Option<Arc<LookupTable<C_SIZE, C>>>, gen_f: &dyn Fn() -> LookupTable<C_SIZE, C>, load_f: &dyn Fn() -> Result<LookupTable<C_SIZE, C>, Error>) -> bool {
         let should_save = Self::load_and_gen_normal(key, mut_f(self), gen_f, load_f);
         if should_save {
-            if let Err(e) = self.save(key) {
-                error!("Error saving {key} table to disk: {e}");
-            } else {
-                info!("Saved {key} table to disk");
-            }
+            self.save_and_log(key);
         }
         should_save
     }
 
     #[cfg(feature = "fs")]
-    pub fn load_and_save_niss<const C_SIZE: usize, C: Coord<C_SIZE>>(&mut self, key: &str, mut_f: &dyn Fn(&mut Self) -> &mut Option<NissLookupTable<C_SIZE, C>>, gen_f: &dyn Fn() -> NissLookupTable<C_SIZE, C>, load_f: &dyn Fn() -> Result<NissLookupTable<C_SIZE, C>, String>) -> bool {
+    pub fn load_and_save_niss<const C_SIZE: usize, C: Coord<C_SIZE>>(&mut self, key: &str, mut_f: &dyn Fn(&mut Self) -> &mut Option<Arc<NissLookupTable<C_SIZE, C>>>, gen_f: &dyn Fn() -> NissLookupTable<C_SIZE, C>, load_f: &dyn Fn() -> Result<NissLookupTable<C_SIZE, C>, Error>) -> bool {
         let should_save = Self::load_and_gen_niss(key, mut_f(self), gen_f, load_f);
         if should_save {
-            if let Err(e) = self.save(key) {
-                error!("Error saving {key} table to disk: {e}");
-            } else {
-                info!("Saved {key} table to disk");
-            }
+            self.save_and_log(key);
         }
         should_save
     }
 
     #[cfg(all(feature = "333eo", feature = "fs"))]
     pub fn gen_eo(&mut self) {
-        self.load_and_save_normal("eo", &|x|&mut x.eo, &gen_eo, &|| EOPruningTable::load_from_disk("333", "eo"));
+        let store = self.store.clone();
+        self.load_and_save_normal("eo", &|x|&mut x.eo, &gen_eo, &|| Self::load_table(&store, "eo"));
     }
 
     #[cfg(all(feature = "333eo", not(feature = "fs")))]
     pub fn gen_eo(&mut self) {
-        self.eo = Some(gen_eo());
+        if self.eo.is_none() {
+            self.eo = Some(Arc::new(gen_eo()));
+        }
     }
 
     #[cfg(feature = "333eo")]
-    pub fn eo(&self) -> Option<&EOPruningTable> {
+    pub fn eo(&self) -> Option<&Arc<EOPruningTable>> {
         self.eo.as_ref()
     }
 
     #[cfg(all(feature = "333dr", feature = "fs"))]
     pub fn gen_dr(&mut self) {
-        self.load_and_save_normal("dr", &|x|&mut x.dr, &gen_dr, &|| DRPruningTable::load_from_disk("333", "dr"));
+        let store = self.store.clone();
+        self.load_and_save_normal("dr", &|x|&mut x.dr, &gen_dr, &|| Self::load_table(&store, "dr"));
     }
 
     #[cfg(all(feature = "333dr", not(feature = "fs")))]
     pub fn gen_dr(&mut self) {
-        self.dr = Some(gen_dr());
+        if self.dr.is_none() {
+            self.dr = Some(Arc::new(gen_dr()));
+        }
     }
 
     #[cfg(feature = "333dr")]
-    pub fn dr(&self) -> Option<&DRPruningTable> {
+    pub fn dr(&self) -> Option<&Arc<DRPruningTable>> {
         self.dr.as_ref()
     }
 
     #[cfg(all(feature = "333htr", feature = "fs"))]
     pub fn gen_htr(&mut self) {
-        let new_table = self.load_and_save_niss("htr", &|x|&mut x.htr, &gen_htr, &|| HTRPruningTable::load_from_disk("333", "htr"));
+        let store = self.store.clone();
+        let new_table = self.load_and_save_niss("htr", &|x|&mut x.htr, &gen_htr, &|| Self::load_table(&store, "htr"));
+        self.gen_htr_subset(new_table);
+    }
+
+    //The subset table is derived from the htr table and also updates it, so both are regenerated if the htr table is new
+    #[cfg(feature = "333htr")]
+    fn gen_htr_subset(&mut self, new_table: bool) {
         if let Some(htr_table) = &mut self.htr {
-            if self.htr_subset.is_some() {
+            if self.htr_subset.is_some() && !new_table {
                 return;
             }
-            match HTRSubsetTable::load_from_disk("333", "htr-subset") {
-                Ok(v) => {
-                    self.htr_subset = Some(v);
-                    debug!("Loaded htr-subset table from disk");
-                },
-                Err(_) => {
-                    info!("htr-subset table not found on disk");
+            #[cfg(feature = "fs")]
+            if !new_table {
+                match Self::load_table(&self.store, "htr-subset") {
+                    Ok(v) => {
+                        self.htr_subset = Some(Arc::new(v));
+                        debug!("Loaded htr-subset table from disk");
+                    },
+                    Err(e) => {
+                        info!("Unable to load htr-subset table from disk: {e}");
+                    }
                 }
             }
             if new_table || self.htr_subset.is_none() {
-                let table = gen_htr_subsets(htr_table);
-                self.htr_subset = Some(table);
-                if let Err(e) = self.save("htr-subset") {
-                    error!("Error saving htr-subset table to disk: {e}");
-                } else {
-                    info!("Saved htr-subset table to disk");
-                }
-                if let Err(e) = self.save("htr") {
-                    error!("Error saving htr table to disk: {e}");
-                } else {
-                    info!("Saved htr table to disk");
+                let table = gen_htr_subsets(Arc::make_mut(htr_table));
+                self.htr_subset = Some(Arc::new(table));
+                #[cfg(feature = "fs")]
+                {
+                    self.save_and_log("htr-subset");
+                    self.save_and_log("htr");
                 }
             }
         }
@@ -284,96 +640,115 @@ impl PruningTables333 {
 
     #[cfg(all(feature = "333htr", not(feature = "fs")))]
     pub fn gen_htr(&mut self) {
-        self.htr = Some(gen_htr());
+        let new_table = self.htr.is_none();
+        if new_table {
+            self.htr = Some(Arc::new(gen_htr()));
+        }
+        self.gen_htr_subset(new_table);
     }
 
     #[cfg(feature = "333htr")]
-    pub fn htr(&self) -> Option<&HTRPruningTable> {
+    pub fn htr(&self) -> Option<&Arc<HTRPruningTable>> {
         self.htr.as_ref()
     }
 
     #[cfg(feature = "333htr")]
-    pub fn htr_subset(&self) -> Option<&HTRSubsetTable> {
+    pub fn htr_subset(&self) -> Option<&Arc<HTRSubsetTable>> {
         self.htr_subset.as_ref()
     }
 
     #[cfg(feature = "333htr")]
     pub fn htr_mut(&mut self) -> Option<&mut HTRPruningTable> {
-        self.htr.as_mut()
+        self.htr.as_mut().map(Arc::make_mut)
     }
 
     #[cfg(all(feature = "333fr", feature = "fs"))]
     pub fn gen_fr_leave_slice(&mut self) {
-        self.load_and_save_normal("frls", &|x|&mut x.frls, &gen_fr_leave_slice, &|| FRLeaveSlicePruningTable::load_from_disk("333", "frls"));
+        let store = self.store.clone();
+        self.load_and_save_normal("frls", &|x|&mut x.frls, &gen_fr_leave_slice, &|| Self::load_table(&store, "frls"));
     }
 
     #[cfg(all(feature = "333fr", not(feature = "fs")))]
     pub fn gen_fr_leave_slice(&mut self) {
-        self.frls = Some(gen_fr_leave_slice());
+        if self.frls.is_none() {
+            self.frls = Some(Arc::new(gen_fr_leave_slice()));
+        }
     }
 
     #[cfg(feature = "333fr")]
-    pub fn fr_leave_slice(&self) -> Option<&FRLeaveSlicePruningTable> {
+    pub fn fr_leave_slice(&self) -> Option<&Arc<FRLeaveSlicePruningTable>> {
         self.frls.as_ref()
     }
 
     #[cfg(all(feature = "333fr", feature = "fs"))]
     pub fn gen_fr(&mut self) {
-        self.load_and_save_normal("fr", &|x|&mut x.fr, &gen_fr, &|| FRPruningTable::load_from_disk("333", "fr"));
+        let store = self.store.clone();
+        self.load_and_save_normal("fr", &|x|&mut x.fr, &gen_fr, &|| Self::load_table(&store, "fr"));
     }
 
     #[cfg(all(feature = "333fr", not(feature = "fs")))]
     pub fn gen_fr(&mut self) {
-        self.fr = Some(gen_fr());
+        if self.fr.is_none() {
+            self.fr = Some(Arc::new(gen_fr()));
+        }
     }
 
     #[cfg(feature = "333fr")]
-    pub fn fr(&self) -> Option<&FRPruningTable> {
+    pub fn fr(&self) -> Option<&Arc<FRPruningTable>> {
         self.fr.as_ref()
     }
 
     #[cfg(all(feature = "333finish", feature = "fs"))]
     pub fn gen_fr_finish(&mut self) {
-        self.load_and_save_normal("frfin", &|x|&mut x.fr_finish, &gen_fr_finish, &|| FRFinishPruningTable::load_from_disk("333", "frfin"));
+        let store = self.store.clone();
+        self.load_and_save_normal("frfin", &|x|&mut x.fr_finish, &gen_fr_finish, &|| Self::load_table(&store, "frfin"));
     }
 
     #[cfg(all(feature = "333finish", not(feature = "fs")))]
     pub fn gen_fr_finish(&mut self) {
-        self.fr_finish = Some(gen_fr_finish());
+        if self.fr_finish.is_none() {
+            self.fr_finish = Some(Arc::new(gen_fr_finish()));
+        }
     }
 
     #[cfg(feature = "333finish")]
-    pub fn fr_finish(&self) -> Option<&FRFinishPruningTable> {
+    pub fn fr_finish(&self) -> Option<&Arc<FRFinishPruningTable>> {
         self.fr_finish.as_ref()
     }
 
     #[cfg(all(feature = "333finish", feature = "fs"))]
     pub fn gen_htr_finish(&mut self) {
-        self.load_and_save_normal("htrfin", &|x|&mut x.htr_finish, &gen_htr_finish, &|| HTRFinishPruningTable::load_from_disk("333", "htrfin"));
+        let store = self.store.clone();
+        self.load_and_save_normal("htrfin", &|x|&mut x.htr_finish, &gen_htr_finish, &|| Self::load_table(&store, "htrfin"));
     }
 
     #[cfg(all(feature = "333finish", not(feature = "fs")))]
     pub fn gen_htr_finish(&mut self) {
-        self.htr_ls_finish = Some(crate::steps::tables::gen_htr_no_slice_finish());
+        if self.htr_finish.is_none() {
+            self.htr_finish = Some(Arc::new(gen_htr_finish()));
+        }
     }
 
     #[cfg(feature = "333finish")]
-    pub fn htr_finish(&self) -> Option<&HTRFinishPruningTable> {
+    pub fn htr_finish(&self) -> Option<&Arc<HTRFinishPruningTable>> {
         self.htr_finish.as_ref()
     }
 
     #[cfg(all(feature = "333finish", feature = "fs"))]
     pub fn gen_htr_leave_slice_finish(&mut self) {
-        self.load_and_save_normal("htrlsfin", &|x|&mut x.htr_ls_finish, &gen_htr_no_slice_finish, &|| HTRLeaveSliceFinishPruningTable::load_from_disk("333", "htrlsfin"));
+        let store = self.store.clone();
+        self.load_and_save_normal("htrlsfin", &|x|&mut x.htr_ls_finish, &gen_htr_no_slice_finish, &|| Self::load_table(&store, "htrlsfin"));
     }
 
     #[cfg(all(feature = "333finish", not(feature = "fs")))]
     pub fn gen_htr_leave_slice_finish(&mut self) {
-        self.htr_finish = Some(crate::steps::tables::gen_htr_finish());
+        if self.htr_ls_finish.is_none() {
+            self.htr_ls_finish = Some(Arc::new(gen_htr_no_slice_finish()));
+        }
     }
 
     #[cfg(feature = "333finish")]
-    pub fn htr_leave_slice_finish(&self) -> Option<&HTRLeaveSliceFinishPruningTable> {
+    pub fn htr_leave_slice_finish(&self) -> Option<&Arc<HTRLeaveSliceFinishPruningTable>> {
         self.htr_ls_finish.as_ref()
     }
 }
@@ -386,7 +761,6 @@ fn gen_eo() -> EOPruningTable {
     let table = lookup_table::generate(&EO_FB_MOVESET,
                                        &|c: &crate::cube::Cube333| EOCoordFB::from(c),
                                        &|| EOPruningTable::new(false),
-                                       &|table, coord|table.get(coord),
                                        &|table, coord, val|table.set(coord, val));
     #[cfg(not(target_arch = "wasm32"))]
     debug!("Took {}ms", time.elapsed().as_millis());
@@ -398,10 +772,11 @@ fn gen_dr() -> DRPruningTable {
     info!("Generating DR pruning table...");
     #[cfg(not(target_arch = "wasm32"))]
     let time = Instant::now();
-    let table = lookup_table::generate(&DR_UD_EO_FB_MOVESET,
-                                       &|c: &crate::cube::Cube333| DRUDEOFBCoord::from(c),
+    let table = lookup_table::generate_from_parts(&DR_UD_EO_FB_MOVESET,
+                                       (DRUDEOFBCoord::CORNERS_SIZE, &DRUDEOFBCoord::corners),
+                                       (DRUDEOFBCoord::EDGES_SIZE, &DRUDEOFBCoord::edges),
+                                       DRUDEOFBCoord::from_parts,
                                        &|| DRPruningTable::new(false),
-                                       &|table, coord|table.get(coord),
                                        &|table, coord, val|table.set(coord, val));
     #[cfg(not(target_arch = "wasm32"))]
     debug!("Took {}ms", time.elapsed().as_millis());
@@ -413,10 +788,11 @@ fn gen_htr() -> HTRPruningTable {
     info!("Generating HTR pruning table...");
     #[cfg(not(target_arch = "wasm32"))]
     let time = Instant::now();
-    let table = lookup_table::generate(&HTR_DR_UD_MOVESET,
-                                           &|c: &crate::cube::Cube333| HTRDRUDCoord::from(c),
+    let table = lookup_table::generate_from_parts(&HTR_DR_UD_MOVESET,
+                                           (HTRDRUDCoord::CORNERS_SIZE, &HTRDRUDCoord::corners),
+                                           (HTRDRUDCoord::EDGES_SIZE, &HTRDRUDCoord::edges),
+                                           HTRDRUDCoord::from_parts,
                                            &|| HTRPruningTable::new(),
-                                           &|table, coord|table.get(coord).0,
                                            &|table, coord, val|table.set(coord, val));
     #[cfg(not(target_arch = "wasm32"))]
     debug!("Took {}ms", time.elapsed().as_millis());
@@ -424,7 +800,6 @@ fn gen_htr() -> HTRPruningTable {
 }
 
 #[cfg(feature = "333htr")]
-#[cfg(feature = "fs")]
 fn gen_htr_subsets(htr_table: &mut HTRPruningTable) -> HTRSubsetTable {
     info!("Generating HTR subset table...");
     #[cfg(not(target_arch = "wasm32"))]
@@ -443,7 +818,6 @@ fn gen_fr_leave_slice() -> FRLeaveSlicePruningTable {
     let table = lookup_table::generate(&FR_UD_MOVESET,
                                        &|c: &crate::cube::Cube333| FRUDNoSliceCoord::from(c),
                                        &|| FRLeaveSlicePruningTable::new(false),
-                                       &|table, coord|table.get(coord),
                                        &|table, coord, val|table.set(coord, val));
     #[cfg(not(target_arch = "wasm32"))]
     debug!("Took {}ms", time.elapsed().as_millis());
@@ -458,7 +832,6 @@ fn gen_fr() -> FRPruningTable {
     let table = lookup_table::generate(&FR_UD_MOVESET,
                                        &|c: &crate::cube::Cube333| FRUDWithSliceCoord::from(c),
                                        &|| FRPruningTable::new(false),
-                                       &|table, coord|table.get(coord),
                                        &|table, coord, val|table.set(coord, val));
     #[cfg(not(target_arch = "wasm32"))]
     debug!("Took {}ms", time.elapsed().as_millis());
@@ -473,7 +846,6 @@ fn gen_fr_finish() -> FRFinishPruningTable {
     let table = lookup_table::generate(&FRUD_FINISH_MOVESET,
                                        &|c: &crate::cube::Cube333| FRUDFinishCoord::from(c),
                                        &|| FRFinishPruningTable::new(false),
-                                       &|table, coord|table.get(coord),
                                        &|table, coord, val|table.set(coord, val));
     #[cfg(not(target_arch = "wasm32"))]
     debug!("Took {}ms", time.elapsed().as_millis());
@@ -488,7 +860,6 @@ fn gen_htr_finish() -> HTRFinishPruningTable {
     let table = lookup_table::generate(&HTR_FINISH_MOVESET,
                                        &|c: &crate::cube::Cube333| HTRFinishCoord::from(c),
                                        &|| HTRFinishPruningTable::new(false),
-                                       &|table, coord|table.get(coord),
                                        &|table, coord, val|table.set(coord, val));
     #[cfg(not(target_arch = "wasm32"))]
     debug!("Took {}ms", time.elapsed().as_millis());
@@ -503,7 +874,6 @@ fn gen_htr_no_slice_finish() -> HTRLeaveSliceFinishPruningTable {
     let table = lookup_table::generate(&HTR_FINISH_MOVESET,
                                        &|c: &crate::cube::Cube333| HTRLeaveSliceFinishCoord::from(c),
                                        &|| HTRLeaveSliceFinishPruningTable::new(false),
-                                       &|table, coord|table.get(coord),
                                        &|table, coord, val|table.set(coord, val));
     #[cfg(not(target_arch = "wasm32"))]
     debug!("Took {}ms", time.elapsed().as_millis());
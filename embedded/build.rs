@@ -0,0 +1,19 @@
+use std::fs;
+use std::path::PathBuf;
+
+use cubelib::solver::table_header::Compression;
+use cubelib::steps::tables::PruningTables333;
+
+//Keep in sync with KEYS in src/lib.rs
+const KEYS: [&str; 6] = ["eo", "dr", "htr", "htr-subset", "fr", "frls"];
+
+fn main() {
+    println!("cargo:rerun-if-changed=build.rs");
+    let out_dir = PathBuf::from(std::env::var_os("OUT_DIR").unwrap());
+    let mut tables = PruningTables333::new();
+    tables.gen_concurrently(&["eo", "dr", "htr", "fr", "frls"]);
+    for key in KEYS {
+        let data = tables.to_bytes(key, Compression::Lz4).unwrap_or_else(|| panic!("The {key} table wasn't generated"));
+        fs::write(out_dir.join(format!("{key}.tbl")), data).unwrap();
+    }
+}
@@ -0,0 +1,35 @@
+use cubelib::steps::tables::PruningTables333;
+use cubelib::Error;
+
+//Pruning tables that are generated when this crate is built and compressed into the binary.
+//Loading them only takes a few milliseconds, which helps wherever tables can't be stored, like in the browser or in
+//short-lived processes. The finish tables are large and have to be generated or loaded from a store as usual.
+pub const KEYS: [&str; 6] = ["eo", "dr", "htr", "htr-subset", "fr", "frls"];
+
+const TABLES: [&[u8]; 6] = [
+    include_bytes!(concat!(env!("OUT_DIR"), "/eo.tbl")),
+    include_bytes!(concat!(env!("OUT_DIR"), "/dr.tbl")),
+    include_bytes!(concat!(env!("OUT_DIR"), "/htr.tbl")),
+    include_bytes!(concat!(env!("OUT_DIR"), "/htr-subset.tbl")),
+    include_bytes!(concat!(env!("OUT_DIR"), "/fr.tbl")),
+    include_bytes!(concat!(env!("OUT_DIR"), "/frls.tbl")),
+];
+
+//The table file of the key, see PruningTables333::load_bytes
+pub fn table(key: &str) -> Option<&'static [u8]> {
+    KEYS.iter().position(|k| *k == key).map(|i| TABLES[i])
+}
+
+//Loads every embedded table, replacing tables that are already loaded
+pub fn load(tables: &mut PruningTables333) -> Result<(), Error> {
+    for (key, data) in KEYS.iter().zip(TABLES) {
+        tables.load_bytes(key, data)?;
+    }
+    Ok(())
+}
+
+pub fn tables() -> PruningTables333 {
+    let mut tables = PruningTables333::new();
+    load(&mut tables).expect("Embedded tables are generated by the same version of cubelib");
+    tables
+}
@@ -78,6 +78,7 @@ pub mod backend {
             }
             prev_req.set(Some(req.clone()));
             if let Some(req) = req {
+                log::debug!("Requesting solution for {} with steps {}", req.scramble, cubelib::steps::dsl::format_steps(&req.steps));
                 req_id.update(|x| *x = *x + 1);
                 if req.scramble.is_empty() {
                     solution_data.set(SolutionState::NotFetched);
@@ -269,6 +270,7 @@ fn get_step_configs(eo: EOConfig, rzp: RZPConfig, dr: DRConfig, htr: HTRConfig,
             step_limit: None,
             quality: 10000,
             niss: Some(eo.niss.0.get()),
+            max_niss_switches: None,
             params: Default::default(),
         });
     }
@@ -288,6 +290,7 @@ fn get_step_configs(eo: EOConfig, rzp: RZPConfig, dr: DRConfig, htr: HTRConfig,
                 step_limit: None,
                 quality: 10000,
                 niss: Some(rzp.niss.0.get()),
+                max_niss_switches: None,
                 params: Default::default(),
             });
             params.insert("triggers".to_string(), dr.triggers.0.get().join(","));
@@ -301,6 +304,7 @@ fn get_step_configs(eo: EOConfig, rzp: RZPConfig, dr: DRConfig, htr: HTRConfig,
                 step_limit: None,
                 quality: 10000,
                 niss: Some(dr.niss.0.get()),
+                max_niss_switches: None,
                 params,
             });
         } else {
@@ -314,6 +318,7 @@ fn get_step_configs(eo: EOConfig, rzp: RZPConfig, dr: DRConfig, htr: HTRConfig,
                 step_limit: None,
                 quality: 10000,
                 niss: Some(dr.niss.0.get()),
+                max_niss_switches: None,
                 params,
             });
         }
@@ -329,6 +334,7 @@ fn get_step_configs(eo: EOConfig, rzp: RZPConfig, dr: DRConfig, htr: HTRConfig,
             step_limit: None,
             quality: 10000,
             niss: Some(htr.niss.0.get()),
+            max_niss_switches: None,
             params: Default::default(),
         });
     }
@@ -347,6 +353,7 @@ fn get_step_configs(eo: EOConfig, rzp: RZPConfig, dr: DRConfig, htr: HTRConfig,
             step_limit: None,
             quality: 10000,
             niss: Some(fr.niss.0.get()),
+            max_niss_switches: None,
             params: Default::default(),
         });
     }
@@ -365,6 +372,7 @@ fn get_step_configs(eo: EOConfig, rzp: RZPConfig, dr: DRConfig, htr: HTRConfig,
             step_limit: None,
             quality: 10000,
             niss: Some(NissSwitchType::Never),
+            max_niss_switches: None,
             params: Default::default(),
         });
     }
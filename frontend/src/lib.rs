@@ -1,20 +1,24 @@
 #[cfg(feature = "wasm_solver")]
 pub mod worker {
-    use cubelib::cubie::CubieCube;
-    use cubelib::lookup_table::TableSource;
-    use cubelib::solution::Solution;
-    use cubelib::solver::gen_tables;
+    use cubelib::cube::Cube333;
+    use cubelib::solver::df_search::CancelToken;
+    use cubelib::solver::solution::Solution;
+    use cubelib::solver::table_header::Compression;
+    use cubelib::steps::solver::{build_steps, gen_tables};
     use cubelib::steps::step::StepConfig;
-    use cubelib::tables::PruningTables;
+    use cubelib::steps::tables::PruningTables333;
     use gloo_worker::{HandlerId, Worker, WorkerScope};
     use leptos::spawn_local;
     use serde::{Deserialize, Serialize};
 
-    use idb::{Error, Factory, IndexParams, KeyPath, ObjectStore, ObjectStoreParams, TransactionMode};
+    use idb::{Factory, IndexParams, KeyPath, ObjectStore, ObjectStoreParams, TransactionMode};
     use wasm_bindgen::JsValue;
 
     use crate::worker::WorkerResponse::{InvalidStepConfig, NoSolution, Solved, UnknownError};
 
+    //Every table that can be cached in IndexedDB
+    const TABLE_KEYS: [&str; 9] = ["eo", "dr", "htr", "htr-subset", "fr", "frls", "frfin", "htrfin", "htrlsfin"];
+
     pub struct FMCSolver {
         cancel: bool,
     }
@@ -28,7 +32,7 @@ pub mod worker {
     }
 
     impl Worker for FMCSolver {
-        type Input = (CubieCube, Vec<StepConfig>);
+        type Input = (Cube333, Vec<StepConfig>);
         type Message = ();
         type Output = WorkerResponse;
 
@@ -48,9 +52,10 @@ pub mod worker {
             spawn_local(async move {
                 match load_pt(&steps_config).await {
                     Ok(tables) => {
-                        if let Some(steps) = cubelib::solver::build_steps(steps_config, &tables).ok() {
-                            let solution = cubelib::solver::solve_steps(cube, &steps).next()
-                                .map_or(NoSolution, |s| Solved(s));
+                        if let Ok(steps) = build_steps(steps_config, &tables) {
+                            let cancel_token = CancelToken::default();
+                            let solution = cubelib::solver::solve_steps(cube, &steps, &cancel_token).next()
+                                .map_or(NoSolution, Solved);
                             scope.respond(id, solution);
                         } else {
                             scope.respond(id, InvalidStepConfig);
@@ -72,77 +77,54 @@ pub mod worker {
         data: serde_bytes::ByteBuf,
     }
 
-    async fn load_pt(steps: &Vec<StepConfig>) -> Result<PruningTables, String> {
+    //Tables are taken from the binary (with the embedded_tables feature), then from IndexedDB.
+    //Everything else is generated and cached for the next time
+    async fn load_pt(steps: &Vec<StepConfig>) -> Result<PruningTables333, String> {
         let factory = Factory::new().map_err(|e| e.to_string())?;
-        let mut open_req = factory.open("maillard", Some(PruningTables::VERSION)).map_err(|e| e.to_string())?;
+        let mut open_req = factory.open("maillard", Some(PruningTables333::VERSION)).map_err(|e| e.to_string())?;
         open_req.on_upgrade_needed(|event| {
             let db = event.database().unwrap();
-            let mut params = ObjectStoreParams::new();
-            // params.key_path(Some(KeyPath::new_single("id")));
+            let params = ObjectStoreParams::new();
             let store = db.create_object_store("pruning_tables", params).unwrap();
 
             let mut index_params = IndexParams::new();
             index_params.unique(true);
             store.create_index("entry", KeyPath::new_single("id"), Some(index_params)).unwrap();
         });
-        let db = open_req.await.map_err(|e| e.to_string()).map_err(|e| e.to_string())?;
+        let db = open_req.await.map_err(|e| e.to_string())?;
         let transaction = db.transaction(&["pruning_tables"], TransactionMode::ReadOnly).map_err(|e| e.to_string())?;
         let store = transaction.object_store("pruning_tables").map_err(|e| e.to_string())?;
 
-        let mut pt = PruningTables::new();
+        let mut pt = PruningTables333::new();
+        let mut available: Vec<String> = vec![];
+        #[cfg(feature = "embedded_tables")]
+        {
+            cubelib_embedded::load(&mut pt).map_err(|e| e.to_string())?;
+            available.extend(cubelib_embedded::KEYS.iter().map(|key| key.to_string()));
+        }
         for val in store.get_all(None, None).await.map_err(|e| e.to_string())? {
             let entry: PtEntry = serde_wasm_bindgen::from_value(val).map_err(|e| e.to_string())?;
-            pt.load(entry.id.as_str(), entry.data.into_vec())
+            if available.contains(&entry.id) {
+                continue;
+            }
+            //Outdated or corrupt tables are regenerated and replaced
+            match pt.load_bytes(entry.id.as_str(), &entry.data) {
+                Ok(_) => available.push(entry.id),
+                Err(e) => log::warn!("Unable to load cached {} table: {e}", entry.id),
+            }
         }
         transaction.done().await.map_err(|e| e.to_string())?;
         gen_tables(steps, &mut pt);
         let transaction = db.transaction(&["pruning_tables"], TransactionMode::ReadWrite).map_err(|e| e.to_string())?;
         let store = transaction.object_store("pruning_tables").map_err(|e| e.to_string())?;
-        if let Some(eo) = pt.eo() {
-            if eo.get_source() == TableSource::Generated {
-                crate::worker::store(&store, &PtEntry {
-                    id: "eo".to_string(),
-                    data: serde_bytes::ByteBuf::from(eo.get_bytes())
-                }).await?;
+        for key in TABLE_KEYS {
+            if available.iter().any(|k| k == key) {
+                continue;
             }
-        }
-        if let Some(dr) = pt.dr() {
-            if dr.get_source() == TableSource::Generated {
-                crate::worker::store(&store, &PtEntry {
-                    id: "dr".to_string(),
-                    data: serde_bytes::ByteBuf::from(dr.get_bytes())
-                }).await?;
-            }
-        }
-        if let Some(htr) = pt.htr() {
-            if htr.get_source() == TableSource::Generated {
-                crate::worker::store(&store, &PtEntry {
-                    id: "htr".to_string(),
-                    data: serde_bytes::ByteBuf::from(htr.get_bytes())
-                }).await?;
-            }
-        }
-        if let Some(fr) = pt.fr() {
-            if fr.get_source() == TableSource::Generated {
-                crate::worker::store(&store, &PtEntry {
-                    id: "fr".to_string(),
-                    data: serde_bytes::ByteBuf::from(fr.get_bytes())
-                }).await?;
-            }
-        }
-        if let Some(frls) = pt.fr_leave_slice() {
-            if frls.get_source() == TableSource::Generated {
-                crate::worker::store(&store, &PtEntry {
-                    id: "frls".to_string(),
-                    data: serde_bytes::ByteBuf::from(frls.get_bytes())
-                }).await?;
-            }
-        }
-        if let Some(frfin) = pt.fr_finish() {
-            if frfin.get_source() == TableSource::Generated {
+            if let Some(data) = pt.to_bytes(key, Compression::Lz4) {
                 crate::worker::store(&store, &PtEntry {
-                    id: "frfin".to_string(),
-                    data: serde_bytes::ByteBuf::from(frfin.get_bytes())
+                    id: key.to_string(),
+                    data: serde_bytes::ByteBuf::from(data),
                 }).await?;
             }
         }
@@ -152,6 +134,6 @@ pub mod worker {
 
     async fn store(store: &ObjectStore, entry: &PtEntry) -> Result<(), String> {
         let val = serde_wasm_bindgen::to_value(entry).map_err(|e| e.to_string())?;
-        store.add(&val, Some(&JsValue::from(entry.id.clone()))).await.map_err(|e| format!("{} {e}", entry.id).to_string()).map(|_|())
+        store.put(&val, Some(&JsValue::from(entry.id.clone()))).await.map_err(|e| format!("{} {e}", entry.id)).map(|_|())
     }
 }